@@ -0,0 +1,53 @@
+use api_types::error::{ErrorCode, ErrorResponse};
+use std::fmt;
+
+#[derive(Debug)]
+pub enum ClientError {
+    /// The API rejected the request with problem details.
+    Api(Box<ErrorResponse>),
+    /// The request couldn't be sent or the response couldn't be read.
+    Http(reqwest::Error),
+    /// The API answered with something it doesn't document, e.g. a proxy's
+    /// error page.
+    UnexpectedResponse { status: u16, body: String },
+    /// The endpoint needs a session token and the client has none.
+    MissingToken,
+}
+
+impl ClientError {
+    /// The `code` of the problem details, if the API sent any.
+    pub fn code(&self) -> Option<ErrorCode> {
+        match self {
+            ClientError::Api(problem) => Some(problem.code),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for ClientError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClientError::Api(problem) => write!(f, "{} ({})", problem.detail, problem.code),
+            ClientError::Http(e) => write!(f, "Request failed: {}", e),
+            ClientError::UnexpectedResponse { status, body } => {
+                write!(f, "Unexpected response with status {}: {}", status, body)
+            }
+            ClientError::MissingToken => f.write_str("Not signed in"),
+        }
+    }
+}
+
+impl std::error::Error for ClientError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ClientError::Http(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<reqwest::Error> for ClientError {
+    fn from(e: reqwest::Error) -> Self {
+        ClientError::Http(e)
+    }
+}
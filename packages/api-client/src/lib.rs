@@ -0,0 +1,589 @@
+//! Typed client of the REST API, for native and wasm targets alike.
+//!
+//! ```no_run
+//! # async fn run() -> Result<(), api_client::ClientError> {
+//! use api_client::{ApiClient, PasswordSession};
+//! use api_types::auth::AuthenticateWithPasswordBody;
+//!
+//! let mut client = ApiClient::new("http://localhost:3000".parse().unwrap());
+//!
+//! let body = AuthenticateWithPasswordBody {
+//!     email: "jane@acme.com".into(),
+//!     password: "correct-horse-battery-staple".into(),
+//! };
+//! if let PasswordSession::Authenticated(session) =
+//!     client.authenticate_with_password(&body).await?
+//! {
+//!     client.set_token(Some(session.token));
+//! }
+//!
+//! let members = client.get_members("acme").await?;
+//! # Ok(())
+//! # }
+//! ```
+
+mod error;
+
+pub use error::ClientError;
+
+use api_types::{
+    auth::{
+        AuthenticateWithGithubBody, AuthenticateWithGithubResponse, AuthenticateWithPasskeyBody,
+        AuthenticateWithPasskeyResponse, AuthenticateWithPasswordBody,
+        AuthenticateWithPasswordResponse, AuthenticateWithTwoFactorBody,
+        AuthenticateWithTwoFactorResponse, ChangePasswordBody, ChangePasswordResponse,
+        ConfirmEmailChangeBody, ConfirmEmailChangeResponse, CreateAccountBody,
+        CreateAccountResponse, DeleteAccountBody, DeleteAccountResponse, DisableTwoFactorBody,
+        EnableTwoFactorBody, EnableTwoFactorResponse, GetAccountsResponse, GetPasskeysResponse,
+        LinkAccountBody, LinkAuthorizeUrlResponse, PasskeyAuthenticationOptionsResponse,
+        PasskeyRegistrationOptionsResponse, ProfileResponse, Provider, RegisterPasskeyBody,
+        RegisterPasskeyResponse, RequestEmailChangeBody, RequestPasswordRecoverBody,
+        RequestPasswordRecoverResponse, ResetPasswordRequest, SetupTwoFactorResponse,
+        TwoFactorChallengeResponse, UpdateProfileBody,
+    },
+    avatar::AvatarResponse,
+    error::ErrorResponse,
+    members::GetMembersResponse,
+    orgs::{AuditLogsQuery, GetAuditLogsResponse, UpdateTwoFactorPolicyBody},
+};
+use reqwest::{
+    header::ACCEPT_LANGUAGE,
+    multipart::{Form, Part},
+    Method, RequestBuilder, Response, StatusCode, Url,
+};
+use serde::de::DeserializeOwned;
+use uuid::Uuid;
+
+/// Version of the API the client speaks, the first segment of every path.
+pub const API_VERSION: &str = "v1";
+
+/// Outcome of signing in with a password.
+#[derive(Debug, Clone)]
+pub enum PasswordSession {
+    Authenticated(AuthenticateWithPasswordResponse),
+    /// Finish with [`ApiClient::authenticate_with_two_factor`].
+    TwoFactorRequired(TwoFactorChallengeResponse),
+}
+
+/// Outcome of signing in with GitHub.
+#[derive(Debug, Clone)]
+pub enum GithubSession {
+    Authenticated(AuthenticateWithGithubResponse),
+    /// Finish with [`ApiClient::authenticate_with_two_factor`].
+    TwoFactorRequired(TwoFactorChallengeResponse),
+}
+
+#[derive(Debug, Clone)]
+pub struct ApiClient {
+    http: reqwest::Client,
+    base_url: Url,
+    token: Option<String>,
+    language: Option<String>,
+}
+
+impl ApiClient {
+    /// `base_url` is where the server is mounted, without the version prefix.
+    pub fn new(base_url: Url) -> Self {
+        Self::with_http_client(reqwest::Client::new(), base_url)
+    }
+
+    /// Like [`Self::new`], reusing a configured `reqwest` client.
+    pub fn with_http_client(http: reqwest::Client, base_url: Url) -> Self {
+        ApiClient {
+            http,
+            base_url,
+            token: None,
+            language: None,
+        }
+    }
+
+    pub fn with_token(mut self, token: impl Into<String>) -> Self {
+        self.token = Some(token.into());
+        self
+    }
+
+    /// Sent as `Accept-Language`, so errors and e-mails come in that language.
+    pub fn with_language(mut self, language: impl Into<String>) -> Self {
+        self.language = Some(language.into());
+        self
+    }
+
+    /// Session token sent as `Authorization: Bearer`, e.g. after signing in.
+    pub fn set_token(&mut self, token: Option<String>) {
+        self.token = token;
+    }
+
+    pub fn token(&self) -> Option<&str> {
+        self.token.as_deref()
+    }
+
+    /// `base_url` followed by [`API_VERSION`] and `segments`, each one
+    /// percent-encoded.
+    fn url(&self, segments: &[&str]) -> Url {
+        let mut url = self.base_url.clone();
+        url.path_segments_mut()
+            .expect("base URL can't be a data: or mailto: URL")
+            .pop_if_empty()
+            .push(API_VERSION)
+            .extend(segments);
+        url
+    }
+
+    fn request(&self, method: Method, segments: &[&str]) -> RequestBuilder {
+        let request = self.http.request(method, self.url(segments));
+
+        match &self.language {
+            Some(language) => request.header(ACCEPT_LANGUAGE, language),
+            None => request,
+        }
+    }
+
+    fn authenticated(
+        &self,
+        method: Method,
+        segments: &[&str],
+    ) -> Result<RequestBuilder, ClientError> {
+        let token = self.token.as_deref().ok_or(ClientError::MissingToken)?;
+
+        Ok(self.request(method, segments).bearer_auth(token))
+    }
+
+    /// Sends the request, turning error statuses into [`ClientError`].
+    async fn send(request: RequestBuilder) -> Result<Response, ClientError> {
+        let response = request.send().await?;
+        let status = response.status();
+
+        if status.is_success() {
+            return Ok(response);
+        }
+
+        let body = response.text().await?;
+
+        Err(match serde_json::from_str::<ErrorResponse>(&body) {
+            Ok(problem) => ClientError::Api(Box::new(problem)),
+            Err(_) => ClientError::UnexpectedResponse {
+                status: status.as_u16(),
+                body,
+            },
+        })
+    }
+
+    async fn json<T: DeserializeOwned>(request: RequestBuilder) -> Result<T, ClientError> {
+        Ok(Self::send(request).await?.json().await?)
+    }
+
+    async fn empty(request: RequestBuilder) -> Result<(), ClientError> {
+        Self::send(request).await?;
+        Ok(())
+    }
+
+    fn avatar_form(image: Vec<u8>, file_name: &str) -> Form {
+        Form::new().part("file", Part::bytes(image).file_name(file_name.to_owned()))
+    }
+
+    pub async fn create_account(
+        &self,
+        body: &CreateAccountBody,
+    ) -> Result<CreateAccountResponse, ClientError> {
+        Self::json(self.request(Method::POST, &["users"]).json(body)).await
+    }
+
+    pub async fn authenticate_with_password(
+        &self,
+        body: &AuthenticateWithPasswordBody,
+    ) -> Result<PasswordSession, ClientError> {
+        let request = self
+            .request(Method::POST, &["sessions", "password"])
+            .json(body);
+        let response = Self::send(request).await?;
+
+        // 202 quer dizer que falta o segundo fator
+        if response.status() == StatusCode::ACCEPTED {
+            Ok(PasswordSession::TwoFactorRequired(response.json().await?))
+        } else {
+            Ok(PasswordSession::Authenticated(response.json().await?))
+        }
+    }
+
+    pub async fn authenticate_with_two_factor(
+        &self,
+        body: &AuthenticateWithTwoFactorBody,
+    ) -> Result<AuthenticateWithTwoFactorResponse, ClientError> {
+        let request = self
+            .request(Method::POST, &["sessions", "two-factor"])
+            .json(body);
+        Self::json(request).await
+    }
+
+    /// Where to send the browser to start signing in with GitHub.
+    pub fn github_authorize_url(&self) -> Url {
+        self.url(&["sessions", "github"])
+    }
+
+    pub async fn authenticate_with_github(
+        &self,
+        body: &AuthenticateWithGithubBody,
+    ) -> Result<GithubSession, ClientError> {
+        let request = self
+            .request(Method::POST, &["sessions", "github"])
+            .json(body);
+        let response = Self::send(request).await?;
+
+        if response.status() == StatusCode::ACCEPTED {
+            Ok(GithubSession::TwoFactorRequired(response.json().await?))
+        } else {
+            Ok(GithubSession::Authenticated(response.json().await?))
+        }
+    }
+
+    pub async fn get_passkey_authentication_options(
+        &self,
+    ) -> Result<PasskeyAuthenticationOptionsResponse, ClientError> {
+        Self::json(self.request(Method::POST, &["sessions", "passkey", "options"])).await
+    }
+
+    pub async fn authenticate_with_passkey(
+        &self,
+        body: &AuthenticateWithPasskeyBody,
+    ) -> Result<AuthenticateWithPasskeyResponse, ClientError> {
+        let request = self
+            .request(Method::POST, &["sessions", "passkey"])
+            .json(body);
+        Self::json(request).await
+    }
+
+    pub async fn request_password_recover(
+        &self,
+        body: &RequestPasswordRecoverBody,
+    ) -> Result<RequestPasswordRecoverResponse, ClientError> {
+        let request = self
+            .request(Method::POST, &["password", "recover"])
+            .json(body);
+        Self::json(request).await
+    }
+
+    pub async fn reset_password(&self, body: &ResetPasswordRequest) -> Result<(), ClientError> {
+        Self::empty(
+            self.request(Method::POST, &["password", "reset"])
+                .json(body),
+        )
+        .await
+    }
+
+    pub async fn get_profile(&self) -> Result<ProfileResponse, ClientError> {
+        Self::json(self.authenticated(Method::GET, &["profile"])?).await
+    }
+
+    pub async fn update_profile(&self, body: &UpdateProfileBody) -> Result<(), ClientError> {
+        Self::empty(self.authenticated(Method::PUT, &["profile"])?.json(body)).await
+    }
+
+    pub async fn delete_account(
+        &self,
+        body: &DeleteAccountBody,
+    ) -> Result<DeleteAccountResponse, ClientError> {
+        Self::json(self.authenticated(Method::DELETE, &["profile"])?.json(body)).await
+    }
+
+    /// Uploads a PNG, JPEG or WebP image as the user's avatar.
+    pub async fn update_avatar(
+        &self,
+        image: Vec<u8>,
+        file_name: &str,
+    ) -> Result<AvatarResponse, ClientError> {
+        let request = self
+            .authenticated(Method::PUT, &["profile", "avatar"])?
+            .multipart(Self::avatar_form(image, file_name));
+        Self::json(request).await
+    }
+
+    pub async fn change_password(
+        &self,
+        body: &ChangePasswordBody,
+    ) -> Result<ChangePasswordResponse, ClientError> {
+        let request = self
+            .authenticated(Method::PUT, &["profile", "password"])?
+            .json(body);
+        Self::json(request).await
+    }
+
+    pub async fn request_email_change(
+        &self,
+        body: &RequestEmailChangeBody,
+    ) -> Result<(), ClientError> {
+        let request = self
+            .authenticated(Method::POST, &["profile", "email"])?
+            .json(body);
+        Self::empty(request).await
+    }
+
+    pub async fn confirm_email_change(
+        &self,
+        body: &ConfirmEmailChangeBody,
+    ) -> Result<ConfirmEmailChangeResponse, ClientError> {
+        let request = self
+            .authenticated(Method::POST, &["profile", "email", "confirm"])?
+            .json(body);
+        Self::json(request).await
+    }
+
+    pub async fn get_accounts(&self) -> Result<GetAccountsResponse, ClientError> {
+        Self::json(self.authenticated(Method::GET, &["profile", "accounts"])?).await
+    }
+
+    /// Where to send the browser to link `provider` to the signed-in user.
+    pub async fn link_account_authorize_url(
+        &self,
+        provider: Provider,
+    ) -> Result<LinkAuthorizeUrlResponse, ClientError> {
+        let request = self.authenticated(
+            Method::GET,
+            &["profile", "accounts", provider.as_str(), "authorize"],
+        )?;
+        Self::json(request).await
+    }
+
+    pub async fn link_account(
+        &self,
+        provider: Provider,
+        body: &LinkAccountBody,
+    ) -> Result<(), ClientError> {
+        let request = self
+            .authenticated(Method::POST, &["profile", "accounts", provider.as_str()])?
+            .json(body);
+        Self::empty(request).await
+    }
+
+    pub async fn unlink_account(&self, provider: Provider) -> Result<(), ClientError> {
+        let request =
+            self.authenticated(Method::DELETE, &["profile", "accounts", provider.as_str()])?;
+        Self::empty(request).await
+    }
+
+    pub async fn setup_two_factor(&self) -> Result<SetupTwoFactorResponse, ClientError> {
+        Self::json(self.authenticated(Method::POST, &["profile", "two-factor"])?).await
+    }
+
+    pub async fn enable_two_factor(
+        &self,
+        body: &EnableTwoFactorBody,
+    ) -> Result<EnableTwoFactorResponse, ClientError> {
+        let request = self
+            .authenticated(Method::POST, &["profile", "two-factor", "verify"])?
+            .json(body);
+        Self::json(request).await
+    }
+
+    pub async fn disable_two_factor(&self, body: &DisableTwoFactorBody) -> Result<(), ClientError> {
+        let request = self
+            .authenticated(Method::DELETE, &["profile", "two-factor"])?
+            .json(body);
+        Self::empty(request).await
+    }
+
+    pub async fn get_passkeys(&self) -> Result<GetPasskeysResponse, ClientError> {
+        Self::json(self.authenticated(Method::GET, &["profile", "passkeys"])?).await
+    }
+
+    pub async fn get_passkey_registration_options(
+        &self,
+    ) -> Result<PasskeyRegistrationOptionsResponse, ClientError> {
+        let request = self.authenticated(Method::POST, &["profile", "passkeys", "options"])?;
+        Self::json(request).await
+    }
+
+    pub async fn register_passkey(
+        &self,
+        body: &RegisterPasskeyBody,
+    ) -> Result<RegisterPasskeyResponse, ClientError> {
+        let request = self
+            .authenticated(Method::POST, &["profile", "passkeys"])?
+            .json(body);
+        Self::json(request).await
+    }
+
+    pub async fn delete_passkey(&self, id: Uuid) -> Result<(), ClientError> {
+        let id = id.to_string();
+        Self::empty(self.authenticated(Method::DELETE, &["profile", "passkeys", &id])?).await
+    }
+
+    /// Starts an export of the user's data; the download link is e-mailed.
+    pub async fn request_data_export(&self) -> Result<(), ClientError> {
+        Self::empty(self.authenticated(Method::POST, &["profile", "export"])?).await
+    }
+
+    /// The zip of an export, by the code of its download link. Works once.
+    pub async fn download_data_export(&self, code: &str) -> Result<Vec<u8>, ClientError> {
+        let response = Self::send(self.request(Method::GET, &["profile", "export", code])).await?;
+        Ok(response.bytes().await?.to_vec())
+    }
+
+    pub async fn get_members(&self, slug: &str) -> Result<GetMembersResponse, ClientError> {
+        Self::json(self.authenticated(Method::GET, &["organizations", slug, "members"])?).await
+    }
+
+    pub async fn update_two_factor_policy(
+        &self,
+        slug: &str,
+        body: &UpdateTwoFactorPolicyBody,
+    ) -> Result<(), ClientError> {
+        let request = self
+            .authenticated(Method::PUT, &["organizations", slug, "two-factor"])?
+            .json(body);
+        Self::empty(request).await
+    }
+
+    pub async fn get_audit_logs(
+        &self,
+        slug: &str,
+        query: &AuditLogsQuery,
+    ) -> Result<GetAuditLogsResponse, ClientError> {
+        let request = self
+            .authenticated(Method::GET, &["organizations", slug, "audit-logs"])?
+            .query(query);
+        Self::json(request).await
+    }
+
+    pub async fn update_organization_avatar(
+        &self,
+        slug: &str,
+        image: Vec<u8>,
+        file_name: &str,
+    ) -> Result<AvatarResponse, ClientError> {
+        let request = self
+            .authenticated(Method::PUT, &["organizations", slug, "avatar"])?
+            .multipart(Self::avatar_form(image, file_name));
+        Self::json(request).await
+    }
+
+    pub async fn update_project_avatar(
+        &self,
+        slug: &str,
+        project_slug: &str,
+        image: Vec<u8>,
+        file_name: &str,
+    ) -> Result<AvatarResponse, ClientError> {
+        let segments = ["organizations", slug, "projects", project_slug, "avatar"];
+        let request = self
+            .authenticated(Method::PUT, &segments)?
+            .multipart(Self::avatar_form(image, file_name));
+        Self::json(request).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use api_types::error::{ErrorCode, PROBLEM_CONTENT_TYPE};
+    use axum::{
+        http::{header, HeaderMap},
+        response::IntoResponse,
+        routing::{get, post},
+        Json, Router,
+    };
+    use serde_json::json;
+
+    /// Serves `app` on a random port and returns a client pointing at it.
+    async fn serve(app: Router) -> ApiClient {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        ApiClient::new(format!("http://{}", addr).parse().unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_sends_token_and_decodes_response() {
+        let app = Router::new().route(
+            "/v1/organizations/{slug}/members",
+            get(|headers: HeaderMap| async move {
+                assert_eq!(headers[header::AUTHORIZATION], "Bearer secret");
+                assert_eq!(headers[header::ACCEPT_LANGUAGE], "pt-BR");
+                Json(json!({ "members": [] }))
+            }),
+        );
+        let client = serve(app).await;
+
+        assert!(matches!(
+            client.get_members("acme").await,
+            Err(ClientError::MissingToken)
+        ));
+
+        let client = client.with_token("secret").with_language("pt-BR");
+        let members = client.get_members("acme").await.unwrap();
+
+        assert!(members.members.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_maps_statuses_to_outcomes_and_problems() {
+        let app = Router::new().route(
+            "/v1/sessions/password",
+            post(
+                |Json(body): Json<AuthenticateWithPasswordBody>| async move {
+                    match body.password.as_str() {
+                        "two-factor" => (StatusCode::ACCEPTED, Json(json!({ "mfaToken": "mfa" })))
+                            .into_response(),
+                        "wrong" => (
+                            StatusCode::FORBIDDEN,
+                            [(header::CONTENT_TYPE, PROBLEM_CONTENT_TYPE)],
+                            Json(json!({
+                                "type": "/problems/forbidden",
+                                "title": "Forbidden",
+                                "status": 403,
+                                "detail": "Invalid credentials.",
+                                "code": "forbidden"
+                            })),
+                        )
+                            .into_response(),
+                        _ => (StatusCode::BAD_GATEWAY, "upstream down").into_response(),
+                    }
+                },
+            ),
+        );
+        let client = serve(app).await;
+        let login = |password: &str| AuthenticateWithPasswordBody {
+            email: "jane@acme.com".into(),
+            password: password.into(),
+        };
+
+        let session = client
+            .authenticate_with_password(&login("two-factor"))
+            .await
+            .unwrap();
+        assert!(matches!(
+            session,
+            PasswordSession::TwoFactorRequired(challenge) if challenge.mfa_token == "mfa"
+        ));
+
+        let error = client
+            .authenticate_with_password(&login("wrong"))
+            .await
+            .unwrap_err();
+        assert_eq!(error.code(), Some(ErrorCode::Forbidden));
+        assert_eq!(error.to_string(), "Invalid credentials. (forbidden)");
+
+        let error = client
+            .authenticate_with_password(&login("other"))
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            error,
+            ClientError::UnexpectedResponse { status: 502, .. }
+        ));
+    }
+
+    #[test]
+    fn test_url_encodes_segments() {
+        let client = ApiClient::new("http://localhost:3000/api/".parse().unwrap());
+
+        assert_eq!(
+            client.url(&["organizations", "a b/c", "members"]).as_str(),
+            "http://localhost:3000/api/v1/organizations/a%20b%2Fc/members"
+        );
+        assert_eq!(
+            client.github_authorize_url().as_str(),
+            "http://localhost:3000/api/v1/sessions/github"
+        );
+    }
+}
@@ -0,0 +1,292 @@
+//! Sessions, sign-in methods and the authenticated user's profile.
+
+use crate::webauthn::{
+    AuthenticationCredential, PublicKeyCredentialCreationOptions,
+    PublicKeyCredentialRequestOptions, RegistrationCredential,
+};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+use validator::Validate;
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema, Validate)]
+pub struct CreateAccountBody {
+    pub name: String,
+    #[validate(email)]
+    pub email: String,
+    pub password: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct CreateAccountResponse {
+    #[serde(rename = "userId")]
+    pub user_id: Uuid,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema, Validate)]
+pub struct AuthenticateWithPasswordBody {
+    #[validate(email)]
+    pub email: String,
+    #[validate(length(min = 6))]
+    pub password: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct AuthenticateWithPasswordResponse {
+    pub token: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct TwoFactorChallengeResponse {
+    #[serde(rename = "mfaToken")]
+    pub mfa_token: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema, Validate)]
+pub struct AuthenticateWithTwoFactorBody {
+    #[serde(rename = "mfaToken")]
+    pub mfa_token: String,
+    /// TOTP code or one of the recovery codes.
+    pub code: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct AuthenticateWithTwoFactorResponse {
+    pub token: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema, Validate)]
+pub struct AuthenticateWithGithubBody {
+    pub code: String,
+    /// The `state` GitHub sent back along with the code.
+    pub state: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct AuthenticateWithGithubResponse {
+    pub token: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct PasskeyAuthenticationOptionsResponse {
+    #[serde(rename = "challengeToken")]
+    pub challenge_token: String,
+    #[serde(rename = "publicKey")]
+    pub public_key: PublicKeyCredentialRequestOptions,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema, Validate)]
+pub struct AuthenticateWithPasskeyBody {
+    #[serde(rename = "challengeToken")]
+    pub challenge_token: String,
+    pub credential: AuthenticationCredential,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct AuthenticateWithPasskeyResponse {
+    pub token: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct ProfileResponse {
+    pub id: Uuid,
+    pub name: Option<String>,
+    pub email: String,
+    pub avatar_url: Option<String>,
+}
+
+/// Fields left out are kept as they are.
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema, Validate)]
+pub struct UpdateProfileBody {
+    #[validate(length(min = 1, max = 255))]
+    pub name: Option<String>,
+    #[serde(rename = "avatarUrl")]
+    #[validate(url)]
+    pub avatar_url: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct OrganizationTransfer {
+    /// Slug of an organization owned by the user.
+    pub organization: String,
+    /// Member who becomes the owner.
+    #[serde(rename = "newOwnerId")]
+    pub new_owner_id: Uuid,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema, Validate)]
+pub struct DeleteAccountBody {
+    /// Required when the user has a password. Otherwise the session must have
+    /// been started less than 10 minutes ago.
+    pub password: Option<String>,
+    /// Every owned organization must be handed over before deleting.
+    #[serde(default)]
+    pub transfers: Vec<OrganizationTransfer>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct DeleteAccountResponse {
+    /// The account can be restored by signing in before this date.
+    #[serde(rename = "deletionScheduledAt")]
+    pub deletion_scheduled_at: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Provider {
+    Github,
+    Google,
+    Passkey,
+}
+
+impl Provider {
+    /// The name used in JSON and in `/profile/accounts/{provider}`.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Provider::Github => "github",
+            Provider::Google => "google",
+            Provider::Passkey => "passkey",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct Account {
+    pub id: Uuid,
+    pub provider: Provider,
+    #[serde(rename = "providerAccountId")]
+    pub provider_account_id: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct GetAccountsResponse {
+    pub accounts: Vec<Account>,
+    #[serde(rename = "hasPassword")]
+    pub has_password: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema, Validate)]
+pub struct LinkAccountBody {
+    pub code: String,
+    /// The `state` GitHub sent back along with the code.
+    pub state: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct LinkAuthorizeUrlResponse {
+    /// Provider page to send the browser to.
+    pub url: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct SetupTwoFactorResponse {
+    pub secret: String,
+    #[serde(rename = "otpauthUri")]
+    pub otpauth_uri: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema, Validate)]
+pub struct EnableTwoFactorBody {
+    pub code: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct EnableTwoFactorResponse {
+    #[serde(rename = "recoveryCodes")]
+    pub recovery_codes: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema, Validate)]
+pub struct DisableTwoFactorBody {
+    pub code: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct Passkey {
+    pub id: Uuid,
+    pub name: Option<String>,
+    #[serde(rename = "createdAt")]
+    pub created_at: String,
+    #[serde(rename = "lastUsedAt")]
+    pub last_used_at: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct GetPasskeysResponse {
+    pub passkeys: Vec<Passkey>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct PasskeyRegistrationOptionsResponse {
+    #[serde(rename = "challengeToken")]
+    pub challenge_token: String,
+    #[serde(rename = "publicKey")]
+    pub public_key: PublicKeyCredentialCreationOptions,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema, Validate)]
+pub struct RegisterPasskeyBody {
+    #[serde(rename = "challengeToken")]
+    pub challenge_token: String,
+    pub name: Option<String>,
+    pub credential: RegistrationCredential,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct RegisterPasskeyResponse {
+    #[serde(rename = "passkeyId")]
+    pub passkey_id: Uuid,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema, Validate)]
+pub struct RequestPasswordRecoverBody {
+    #[validate(email)]
+    pub email: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct RequestPasswordRecoverResponse {
+    pub code: Uuid,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema, Validate)]
+pub struct ResetPasswordRequest {
+    pub code: Uuid,
+    pub password: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema, Validate)]
+pub struct ChangePasswordBody {
+    /// Required when the user already has a password.
+    #[serde(rename = "currentPassword")]
+    pub current_password: Option<String>,
+    #[serde(rename = "newPassword")]
+    pub new_password: String,
+}
+
+/// New session token, since every other session gets revoked.
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct ChangePasswordResponse {
+    pub token: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema, Validate)]
+pub struct RequestEmailChangeBody {
+    #[serde(rename = "newEmail")]
+    #[validate(email)]
+    pub new_email: String,
+    /// Required when the user has a password.
+    #[serde(rename = "currentPassword")]
+    pub current_password: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema, Validate)]
+pub struct ConfirmEmailChangeBody {
+    pub code: Uuid,
+}
+
+/// New session token, since every other session gets revoked.
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct ConfirmEmailChangeResponse {
+    pub token: String,
+}
@@ -0,0 +1,10 @@
+//! Avatar uploads of users, organizations and projects.
+
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct AvatarResponse {
+    #[serde(rename = "avatarUrl")]
+    pub avatar_url: String,
+}
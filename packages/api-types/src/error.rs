@@ -0,0 +1,114 @@
+//! Problem details (RFC 7807) sent by the API for every failed request.
+
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, fmt};
+use utoipa::ToSchema;
+
+pub const PROBLEM_CONTENT_TYPE: &str = "application/problem+json";
+
+/// Broken rules keyed by the JSON name of the field, e.g. `newPassword` or
+/// `items[0].name` for nested bodies.
+pub type FieldErrors = BTreeMap<String, Vec<ErrorDetail>>;
+
+/// Stable identifier of an error kind, sent as `code` and in the `type` of
+/// every problem. Unlike `title` and `detail`, it's never localized.
+///
+/// - `bad_request` (400): the request is malformed.
+/// - `validation_failed` (400): fields break their rules, listed in `errors`.
+/// - `unauthorized` (401): missing, invalid or revoked session.
+/// - `forbidden` (403)
+/// - `not_found` (404)
+/// - `conflict` (409): e.g. a unique field already in use, named in `errors`.
+/// - `unprocessable_entity` (422): e.g. a reference to a missing record.
+/// - `payload_too_large` (413)
+/// - `unsupported_media_type` (415)
+/// - `too_many_requests` (429): retry after the `Retry-After` seconds.
+/// - `database_error` (500)
+/// - `upstream_error` (502): a third-party service such as GitHub failed.
+/// - `internal_error` (500)
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    BadRequest,
+    ValidationFailed,
+    Unauthorized,
+    Forbidden,
+    NotFound,
+    Conflict,
+    UnprocessableEntity,
+    PayloadTooLarge,
+    UnsupportedMediaType,
+    TooManyRequests,
+    DatabaseError,
+    UpstreamError,
+    InternalError,
+}
+
+impl ErrorCode {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ErrorCode::BadRequest => "bad_request",
+            ErrorCode::ValidationFailed => "validation_failed",
+            ErrorCode::Unauthorized => "unauthorized",
+            ErrorCode::Forbidden => "forbidden",
+            ErrorCode::NotFound => "not_found",
+            ErrorCode::Conflict => "conflict",
+            ErrorCode::UnprocessableEntity => "unprocessable_entity",
+            ErrorCode::PayloadTooLarge => "payload_too_large",
+            ErrorCode::UnsupportedMediaType => "unsupported_media_type",
+            ErrorCode::TooManyRequests => "too_many_requests",
+            ErrorCode::DatabaseError => "database_error",
+            ErrorCode::UpstreamError => "upstream_error",
+            ErrorCode::InternalError => "internal_error",
+        }
+    }
+}
+
+impl fmt::Display for ErrorCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Problem details (RFC 7807) describing why a request failed.
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct ErrorResponse {
+    /// `/problems/<code>`, identifies the kind of problem.
+    #[serde(rename = "type")]
+    pub problem_type: String,
+    /// Summary of the kind of problem, localized.
+    pub title: String,
+    pub status: u16,
+    /// Explanation of this occurrence, meant for humans and localized.
+    pub detail: String,
+    /// Machine-readable error kind. Never changes for a given kind, unlike
+    /// `detail`.
+    pub code: ErrorCode,
+    /// Same as the `X-Request-Id` header, to find the request in the logs.
+    #[serde(rename = "requestId", default, skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+    /// Broken rules per field, for `validation_failed`, `conflict` and
+    /// `unprocessable_entity`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    #[schema(value_type = BTreeMap<String, Vec<ErrorDetail>>)]
+    pub errors: FieldErrors,
+    /// Causes that aren't about a single field.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub details: Vec<ErrorDetail>,
+}
+
+/// A single broken rule, for errors that can have several causes at once.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, ToSchema)]
+pub struct ErrorDetail {
+    pub rule: String,
+    pub message: String,
+}
+
+impl ErrorDetail {
+    pub fn new(rule: impl Into<String>, message: impl Into<String>) -> Self {
+        ErrorDetail {
+            rule: rule.into(),
+            message: message.into(),
+        }
+    }
+}
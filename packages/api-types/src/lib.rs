@@ -0,0 +1,11 @@
+//! Request and response bodies of the REST API, shared by the `server` that
+//! handles them and the `api-client` that sends them so both sides can't
+//! drift apart. Field names follow the JSON wire format through `serde`
+//! renames.
+
+pub mod auth;
+pub mod avatar;
+pub mod error;
+pub mod members;
+pub mod orgs;
+pub mod webauthn;
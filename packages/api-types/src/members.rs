@@ -0,0 +1,29 @@
+//! Organization members.
+
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, ToSchema)]
+pub enum Role {
+    Admin,
+    Member,
+    Billing,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct Member {
+    pub id: Uuid,
+    #[serde(rename = "userId")]
+    pub user_id: Uuid,
+    pub role: Role,
+    pub name: Option<String>,
+    pub email: String,
+    #[serde(rename = "avatarUrl")]
+    pub avatar_url: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct GetMembersResponse {
+    pub members: Vec<Member>,
+}
@@ -0,0 +1,66 @@
+//! Organization settings and audit log.
+
+use chrono::{DateTime, FixedOffset};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+use validator::Validate;
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema, Validate)]
+pub struct UpdateTwoFactorPolicyBody {
+    pub required: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, IntoParams, Validate)]
+#[into_params(parameter_in = Query)]
+pub struct AuditLogsQuery {
+    /// Only entries with this action, e.g. `member.role_updated`.
+    pub action: Option<String>,
+    #[serde(rename = "actorId")]
+    pub actor_id: Option<Uuid>,
+    /// Only entries about this kind of target, e.g. `organization`.
+    #[serde(rename = "targetType")]
+    pub target_type: Option<String>,
+    #[serde(rename = "targetId")]
+    pub target_id: Option<Uuid>,
+    /// Only entries at or after this RFC 3339 timestamp.
+    #[param(value_type = Option<String>, format = DateTime)]
+    pub from: Option<DateTime<FixedOffset>>,
+    /// Only entries before this RFC 3339 timestamp.
+    #[param(value_type = Option<String>, format = DateTime)]
+    pub to: Option<DateTime<FixedOffset>>,
+    /// Page size, 50 by default.
+    #[validate(range(min = 1, max = 100))]
+    pub limit: Option<u64>,
+    /// `nextCursor` of the previous page.
+    pub cursor: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct AuditLog {
+    pub id: Uuid,
+    #[serde(rename = "actorId")]
+    pub actor_id: Option<Uuid>,
+    pub action: String,
+    #[serde(rename = "targetType")]
+    pub target_type: Option<String>,
+    #[serde(rename = "targetId")]
+    pub target_id: Option<Uuid>,
+    pub before: Option<Value>,
+    pub after: Option<Value>,
+    pub ip: Option<String>,
+    #[serde(rename = "userAgent")]
+    pub user_agent: Option<String>,
+    #[serde(rename = "createdAt")]
+    pub created_at: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
+pub struct GetAuditLogsResponse {
+    #[serde(rename = "auditLogs")]
+    pub audit_logs: Vec<AuditLog>,
+    /// Pass as `cursor` to get the next page; absent on the last one.
+    #[serde(rename = "nextCursor")]
+    pub next_cursor: Option<String>,
+}
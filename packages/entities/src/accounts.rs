@@ -15,6 +15,7 @@ pub struct Model {
     #[sea_orm(unique)]
     pub provider_account_id: String,
     pub user_id: Uuid,
+    pub provider_login: Option<String>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -34,5 +35,3 @@ impl Related<super::users::Entity> for Entity {
         Relation::Users.def()
     }
 }
-
-impl ActiveModelBehavior for ActiveModel {}
@@ -0,0 +1,35 @@
+//! `ActiveModelBehavior` of the entities, kept out of the generated files.
+//!
+//! Ids are generated here rather than by the database, since SQLite has no
+//! `gen_random_uuid()`. After regenerating the entities, add `mod behaviors;`
+//! back to `lib.rs` and delete the empty `impl ActiveModelBehavior`
+//! sea-orm-codegen writes into each file.
+
+use sea_orm::{prelude::Uuid, ActiveModelBehavior, ActiveModelTrait, Set};
+
+macro_rules! uuid_primary_key {
+    ($($entity:ident),+ $(,)?) => {$(
+        impl ActiveModelBehavior for crate::$entity::ActiveModel {
+            fn new() -> Self {
+                Self {
+                    id: Set(Uuid::new_v4()),
+                    ..ActiveModelTrait::default()
+                }
+            }
+        }
+    )+};
+}
+
+uuid_primary_key!(
+    accounts,
+    audit_logs,
+    challenges,
+    invites,
+    members,
+    organizations,
+    passkeys,
+    projects,
+    recovery_codes,
+    tokens,
+    users,
+);
@@ -2,11 +2,17 @@
 
 pub mod prelude;
 
+mod behaviors;
+
 pub mod accounts;
+pub mod audit_logs;
+pub mod challenges;
 pub mod invites;
 pub mod members;
 pub mod organizations;
+pub mod passkeys;
 pub mod projects;
+pub mod recovery_codes;
 pub mod sea_orm_active_enums;
 pub mod tokens;
-pub mod users;
\ No newline at end of file
+pub mod users;
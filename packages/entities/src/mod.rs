@@ -3,10 +3,14 @@
 pub mod prelude;
 
 pub mod accounts;
+pub mod audit_logs;
+pub mod challenges;
 pub mod invites;
 pub mod members;
 pub mod organizations;
+pub mod passkeys;
 pub mod projects;
+pub mod recovery_codes;
 pub mod sea_orm_active_enums;
 pub mod tokens;
 pub mod users;
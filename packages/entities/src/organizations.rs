@@ -18,10 +18,13 @@ pub struct Model {
     pub should_attach_users_by_domain: bool,
     pub avatar_url: Option<String>,
     pub owner_id: Uuid,
+    pub require_two_factor: bool,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
+    #[sea_orm(has_many = "super::audit_logs::Entity")]
+    AuditLogs,
     #[sea_orm(has_many = "super::invites::Entity")]
     Invites,
     #[sea_orm(has_many = "super::members::Entity")]
@@ -38,6 +41,12 @@ pub enum Relation {
     Users,
 }
 
+impl Related<super::audit_logs::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::AuditLogs.def()
+    }
+}
+
 impl Related<super::invites::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::Invites.def()
@@ -61,5 +70,3 @@ impl Related<super::users::Entity> for Entity {
         Relation::Users.def()
     }
 }
-
-impl ActiveModelBehavior for ActiveModel {}
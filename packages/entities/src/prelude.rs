@@ -1,9 +1,13 @@
 //! `SeaORM` Entity, @generated by sea-orm-codegen 1.1.19
 
 pub use super::accounts::Entity as Accounts;
+pub use super::audit_logs::Entity as AuditLogs;
+pub use super::challenges::Entity as Challenges;
 pub use super::invites::Entity as Invites;
 pub use super::members::Entity as Members;
 pub use super::organizations::Entity as Organizations;
+pub use super::passkeys::Entity as Passkeys;
 pub use super::projects::Entity as Projects;
+pub use super::recovery_codes::Entity as RecoveryCodes;
 pub use super::tokens::Entity as Tokens;
 pub use super::users::Entity as Users;
@@ -10,6 +10,8 @@ pub enum AccountProvider {
     Github,
     #[sea_orm(string_value = "GOOGLE")]
     Google,
+    #[sea_orm(string_value = "PASSKEY")]
+    Passkey,
 }
 #[derive(Debug, Clone, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
 #[sea_orm(rs_type = "String", db_type = "Enum", enum_name = "role")]
@@ -26,4 +28,8 @@ pub enum Role {
 pub enum TokenType {
     #[sea_orm(string_value = "PASSWORD_RECOVER")]
     PasswordRecover,
+    #[sea_orm(string_value = "EMAIL_CHANGE")]
+    EmailChange,
+    #[sea_orm(string_value = "DATA_EXPORT")]
+    DataExport,
 }
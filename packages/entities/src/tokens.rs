@@ -12,6 +12,7 @@ pub struct Model {
     pub r#type: TokenType,
     pub created_at: DateTimeWithTimeZone,
     pub user_id: Uuid,
+    pub payload: Option<String>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -31,5 +32,3 @@ impl Related<super::users::Entity> for Entity {
         Relation::Users.def()
     }
 }
-
-impl ActiveModelBehavior for ActiveModel {}
@@ -15,20 +15,31 @@ pub struct Model {
     pub email: String,
     pub password_hash: Option<String>,
     pub avatar_url: Option<String>,
+    pub totp_secret: Option<String>,
+    pub totp_enabled: bool,
+    pub totp_last_step: Option<i64>,
+    pub session_version: i32,
+    pub deletion_scheduled_at: Option<DateTimeWithTimeZone>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
 pub enum Relation {
     #[sea_orm(has_many = "super::accounts::Entity")]
     Accounts,
+    #[sea_orm(has_many = "super::audit_logs::Entity")]
+    AuditLogs,
     #[sea_orm(has_many = "super::invites::Entity")]
     Invites,
     #[sea_orm(has_many = "super::members::Entity")]
     Members,
     #[sea_orm(has_many = "super::organizations::Entity")]
     Organizations,
+    #[sea_orm(has_many = "super::passkeys::Entity")]
+    Passkeys,
     #[sea_orm(has_many = "super::projects::Entity")]
     Projects,
+    #[sea_orm(has_many = "super::recovery_codes::Entity")]
+    RecoveryCodes,
     #[sea_orm(has_many = "super::tokens::Entity")]
     Tokens,
 }
@@ -39,6 +50,12 @@ impl Related<super::accounts::Entity> for Entity {
     }
 }
 
+impl Related<super::audit_logs::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::AuditLogs.def()
+    }
+}
+
 impl Related<super::invites::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::Invites.def()
@@ -57,16 +74,26 @@ impl Related<super::organizations::Entity> for Entity {
     }
 }
 
+impl Related<super::passkeys::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Passkeys.def()
+    }
+}
+
 impl Related<super::projects::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::Projects.def()
     }
 }
 
+impl Related<super::recovery_codes::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::RecoveryCodes.def()
+    }
+}
+
 impl Related<super::tokens::Entity> for Entity {
     fn to() -> RelationDef {
         Relation::Tokens.def()
     }
 }
-
-impl ActiveModelBehavior for ActiveModel {}
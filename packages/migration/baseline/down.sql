@@ -0,0 +1,10 @@
+DROP TABLE "invites"
+DROP TABLE "members"
+DROP TABLE "projects"
+DROP TABLE "organizations"
+DROP TABLE "accounts"
+DROP TABLE "tokens"
+DROP TABLE "users"
+DROP TYPE "role"
+DROP TYPE "account_provider"
+DROP TYPE "token_type"
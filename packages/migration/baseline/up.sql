@@ -0,0 +1,11 @@
+CREATE TYPE "token_type" AS ENUM ('PASSWORD_RECOVER')
+CREATE TYPE "account_provider" AS ENUM ('GITHUB', 'GOOGLE')
+CREATE TYPE "role" AS ENUM ('ADMIN', 'MEMBER', 'BILLING')
+CREATE TABLE IF NOT EXISTS "users" ( "created_at" timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP, "updated_at" timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP, "id" uuid NOT NULL PRIMARY KEY DEFAULT gen_random_uuid(), "name" varchar NULL, "email" varchar NOT NULL UNIQUE, "password_hash" varchar NULL, "avatar_url" varchar NULL )
+CREATE TABLE "tokens" ( "id" uuid NOT NULL PRIMARY KEY DEFAULT gen_random_uuid(), "type" token_type NOT NULL, "created_at" timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP, "user_id" uuid NOT NULL, FOREIGN KEY ("user_id") REFERENCES "users" ("id") ON DELETE CASCADE )
+CREATE TABLE IF NOT EXISTS "accounts" ( "created_at" timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP, "updated_at" timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP, "id" uuid NOT NULL PRIMARY KEY DEFAULT gen_random_uuid(), "provider" account_provider NOT NULL, "provider_account_id" varchar NOT NULL UNIQUE, "user_id" uuid NOT NULL, CONSTRAINT "accounts_provider_user_unique" UNIQUE ("provider", "user_id"), FOREIGN KEY ("user_id") REFERENCES "users" ("id") ON DELETE CASCADE )
+CREATE TABLE IF NOT EXISTS "organizations" ( "created_at" timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP, "updated_at" timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP, "id" uuid NOT NULL PRIMARY KEY DEFAULT gen_random_uuid(), "name" varchar NOT NULL, "slug" varchar NOT NULL UNIQUE, "domain" varchar NULL UNIQUE, "should_attach_users_by_domain" bool NOT NULL DEFAULT FALSE, "avatar_url" varchar NULL, "owner_id" uuid NOT NULL, FOREIGN KEY ("owner_id") REFERENCES "users" ("id") )
+CREATE TABLE IF NOT EXISTS "projects" ( "created_at" timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP, "updated_at" timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP, "id" uuid NOT NULL PRIMARY KEY DEFAULT gen_random_uuid(), "name" varchar NOT NULL, "description" varchar NOT NULL, "slug" varchar NOT NULL UNIQUE, "avatar_url" varchar NULL, "organization_id" uuid NOT NULL, "owner_id" uuid NOT NULL, FOREIGN KEY ("organization_id") REFERENCES "organizations" ("id") ON DELETE CASCADE, FOREIGN KEY ("owner_id") REFERENCES "users" ("id") )
+CREATE TABLE IF NOT EXISTS "members" ( "created_at" timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP, "updated_at" timestamp NOT NULL DEFAULT CURRENT_TIMESTAMP, "id" uuid NOT NULL PRIMARY KEY DEFAULT gen_random_uuid(), "role" role NOT NULL DEFAULT 'MEMBER', "organization_id" uuid NOT NULL, "user_id" uuid NOT NULL, CONSTRAINT "members_org_user_unique" UNIQUE ("organization_id", "user_id"), FOREIGN KEY ("organization_id") REFERENCES "organizations" ("id") ON DELETE CASCADE, FOREIGN KEY ("user_id") REFERENCES "users" ("id") ON DELETE CASCADE )
+CREATE TABLE "invites" ( "id" uuid NOT NULL PRIMARY KEY DEFAULT gen_random_uuid(), "email" varchar NOT NULL, "role" role NOT NULL, "created_at" timestamp with time zone NOT NULL DEFAULT CURRENT_TIMESTAMP, "author_id" uuid NULL, "organization_id" uuid NOT NULL, CONSTRAINT "invites_email_org_unique" UNIQUE ("email", "organization_id"), FOREIGN KEY ("author_id") REFERENCES "users" ("id") ON DELETE SET NULL, FOREIGN KEY ("organization_id") REFERENCES "organizations" ("id") ON DELETE CASCADE )
+CREATE INDEX "invites_email_idx" ON "invites" ("email")
@@ -0,0 +1,159 @@
+//! Schema pieces that differ between Postgres and SQLite.
+//!
+//! Postgres keeps its native enum types and `gen_random_uuid()` defaults.
+//! SQLite has neither, so enums become strings restricted by a `CHECK` and
+//! the entities generate their ids themselves.
+
+use sea_orm::{ConnectionTrait, DbBackend, Statement, TransactionTrait};
+use sea_orm_migration::prelude::extension::postgres::Type;
+use sea_orm_migration::{prelude::*, schema::*};
+
+fn is_sqlite(manager: &SchemaManager) -> bool {
+    manager.get_database_backend() == DbBackend::Sqlite
+}
+
+/// UUID primary key, generated by the database where it can.
+pub fn uuid_pk<T: IntoIden>(manager: &SchemaManager, col: T) -> ColumnDef {
+    let mut def = pk_uuid(col);
+    if !is_sqlite(manager) {
+        def.default(Expr::cust("gen_random_uuid()"));
+    }
+    def
+}
+
+/// Column of the enum type `name`, or a string checked against `variants`.
+pub fn enum_column<T, N, S, V>(manager: &SchemaManager, col: T, name: N, variants: V) -> ColumnDef
+where
+    T: IntoIden,
+    N: IntoIden,
+    S: IntoIden,
+    V: IntoIterator<Item = S>,
+{
+    if !is_sqlite(manager) {
+        return enumeration(col, name, variants);
+    }
+
+    let col = col.into_iden();
+    let values: Vec<String> = variants
+        .into_iter()
+        .map(|variant| variant.into_iden().to_string())
+        .collect();
+    string(col.clone())
+        .check(Expr::col(col).is_in(values))
+        .take()
+}
+
+pub async fn create_enum<S, V>(
+    manager: &SchemaManager<'_>,
+    name: &str,
+    variants: V,
+) -> Result<(), DbErr>
+where
+    S: IntoIden,
+    V: IntoIterator<Item = S>,
+{
+    if is_sqlite(manager) {
+        return Ok(());
+    }
+
+    manager
+        .create_type(
+            Type::create()
+                .as_enum(Alias::new(name))
+                .values(variants)
+                .to_owned(),
+        )
+        .await
+}
+
+pub async fn drop_enum(manager: &SchemaManager<'_>, name: &str) -> Result<(), DbErr> {
+    if is_sqlite(manager) {
+        return Ok(());
+    }
+
+    manager
+        .drop_type(Type::drop().name(Alias::new(name)).to_owned())
+        .await
+}
+
+/// Adds `value` to the enum type `name`. SQLite has no enum types, so there
+/// the column's `CHECK` is widened by [`rebuild_table`] instead.
+pub async fn add_enum_value(
+    manager: &SchemaManager<'_>,
+    name: &str,
+    value: &str,
+) -> Result<(), DbErr> {
+    if is_sqlite(manager) {
+        return Ok(());
+    }
+
+    manager
+        .alter_type(
+            Type::alter()
+                .name(Alias::new(name))
+                .add_value(Alias::new(value))
+                .if_not_exists()
+                .to_owned(),
+        )
+        .await
+}
+
+/// Replaces a table with `create`, its full definition as of the calling
+/// migration, keeping the rows and indexes. Only on SQLite, which can't alter
+/// a constraint, following the steps its docs recommend.
+pub async fn rebuild_table(
+    manager: &SchemaManager<'_>,
+    mut create: TableCreateStatement,
+) -> Result<(), DbErr> {
+    if !is_sqlite(manager) {
+        return Ok(());
+    }
+
+    let table = match create.get_table_name() {
+        Some(TableRef::Table(table)) => table.clone(),
+        _ => return Err(DbErr::Migration("rebuilt table has no name".into())),
+    };
+    let new_table = Alias::new(format!("{}_new", table.to_string()));
+    let columns: Vec<Alias> = create
+        .get_columns()
+        .iter()
+        .map(|column| Alias::new(column.get_column_name()))
+        .collect();
+
+    let db = manager.get_connection();
+    let builder = db.get_database_backend();
+    let indexes = db
+        .query_all(Statement::from_sql_and_values(
+            DbBackend::Sqlite,
+            "SELECT sql FROM sqlite_master WHERE type = 'index' AND tbl_name = ? AND sql IS NOT NULL",
+            [table.to_string().into()],
+        ))
+        .await?;
+
+    let copy = Query::insert()
+        .into_table(new_table.clone())
+        .columns(columns.clone())
+        .select_from(
+            Query::select()
+                .columns(columns)
+                .from(table.clone())
+                .to_owned(),
+        )
+        .map_err(|e| DbErr::Migration(e.to_string()))?
+        .to_owned();
+
+    let txn = db.begin().await?;
+    txn.execute(builder.build(create.table(new_table.clone())))
+        .await?;
+    txn.execute(builder.build(&copy)).await?;
+    txn.execute(builder.build(Table::drop().table(table.clone())))
+        .await?;
+    txn.execute(builder.build(Table::rename().table(new_table, table)))
+        .await?;
+    // Os índices somem com a tabela antiga e voltam como estavam
+    for index in indexes {
+        let sql: String = index.try_get("", "sql")?;
+        txn.execute_unprepared(&sql).await?;
+    }
+    txn.commit().await
+}
@@ -1,11 +1,21 @@
 pub use sea_orm_migration::prelude::*;
 
+mod dialect;
 mod m20251229_041332_create_user_and_enums;
 mod m20251229_042509_create_auth_tables;
 mod m20251229_043950_create_organization_table;
 mod m20251229_044000_create_project_table;
 mod m20251229_044017_create_member_invite_table;
 mod m20251229_052055_seed_data;
+mod m20261018_000001_add_provider_login_to_accounts;
+mod m20261018_000002_create_two_factor_tables;
+mod m20261018_000003_create_passkeys_table;
+mod m20261018_000004_add_session_version_and_email_change;
+mod m20261018_000005_add_account_deletion_schedule;
+mod m20261018_000006_add_data_export_token_type;
+mod m20261018_000007_create_audit_logs_table;
+mod m20261018_000008_create_challenges_table;
+mod m20261018_000009_add_totp_last_step_to_users;
 
 pub struct Migrator;
 
@@ -18,7 +28,101 @@ impl MigratorTrait for Migrator {
             Box::new(m20251229_043950_create_organization_table::Migration),
             Box::new(m20251229_044000_create_project_table::Migration),
             Box::new(m20251229_044017_create_member_invite_table::Migration),
-            Box::new(m20251229_052055_seed_data::Migration),
+            Box::new(m20261018_000001_add_provider_login_to_accounts::Migration),
+            Box::new(m20261018_000002_create_two_factor_tables::Migration),
+            Box::new(m20261018_000003_create_passkeys_table::Migration),
+            Box::new(m20261018_000004_add_session_version_and_email_change::Migration),
+            Box::new(m20261018_000005_add_account_deletion_schedule::Migration),
+            Box::new(m20261018_000006_add_data_export_token_type::Migration),
+            Box::new(m20261018_000007_create_audit_logs_table::Migration),
+            Box::new(m20261018_000008_create_challenges_table::Migration),
+            Box::new(m20261018_000009_add_totp_last_step_to_users::Migration),
         ]
     }
 }
+
+/// Version [`Migrator`] recorded for the seed back when it was part of it.
+pub const LEGACY_SEED_VERSION: &str = "m20251229_052055_seed_data";
+
+/// Development data, kept out of [`Migrator`] because it wipes every user and
+/// organization. It inserts through the current entities, so it only runs on
+/// a database with every schema migration applied.
+pub struct Seeder;
+
+#[async_trait::async_trait]
+impl MigratorTrait for Seeder {
+    fn migrations() -> Vec<Box<dyn MigrationTrait>> {
+        vec![Box::new(m20251229_052055_seed_data::Migration)]
+    }
+
+    fn migration_table_name() -> DynIden {
+        Alias::new("seaql_seeds").into_iden()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sea_orm::{
+        Database, DatabaseBackend, DatabaseConnection, ProxyDatabaseTrait, ProxyExecResult,
+        ProxyRow, Statement,
+    };
+    use std::sync::{Arc, Mutex};
+
+    /// Migrations written before SQLite support, which must keep producing the
+    /// exact schema Postgres databases already have.
+    const BASELINE: usize = 5;
+
+    /// Stands in for Postgres, keeping the SQL it's sent.
+    #[derive(Debug, Default, Clone)]
+    struct Recorder(Arc<Mutex<Vec<String>>>);
+
+    #[async_trait::async_trait]
+    impl ProxyDatabaseTrait for Recorder {
+        async fn query(&self, statement: Statement) -> Result<Vec<ProxyRow>, DbErr> {
+            Err(DbErr::Custom(format!("unexpected query {}", statement)))
+        }
+
+        async fn execute(&self, statement: Statement) -> Result<ProxyExecResult, DbErr> {
+            self.0.lock().unwrap().push(statement.sql);
+            Ok(ProxyExecResult::default())
+        }
+    }
+
+    async fn postgres(recorder: &Recorder) -> DatabaseConnection {
+        Database::connect_proxy(
+            DatabaseBackend::Postgres,
+            Arc::new(Box::new(recorder.clone())),
+        )
+        .await
+        .unwrap()
+    }
+
+    fn lines(sql: &str) -> Vec<String> {
+        sql.lines().map(String::from).collect()
+    }
+
+    #[async_std::test]
+    async fn test_baseline_migrations_are_unchanged_on_postgres() {
+        let baseline = &Migrator::migrations()[..BASELINE];
+        let (up, down) = (Recorder::default(), Recorder::default());
+
+        let db = postgres(&up).await;
+        for migration in baseline {
+            migration.up(&SchemaManager::new(&db)).await.unwrap();
+        }
+        let db = postgres(&down).await;
+        for migration in baseline.iter().rev() {
+            migration.down(&SchemaManager::new(&db)).await.unwrap();
+        }
+
+        assert_eq!(
+            *up.0.lock().unwrap(),
+            lines(include_str!("../baseline/up.sql"))
+        );
+        assert_eq!(
+            *down.0.lock().unwrap(),
+            lines(include_str!("../baseline/down.sql"))
+        );
+    }
+}
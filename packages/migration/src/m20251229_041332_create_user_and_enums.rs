@@ -1,7 +1,8 @@
 use sea_orm::{EnumIter, Iterable};
-use sea_orm_migration::prelude::extension::postgres::Type;
 use sea_orm_migration::{prelude::*, schema::*};
 
+use crate::dialect::{create_enum, drop_enum, uuid_pk};
+
 #[derive(DeriveMigrationName)]
 pub struct Migration;
 
@@ -9,36 +10,15 @@ pub struct Migration;
 impl MigrationTrait for Migration {
     async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
         // 1. Criar Enums
-        manager
-            .create_type(
-                Type::create()
-                    .as_enum(Alias::new("token_type"))
-                    .values(TokenType::iter())
-                    .to_owned(),
-            )
-            .await?;
-        manager
-            .create_type(
-                Type::create()
-                    .as_enum(Alias::new("account_provider"))
-                    .values(AccountProvider::iter())
-                    .to_owned(),
-            )
-            .await?;
-        manager
-            .create_type(
-                Type::create()
-                    .as_enum(Alias::new("role"))
-                    .values(Role::iter())
-                    .to_owned(),
-            )
-            .await?;
+        create_enum(manager, "token_type", TokenType::iter()).await?;
+        create_enum(manager, "account_provider", AccountProvider::iter()).await?;
+        create_enum(manager, "role", Role::iter()).await?;
 
         // 2. Criar tabela Users
         manager
             .create_table(
                 table_auto(Users::Table)
-                    .col(pk_uuid(Users::Id).default(Expr::cust("gen_random_uuid()")))
+                    .col(uuid_pk(manager, Users::Id))
                     .col(string_null(Users::Name))
                     .col(string(Users::Email).unique_key())
                     .col(string_null(Users::PasswordHash))
@@ -52,16 +32,9 @@ impl MigrationTrait for Migration {
         manager
             .drop_table(Table::drop().table(Users::Table).to_owned())
             .await?;
-        manager
-            .drop_type(Type::drop().name(Alias::new("role")).to_owned())
-            .await?;
-        manager
-            .drop_type(Type::drop().name(Alias::new("account_provider")).to_owned())
-            .await?;
-        manager
-            .drop_type(Type::drop().name(Alias::new("token_type")).to_owned())
-            .await?;
-        Ok(())
+        drop_enum(manager, "role").await?;
+        drop_enum(manager, "account_provider").await?;
+        drop_enum(manager, "token_type").await
     }
 }
 
@@ -1,3 +1,4 @@
+use crate::dialect::{enum_column, uuid_pk};
 use crate::m20251229_041332_create_user_and_enums::{AccountProvider, TokenType, Users};
 use sea_orm::Iterable;
 use sea_orm_migration::{prelude::*, schema::*};
@@ -10,58 +11,12 @@ impl MigrationTrait for Migration {
     async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
         // Tabela Tokens
         manager
-            .create_table(
-                Table::create()
-                    .table(Tokens::Table)
-                    .col(pk_uuid(Tokens::Id).default(Expr::cust("gen_random_uuid()")))
-                    .col(enumeration(
-                        Tokens::Type,
-                        Alias::new("token_type"),
-                        TokenType::iter(),
-                    ))
-                    .col(
-                        timestamp_with_time_zone(Tokens::CreatedAt)
-                            .default(Expr::current_timestamp()),
-                    )
-                    .col(uuid(Tokens::UserId))
-                    .foreign_key(
-                        ForeignKey::create()
-                            .from(Tokens::Table, Tokens::UserId)
-                            .to(Users::Table, Users::Id)
-                            .on_delete(ForeignKeyAction::Cascade),
-                    )
-                    .to_owned(),
-            )
+            .create_table(tokens_table(manager, TokenType::iter()))
             .await?;
 
         // Tabela Accounts
         manager
-            .create_table(
-                table_auto(Accounts::Table)
-                    .col(pk_uuid(Accounts::Id).default(Expr::cust("gen_random_uuid()")))
-                    .col(enumeration(
-                        Accounts::Provider,
-                        Alias::new("account_provider"),
-                        AccountProvider::iter(),
-                    ))
-                    .col(string(Accounts::ProviderAccountId).unique_key())
-                    .col(uuid(Accounts::UserId))
-                    .foreign_key(
-                        ForeignKey::create()
-                            .from(Accounts::Table, Accounts::UserId)
-                            .to(Users::Table, Users::Id)
-                            .on_delete(ForeignKeyAction::Cascade),
-                    )
-                    .index(
-                        Index::create()
-                            .name("accounts_provider_user_unique")
-                            .table(Accounts::Table)
-                            .col(Accounts::Provider)
-                            .col(Accounts::UserId)
-                            .unique(),
-                    )
-                    .to_owned(),
-            )
+            .create_table(accounts_table(manager, AccountProvider::iter()))
             .await
     }
 
@@ -76,8 +31,68 @@ impl MigrationTrait for Migration {
     }
 }
 
+/// The `tokens` table as first created, with `types` allowed in `type`.
+pub fn tokens_table<S, V>(manager: &SchemaManager, types: V) -> TableCreateStatement
+where
+    S: IntoIden,
+    V: IntoIterator<Item = S>,
+{
+    Table::create()
+        .table(Tokens::Table)
+        .col(uuid_pk(manager, Tokens::Id))
+        .col(enum_column(
+            manager,
+            Tokens::Type,
+            Alias::new("token_type"),
+            types,
+        ))
+        .col(timestamp_with_time_zone(Tokens::CreatedAt).default(Expr::current_timestamp()))
+        .col(uuid(Tokens::UserId))
+        .foreign_key(
+            ForeignKey::create()
+                .from(Tokens::Table, Tokens::UserId)
+                .to(Users::Table, Users::Id)
+                .on_delete(ForeignKeyAction::Cascade),
+        )
+        .to_owned()
+}
+
+/// The `accounts` table as first created, with `providers` allowed in
+/// `provider`.
+pub fn accounts_table<S, V>(manager: &SchemaManager, providers: V) -> TableCreateStatement
+where
+    S: IntoIden,
+    V: IntoIterator<Item = S>,
+{
+    table_auto(Accounts::Table)
+        .col(uuid_pk(manager, Accounts::Id))
+        .col(enum_column(
+            manager,
+            Accounts::Provider,
+            Alias::new("account_provider"),
+            providers,
+        ))
+        .col(string(Accounts::ProviderAccountId).unique_key())
+        .col(uuid(Accounts::UserId))
+        .foreign_key(
+            ForeignKey::create()
+                .from(Accounts::Table, Accounts::UserId)
+                .to(Users::Table, Users::Id)
+                .on_delete(ForeignKeyAction::Cascade),
+        )
+        .index(
+            Index::create()
+                .name("accounts_provider_user_unique")
+                .table(Accounts::Table)
+                .col(Accounts::Provider)
+                .col(Accounts::UserId)
+                .unique(),
+        )
+        .to_owned()
+}
+
 #[derive(DeriveIden)]
-enum Tokens {
+pub enum Tokens {
     Table,
     Id,
     Type,
@@ -1,5 +1,6 @@
 use sea_orm_migration::{prelude::*, schema::*};
 
+use crate::dialect::uuid_pk;
 use crate::m20251229_041332_create_user_and_enums::Users;
 
 #[derive(DeriveMigrationName)]
@@ -11,7 +12,7 @@ impl MigrationTrait for Migration {
         manager
             .create_table(
                 table_auto(Organizations::Table)
-                    .col(pk_uuid(Organizations::Id).default(Expr::cust("gen_random_uuid()")))
+                    .col(uuid_pk(manager, Organizations::Id))
                     .col(string(Organizations::Name))
                     .col(string(Organizations::Slug).unique_key())
                     .col(string_null(Organizations::Domain).unique_key())
@@ -47,4 +48,3 @@ pub enum Organizations {
     AvatarUrl,
     OwnerId,
 }
-
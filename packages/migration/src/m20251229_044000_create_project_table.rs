@@ -1,7 +1,7 @@
 use sea_orm_migration::{prelude::*, schema::*};
 
 use crate::{
-    m20251229_041332_create_user_and_enums::Users,
+    dialect::uuid_pk, m20251229_041332_create_user_and_enums::Users,
     m20251229_043950_create_organization_table::Organizations,
 };
 
@@ -14,7 +14,7 @@ impl MigrationTrait for Migration {
         manager
             .create_table(
                 table_auto(Projects::Table)
-                    .col(pk_uuid(Projects::Id).default(Expr::cust("gen_random_uuid()")))
+                    .col(uuid_pk(manager, Projects::Id))
                     .col(string(Projects::Name))
                     .col(string(Projects::Description))
                     .col(string(Projects::Slug).unique_key())
@@ -2,6 +2,7 @@ use sea_orm::Iterable;
 use sea_orm_migration::{prelude::*, schema::*};
 
 use crate::{
+    dialect::{enum_column, uuid_pk},
     m20251229_041332_create_user_and_enums::{Role, Users},
     m20251229_043950_create_organization_table::Organizations,
 };
@@ -16,12 +17,9 @@ impl MigrationTrait for Migration {
         manager
             .create_table(
                 table_auto(Members::Table)
-                    .col(pk_uuid(Members::Id).default(Expr::cust("gen_random_uuid()")))
-                    // Role tem default(MEMBER). O helper 'enumeration' não aceita default facilmente, então usamos ColumnDef manual
+                    .col(uuid_pk(manager, Members::Id))
                     .col(
-                        ColumnDef::new(Members::Role)
-                            .custom(Alias::new("role"))
-                            .not_null()
+                        enum_column(manager, Members::Role, Alias::new("role"), Role::iter())
                             .default("MEMBER"),
                     )
                     .col(uuid(Members::OrganizationId))
@@ -56,9 +54,14 @@ impl MigrationTrait for Migration {
             .create_table(
                 Table::create()
                     .table(Invites::Table)
-                    .col(pk_uuid(Invites::Id).default(Expr::cust("gen_random_uuid()")))
+                    .col(uuid_pk(manager, Invites::Id))
                     .col(string(Invites::Email))
-                    .col(enumeration(Invites::Role, Alias::new("role"), Role::iter()))
+                    .col(enum_column(
+                        manager,
+                        Invites::Role,
+                        Alias::new("role"),
+                        Role::iter(),
+                    ))
                     .col(
                         timestamp_with_time_zone(Invites::CreatedAt)
                             .default(Expr::current_timestamp()),
@@ -132,5 +135,3 @@ enum Invites {
     AuthorId,
     OrganizationId,
 }
-
-
@@ -0,0 +1,109 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+use crate::{
+    dialect::uuid_pk, m20251229_041332_create_user_and_enums::Users,
+    m20251229_043950_create_organization_table::Organizations,
+};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Users::Table)
+                    .add_column(string_null(NewColumns::TotpSecret))
+                    .to_owned(),
+            )
+            .await?;
+        // SQLite só aceita uma alteração por ALTER TABLE
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Users::Table)
+                    .add_column(boolean(NewColumns::TotpEnabled).default(false))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Organizations::Table)
+                    .add_column(boolean(NewColumns::RequireTwoFactor).default(false))
+                    .to_owned(),
+            )
+            .await?;
+
+        // Tabela RecoveryCodes
+        manager
+            .create_table(
+                Table::create()
+                    .table(RecoveryCodes::Table)
+                    .col(uuid_pk(manager, RecoveryCodes::Id))
+                    .col(string(RecoveryCodes::CodeHash))
+                    .col(
+                        timestamp_with_time_zone(RecoveryCodes::CreatedAt)
+                            .default(Expr::current_timestamp()),
+                    )
+                    .col(uuid(RecoveryCodes::UserId))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(RecoveryCodes::Table, RecoveryCodes::UserId)
+                            .to(Users::Table, Users::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(RecoveryCodes::Table).to_owned())
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Organizations::Table)
+                    .drop_column(NewColumns::RequireTwoFactor)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Users::Table)
+                    .drop_column(NewColumns::TotpEnabled)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Users::Table)
+                    .drop_column(NewColumns::TotpSecret)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum NewColumns {
+    TotpSecret,
+    TotpEnabled,
+    RequireTwoFactor,
+}
+
+#[derive(DeriveIden)]
+enum RecoveryCodes {
+    Table,
+    Id,
+    CodeHash,
+    CreatedAt,
+    UserId,
+}
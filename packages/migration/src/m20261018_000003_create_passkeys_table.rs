@@ -0,0 +1,67 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+use crate::dialect::{add_enum_value, rebuild_table, uuid_pk};
+use crate::m20251229_041332_create_user_and_enums::Users;
+use crate::m20251229_042509_create_auth_tables::accounts_table;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        add_enum_value(manager, "account_provider", "PASSKEY").await?;
+        rebuild_table(
+            manager,
+            accounts_table(manager, ["GITHUB", "GOOGLE", "PASSKEY"].map(Alias::new))
+                .col(string_null(Accounts::ProviderLogin))
+                .to_owned(),
+        )
+        .await?;
+
+        // Tabela Passkeys
+        manager
+            .create_table(
+                table_auto(Passkeys::Table)
+                    .col(uuid_pk(manager, Passkeys::Id))
+                    .col(string(Passkeys::CredentialId).unique_key())
+                    .col(string(Passkeys::PublicKey))
+                    .col(big_integer(Passkeys::SignCount).default(0))
+                    .col(string_null(Passkeys::Name))
+                    .col(timestamp_with_time_zone_null(Passkeys::LastUsedAt))
+                    .col(uuid(Passkeys::UserId))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(Passkeys::Table, Passkeys::UserId)
+                            .to(Users::Table, Users::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Postgres não permite remover valores de um enum, então PASSKEY permanece
+        manager
+            .drop_table(Table::drop().table(Passkeys::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Accounts {
+    ProviderLogin,
+}
+
+#[derive(DeriveIden)]
+enum Passkeys {
+    Table,
+    Id,
+    CredentialId,
+    PublicKey,
+    SignCount,
+    Name,
+    LastUsedAt,
+    UserId,
+}
@@ -0,0 +1,68 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+use crate::dialect::{add_enum_value, rebuild_table};
+use crate::m20251229_041332_create_user_and_enums::Users;
+use crate::m20251229_042509_create_auth_tables::{tokens_table, Tokens};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        add_enum_value(manager, "token_type", "EMAIL_CHANGE").await?;
+        rebuild_table(
+            manager,
+            tokens_table(
+                manager,
+                ["PASSWORD_RECOVER", "EMAIL_CHANGE"].map(Alias::new),
+            ),
+        )
+        .await?;
+
+        // Incrementado para invalidar os JWTs já emitidos
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Users::Table)
+                    .add_column(integer(NewColumns::SessionVersion).default(0))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Tokens::Table)
+                    .add_column(string_null(NewColumns::Payload))
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Postgres não permite remover valores de um enum, então EMAIL_CHANGE permanece
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Tokens::Table)
+                    .drop_column(NewColumns::Payload)
+                    .to_owned(),
+            )
+            .await?;
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Users::Table)
+                    .drop_column(NewColumns::SessionVersion)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum NewColumns {
+    SessionVersion,
+    Payload,
+}
@@ -0,0 +1,34 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+use crate::dialect::{add_enum_value, rebuild_table};
+use crate::m20251229_042509_create_auth_tables::tokens_table;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        add_enum_value(manager, "token_type", "DATA_EXPORT").await?;
+        rebuild_table(
+            manager,
+            tokens_table(
+                manager,
+                ["PASSWORD_RECOVER", "EMAIL_CHANGE", "DATA_EXPORT"].map(Alias::new),
+            )
+            .col(string_null(Tokens::Payload))
+            .to_owned(),
+        )
+        .await
+    }
+
+    async fn down(&self, _manager: &SchemaManager) -> Result<(), DbErr> {
+        // Postgres não permite remover valores de um enum, então DATA_EXPORT permanece
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum Tokens {
+    Payload,
+}
@@ -0,0 +1,95 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+use crate::{
+    dialect::uuid_pk, m20251229_041332_create_user_and_enums::Users,
+    m20251229_043950_create_organization_table::Organizations,
+};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Tabela AuditLogs
+        manager
+            .create_table(
+                Table::create()
+                    .table(AuditLogs::Table)
+                    .col(uuid_pk(manager, AuditLogs::Id))
+                    // Continua nula depois que o autor é excluído, o registro fica
+                    .col(uuid_null(AuditLogs::ActorId))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(AuditLogs::Table, AuditLogs::ActorId)
+                            .to(Users::Table, Users::Id)
+                            .on_delete(ForeignKeyAction::SetNull),
+                    )
+                    .col(uuid_null(AuditLogs::OrganizationId))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .from(AuditLogs::Table, AuditLogs::OrganizationId)
+                            .to(Organizations::Table, Organizations::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .col(string(AuditLogs::Action))
+                    .col(string_null(AuditLogs::TargetType))
+                    .col(uuid_null(AuditLogs::TargetId))
+                    .col(json_binary_null(AuditLogs::Before))
+                    .col(json_binary_null(AuditLogs::After))
+                    .col(string_null(AuditLogs::Ip))
+                    .col(string_null(AuditLogs::UserAgent))
+                    .col(
+                        timestamp_with_time_zone(AuditLogs::CreatedAt)
+                            .default(Expr::current_timestamp()),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        // A listagem pagina por organização, do mais recente para o mais antigo
+        manager
+            .create_index(
+                Index::create()
+                    .name("audit_logs_org_created_idx")
+                    .table(AuditLogs::Table)
+                    .col(AuditLogs::OrganizationId)
+                    .col(AuditLogs::CreatedAt)
+                    .col(AuditLogs::Id)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("audit_logs_actor_idx")
+                    .table(AuditLogs::Table)
+                    .col(AuditLogs::ActorId)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(AuditLogs::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum AuditLogs {
+    Table,
+    Id,
+    ActorId,
+    OrganizationId,
+    Action,
+    TargetType,
+    TargetId,
+    Before,
+    After,
+    Ip,
+    UserAgent,
+    CreatedAt,
+}
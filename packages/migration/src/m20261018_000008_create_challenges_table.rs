@@ -0,0 +1,48 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+use crate::dialect::uuid_pk;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Tabela Challenges
+        manager
+            .create_table(
+                Table::create()
+                    .table(Challenges::Table)
+                    .col(uuid_pk(manager, Challenges::Id))
+                    .col(integer(Challenges::Attempts).default(0))
+                    .col(timestamp_with_time_zone(Challenges::ExpiresAt))
+                    .to_owned(),
+            )
+            .await?;
+
+        // A limpeza apaga pela expiração
+        manager
+            .create_index(
+                Index::create()
+                    .name("challenges_expires_at_idx")
+                    .table(Challenges::Table)
+                    .col(Challenges::ExpiresAt)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(Challenges::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Challenges {
+    Table,
+    Id,
+    Attempts,
+    ExpiresAt,
+}
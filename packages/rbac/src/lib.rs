@@ -74,10 +74,10 @@ impl Ability {
     }
 
     fn can_billing(&self, action: &Action, resource: &Resource) -> bool {
-        match (action, resource) {
-            (Action::Read, Resource::Organization(_)) => true,
-            _ => false,
-        }
+        matches!(
+            (action, resource),
+            (Action::Read, Resource::Organization(_))
+        )
     }
 }
 
@@ -105,6 +105,7 @@ mod tests {
             updated_at: Default::default(),
             domain: None,
             should_attach_users_by_domain: false,
+            require_two_factor: false,
             avatar_url: None,
         };
         let org_owned_by_admin = organizations::Model {
@@ -126,6 +127,7 @@ mod tests {
 
         assert!(ability.can(&Action::Manage, &Resource::All));
         assert!(ability.can(&Action::Read, &Resource::Subject("User"))); // Admin can read users
+        assert!(ability.can(&Action::Read, &Resource::Subject("AuditLog"))); // Admin can read the audit log
         assert!(ability.can(&Action::Create, &Resource::Subject("Project")));
         assert!(ability.can(&Action::Create, &Resource::Project(&project)));
 
@@ -174,6 +176,7 @@ mod tests {
             updated_at: Default::default(),
             domain: None,
             should_attach_users_by_domain: false,
+            require_two_factor: false,
             avatar_url: None,
         };
 
@@ -205,12 +208,18 @@ mod tests {
 
         assert!(ability.cannot(&Action::Manage, &Resource::All));
         assert!(ability.cannot(&Action::Read, &Resource::Subject("User"))); // Member cannot read users
+        assert!(ability.cannot(&Action::Read, &Resource::Subject("AuditLog"))); // Member cannot read the audit log
 
         let user_model = users::Model {
             id: member_id,
             email: "test@test.com".to_string(),
             name: None,
             password_hash: None,
+            totp_secret: None,
+            totp_enabled: false,
+            totp_last_step: None,
+            session_version: 0,
+            deletion_scheduled_at: None,
             avatar_url: None,
             created_at: Default::default(),
             updated_at: Default::default(),
@@ -259,6 +268,7 @@ mod tests {
             updated_at: Default::default(),
             domain: None,
             should_attach_users_by_domain: false,
+            require_two_factor: false,
             avatar_url: None,
         };
         let project = projects::Model {
@@ -278,5 +288,6 @@ mod tests {
         assert!(ability.cannot(&Action::Update, &Resource::Organization(&org)));
         assert!(ability.cannot(&Action::Manage, &Resource::All));
         assert!(ability.cannot(&Action::Create, &Resource::Project(&project)));
+        assert!(ability.cannot(&Action::Read, &Resource::Subject("AuditLog")));
     }
-}
\ No newline at end of file
+}
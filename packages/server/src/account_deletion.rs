@@ -0,0 +1,115 @@
+//! Accounts are deleted in two steps: `DELETE /profile` schedules the
+//! deletion and signs the user out everywhere, and this job hard-deletes the
+//! row once the grace period is over. Signing in before that cancels it.
+
+use crate::audit::{AuditContext, AuditEvent};
+use entities::{invites, organizations, projects, users};
+use sea_orm::{
+    sea_query::Expr, ColumnTrait, DatabaseConnection, DbErr, EntityTrait, PaginatorTrait,
+    QueryFilter, TransactionTrait,
+};
+use std::time::Duration;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+pub const DELETION_GRACE_DAYS: i64 = 30;
+const PURGE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Hands projects over to their organization owner, anonymizes authored
+/// invites and deletes the user. Memberships, accounts, tokens and passkeys
+/// go away with the row through `ON DELETE CASCADE`.
+pub async fn hard_delete_user(db: &DatabaseConnection, user_id: Uuid) -> Result<(), DbErr> {
+    db.transaction(|txn| {
+        Box::pin(async move {
+            let owned_organizations = organizations::Entity::find()
+                .filter(organizations::Column::OwnerId.eq(user_id))
+                .count(txn)
+                .await?;
+
+            if owned_organizations > 0 {
+                return Err(DbErr::Custom(format!(
+                    "User {} still owns {} organization(s)",
+                    user_id, owned_organizations
+                )));
+            }
+
+            let owned_projects = projects::Entity::find()
+                .filter(projects::Column::OwnerId.eq(user_id))
+                .find_also_related(organizations::Entity)
+                .all(txn)
+                .await?;
+
+            for (project, organization) in owned_projects {
+                let Some(organization) = organization else {
+                    continue;
+                };
+
+                projects::Entity::update_many()
+                    .col_expr(
+                        projects::Column::OwnerId,
+                        Expr::value(organization.owner_id),
+                    )
+                    .filter(projects::Column::Id.eq(project.id))
+                    .exec(txn)
+                    .await?;
+            }
+
+            invites::Entity::update_many()
+                .col_expr(invites::Column::AuthorId, Expr::value(Option::<Uuid>::None))
+                .filter(invites::Column::AuthorId.eq(user_id))
+                .exec(txn)
+                .await?;
+
+            users::Entity::delete_by_id(user_id).exec(txn).await?;
+
+            // Feita pelo job, sem autor nem requisição
+            AuditEvent::new("user.deleted")
+                .target("user", user_id)
+                .record(txn, &AuditContext::default())
+                .await?;
+
+            Ok(())
+        })
+    })
+    .await
+    .map_err(|e| match e {
+        sea_orm::TransactionError::Connection(e) => e,
+        sea_orm::TransactionError::Transaction(e) => e,
+    })
+}
+
+/// Hard-deletes every account whose grace period is over, returning how many
+/// were deleted.
+pub async fn purge_due_accounts(db: &DatabaseConnection) -> Result<usize, DbErr> {
+    let due = users::Entity::find()
+        .filter(Expr::col(users::Column::DeletionScheduledAt).lte(Expr::current_timestamp()))
+        .all(db)
+        .await?;
+
+    let mut deleted = 0;
+
+    for user in due {
+        match hard_delete_user(db, user.id).await {
+            Ok(()) => deleted += 1,
+            // Segue para os próximos; a conta é tentada de novo na próxima rodada
+            Err(e) => warn!("Couldn't delete account {}: {}", user.id, e),
+        }
+    }
+
+    Ok(deleted)
+}
+
+/// Runs [`purge_due_accounts`] every hour, forever.
+pub async fn run_purge_job(db: DatabaseConnection) {
+    let mut interval = tokio::time::interval(PURGE_INTERVAL);
+
+    loop {
+        interval.tick().await;
+
+        match purge_due_accounts(&db).await {
+            Ok(0) => {}
+            Ok(deleted) => info!("Deleted {} account(s) past their grace period", deleted),
+            Err(e) => error!("Account purge failed: {}", e),
+        }
+    }
+}
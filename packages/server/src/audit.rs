@@ -0,0 +1,188 @@
+//! Audit trail of security-relevant and RBAC-mutating actions. Handlers record
+//! an [`AuditEvent`] with the same connection (usually the transaction) as the
+//! change it describes, so the log never mentions a write that rolled back.
+
+use crate::AppState;
+use axum::{
+    extract::FromRequestParts,
+    http::{header, request::Parts},
+};
+use entities::audit_logs;
+use rbac::Action;
+use sea_orm::{ActiveModelTrait, ConnectionTrait, DbErr, NotSet, Set};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::convert::Infallible;
+use uuid::Uuid;
+
+/// Longer user agents are truncated.
+const MAX_USER_AGENT_CHARS: usize = 512;
+
+/// Where a request came from, as stored with its audit events.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AuditContext {
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+}
+
+impl FromRequestParts<AppState> for AuditContext {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        let ip = state
+            .rate_limiter
+            .client_ip(&parts.headers, &parts.extensions);
+
+        let user_agent = parts
+            .headers
+            .get(header::USER_AGENT)
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.chars().take(MAX_USER_AGENT_CHARS).collect());
+
+        Ok(AuditContext { ip, user_agent })
+    }
+}
+
+/// One entry of the audit log, e.g.
+/// `AuditEvent::new("passkey.deleted").actor(user_id).target("passkey", id)`.
+///
+/// Snapshots passed to [`Self::before`] and [`Self::after`] should hold only
+/// the fields the action is about, and never secrets or hashes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuditEvent {
+    pub action: &'static str,
+    pub actor_id: Option<Uuid>,
+    pub organization_id: Option<Uuid>,
+    pub target_type: Option<&'static str>,
+    pub target_id: Option<Uuid>,
+    pub before: Option<Value>,
+    pub after: Option<Value>,
+}
+
+impl AuditEvent {
+    pub fn new(action: &'static str) -> Self {
+        AuditEvent {
+            action,
+            actor_id: None,
+            organization_id: None,
+            target_type: None,
+            target_id: None,
+            before: None,
+            after: None,
+        }
+    }
+
+    /// An attempt the RBAC rules turned down, so probing shows up in the log.
+    pub fn permission_denied(
+        actor_id: Uuid,
+        organization_id: Uuid,
+        action: &Action,
+        subject: &'static str,
+    ) -> Self {
+        AuditEvent::new("permission.denied")
+            .actor(actor_id)
+            .organization(organization_id)
+            .after(json!({ "action": format!("{:?}", action), "subject": subject }))
+    }
+
+    pub fn actor(mut self, actor_id: Uuid) -> Self {
+        self.actor_id = Some(actor_id);
+        self
+    }
+
+    pub fn organization(mut self, organization_id: Uuid) -> Self {
+        self.organization_id = Some(organization_id);
+        self
+    }
+
+    pub fn target(mut self, target_type: &'static str, target_id: Uuid) -> Self {
+        self.target_type = Some(target_type);
+        self.target_id = Some(target_id);
+        self
+    }
+
+    pub fn before(mut self, snapshot: impl Serialize) -> Self {
+        self.before = serde_json::to_value(snapshot).ok();
+        self
+    }
+
+    pub fn after(mut self, snapshot: impl Serialize) -> Self {
+        self.after = serde_json::to_value(snapshot).ok();
+        self
+    }
+
+    /// Inserts the entry through `db`; pass the transaction of the change so
+    /// both commit or roll back together.
+    pub async fn record<C: ConnectionTrait>(
+        self,
+        db: &C,
+        context: &AuditContext,
+    ) -> Result<(), DbErr> {
+        audit_logs::ActiveModel {
+            id: Set(Uuid::new_v4()),
+            actor_id: Set(self.actor_id),
+            organization_id: Set(self.organization_id),
+            action: Set(self.action.to_owned()),
+            target_type: Set(self.target_type.map(str::to_owned)),
+            target_id: Set(self.target_id),
+            before: Set(self.before),
+            after: Set(self.after),
+            ip: Set(context.ip.clone()),
+            user_agent: Set(context.user_agent.clone()),
+            created_at: NotSet,
+        }
+        .insert(db)
+        .await?;
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_state;
+    use axum::{extract::ConnectInfo, http::Request};
+    use std::net::SocketAddr;
+
+    #[tokio::test]
+    async fn test_context_reads_ip_and_truncates_user_agent() {
+        let request = Request::builder()
+            .header(header::USER_AGENT, "a".repeat(MAX_USER_AGENT_CHARS + 10))
+            .extension(ConnectInfo("10.0.0.7:4000".parse::<SocketAddr>().unwrap()))
+            .body(())
+            .unwrap();
+        let (mut parts, _) = request.into_parts();
+
+        let context = AuditContext::from_request_parts(&mut parts, &test_state())
+            .await
+            .unwrap();
+
+        assert_eq!(context.ip.as_deref(), Some("10.0.0.7"));
+        assert_eq!(
+            context.user_agent.map(|agent| agent.len()),
+            Some(MAX_USER_AGENT_CHARS)
+        );
+    }
+
+    #[test]
+    fn test_event_builder_serializes_snapshots() {
+        let actor = Uuid::new_v4();
+        let organization = Uuid::new_v4();
+
+        let event = AuditEvent::new("organization.two_factor_policy_updated")
+            .actor(actor)
+            .organization(organization)
+            .target("organization", organization)
+            .before(json!({ "requireTwoFactor": false }))
+            .after(json!({ "requireTwoFactor": true }));
+
+        assert_eq!(event.actor_id, Some(actor));
+        assert_eq!(event.target_type, Some("organization"));
+        assert_eq!(event.before, Some(json!({ "requireTwoFactor": false })));
+        assert_eq!(event.after, Some(json!({ "requireTwoFactor": true })));
+    }
+}
@@ -1,71 +1,186 @@
-use crate::{error::ErrorResponse, AppState};
-use axum::{
-    http::StatusCode,
-    Json,
+use crate::{
+    audit::{AuditContext, AuditEvent},
+    error::AppError,
+    i18n::t,
+    AppState,
+};
+use axum::Json;
+use entities::{members, organizations, users};
+use jsonwebtoken::{
+    decode, encode, errors::ErrorKind, DecodingKey, EncodingKey, Header, Validation,
+};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DbErr, EntityTrait, QueryFilter, QuerySelect, Set,
+    TransactionTrait,
 };
-use entities::{members, organizations};
-use jsonwebtoken::{decode, errors::ErrorKind, DecodingKey, Validation};
-use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use std::str::FromStr;
-use tracing::error;
+use time::{Duration, OffsetDateTime};
 use uuid::Uuid;
 
+const SESSION_TTL_DAYS: i64 = 7;
+/// How recent the session must be for users without a password, who have
+/// nothing to re-enter, to make sensitive account changes.
+const REAUTH_WINDOW_SECS: i64 = 10 * 60;
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Claims {
     pub sub: String,
     pub exp: i64,
+    /// `users.session_version` at sign-in; bumping the column revokes the token.
+    #[serde(default)]
+    pub ver: i32,
+    #[serde(default)]
+    pub iat: i64,
+}
+
+/// Issues the session JWT returned by every sign-in method.
+pub fn encode_session_token(user: &users::Model, state: &AppState) -> Result<String, AppError> {
+    let now = OffsetDateTime::now_utc();
+    let exp = now + Duration::days(SESSION_TTL_DAYS);
+
+    let claims = Claims {
+        sub: user.id.to_string(),
+        exp: exp.unix_timestamp(),
+        ver: user.session_version,
+        iat: now.unix_timestamp(),
+    };
+
+    encode(
+        &Header::default(),
+        &claims,
+        &EncodingKey::from_secret(state.config.jwt_secret.as_ref()),
+    )
+    .map_err(|e| AppError::internal(format!("Failed to generate JWT: {}", e)))
 }
 
-pub fn get_current_user_id(
+/// Starts a session for a user who just proved their identity with `method`
+/// (`password`, `github`, ...). Signing in during the deletion grace period
+/// cancels the scheduled deletion.
+pub async fn create_session(
+    user: &users::Model,
+    method: &'static str,
+    audit: &AuditContext,
+    state: &AppState,
+) -> Result<String, AppError> {
+    let user_id = user.id;
+    let restore = user.deletion_scheduled_at.is_some().then(|| user.clone());
+    let audit = audit.clone();
+
+    state
+        .db
+        .transaction(|txn| {
+            Box::pin(async move {
+                if let Some(user) = restore {
+                    let mut restored: users::ActiveModel = user.into();
+                    restored.deletion_scheduled_at = Set(None);
+
+                    restored.update(txn).await?;
+
+                    AuditEvent::new("user.deletion_cancelled")
+                        .actor(user_id)
+                        .target("user", user_id)
+                        .record(txn, &audit)
+                        .await?;
+                }
+
+                AuditEvent::new("session.created")
+                    .actor(user_id)
+                    .target("user", user_id)
+                    .after(json!({ "method": method }))
+                    .record(txn, &audit)
+                    .await?;
+
+                Ok::<_, DbErr>(())
+            })
+        })
+        .await?;
+
+    encode_session_token(user, state)
+}
+
+/// Whether the session was started recently enough to stand in for
+/// re-authentication.
+pub fn is_recent_session(claims: &Claims) -> bool {
+    OffsetDateTime::now_utc().unix_timestamp() - claims.iat <= REAUTH_WINDOW_SECS
+}
+
+/// Decodes the session token, rejecting revoked ones.
+pub async fn get_current_session(
     token: &str,
     state: &AppState,
-) -> Result<Uuid, (StatusCode, Json<ErrorResponse>)> {
+) -> Result<(Uuid, Claims), AppError> {
     let token_data = decode::<Claims>(
         token,
-        &DecodingKey::from_secret(state.jwt_secret.as_ref()),
+        &DecodingKey::from_secret(state.config.jwt_secret.as_ref()),
         &Validation::default(),
     )
     .map_err(|err| {
-        error!("JWT decoding error: {}", err);
-        let msg = match err.kind() {
-            ErrorKind::ExpiredSignature => "Token has expired",
-            _ => "Invalid token",
+        let key = match err.kind() {
+            ErrorKind::ExpiredSignature => "auth.token_expired",
+            _ => "auth.invalid_token",
         };
-        ErrorResponse::new(StatusCode::UNAUTHORIZED, msg)
+        AppError::Unauthorized(t(key))
     })?;
 
-    Uuid::from_str(&token_data.claims.sub)
-        .map_err(|_| ErrorResponse::new(StatusCode::UNAUTHORIZED, "Invalid token subject"))
+    let user_id = Uuid::from_str(&token_data.claims.sub)
+        .map_err(|_| AppError::Unauthorized(t("auth.invalid_token_subject")))?;
+
+    let session_version = users::Entity::find_by_id(user_id)
+        .select_only()
+        .column(users::Column::SessionVersion)
+        .into_tuple::<i32>()
+        .one(&state.db)
+        .await?;
+
+    if session_version != Some(token_data.claims.ver) {
+        return Err(AppError::Unauthorized(t("auth.session_revoked")));
+    }
+
+    Ok((user_id, token_data.claims))
+}
+
+pub async fn get_current_user_id(token: &str, state: &AppState) -> Result<Uuid, AppError> {
+    get_current_session(token, state)
+        .await
+        .map(|(user_id, _)| user_id)
 }
 
 pub async fn get_user_membership(
     state: &AppState,
     slug: &str,
     token: &str,
-) -> Result<Json<serde_json::Value>, (StatusCode, Json<ErrorResponse>)> {
-    let user_id = get_current_user_id(token, state)?;
+) -> Result<Json<serde_json::Value>, AppError> {
+    let user_id = get_current_user_id(token, state).await?;
 
     let result = members::Entity::find()
         .filter(members::Column::UserId.eq(user_id))
         .find_also_related(organizations::Entity)
         .filter(organizations::Column::Slug.eq(slug))
         .one(&state.db)
-        .await
-        .map_err(|e| {
-            error!("Database error: {}", e);
-            ErrorResponse::internal_error()
-        })?;
+        .await?;
 
     match result {
-        Some((member, Some(organization))) => Ok(Json(json!({
-            "organization": organization,
-            "membership": member,
-        }))),
-        _ => Err(ErrorResponse::new(
-            StatusCode::FORBIDDEN,
-            "You're not a member of this organization.",
-        )),
+        Some((member, Some(organization))) => {
+            if organization.require_two_factor {
+                let two_factor_enabled = users::Entity::find_by_id(user_id)
+                    .one(&state.db)
+                    .await?
+                    .is_some_and(|user| user.totp_enabled);
+
+                if !two_factor_enabled {
+                    return Err(AppError::forbidden(t(
+                        "two_factor.required_by_organization",
+                    )));
+                }
+            }
+
+            Ok(Json(json!({
+                "organization": organization,
+                "membership": member,
+            })))
+        }
+        _ => Err(AppError::forbidden(t("organization.not_member"))),
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,200 @@
+//! Upload pipeline shared by user, organization and project avatars: reads
+//! the multipart file, validates it, crops it into fixed square thumbnails
+//! and stores them.
+
+use crate::{
+    error::AppError,
+    i18n::{t, t_args},
+    AppState,
+};
+use axum::{extract::Multipart, http::StatusCode};
+use image::{imageops::FilterType, ImageFormat, ImageReader, Limits};
+use std::io::Cursor;
+use time::OffsetDateTime;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+pub const MAX_AVATAR_BYTES: usize = 5 * 1024 * 1024;
+/// Thumbnail edges in pixels; the first one is the URL saved on the model.
+pub const THUMBNAIL_SIZES: [u32; 2] = [256, 64];
+const MAX_SOURCE_DIMENSION: u32 = 4096;
+const ALLOWED_FORMATS: [ImageFormat; 3] = [ImageFormat::Png, ImageFormat::Jpeg, ImageFormat::WebP];
+
+/// Multipart form of the avatar upload endpoints.
+#[derive(ToSchema)]
+#[allow(dead_code)]
+pub struct AvatarUpload {
+    /// PNG, JPEG or WebP image up to 5 MiB.
+    #[schema(value_type = String, format = Binary)]
+    file: Vec<u8>,
+}
+
+#[derive(Debug)]
+pub struct Thumbnail {
+    pub size: u32,
+    pub png: Vec<u8>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum AvatarOwner {
+    User(Uuid),
+    Organization(Uuid),
+    Project(Uuid),
+}
+
+impl AvatarOwner {
+    fn key_prefix(self) -> String {
+        match self {
+            AvatarOwner::User(id) => format!("avatars/users/{}", id),
+            AvatarOwner::Organization(id) => format!("avatars/organizations/{}", id),
+            AvatarOwner::Project(id) => format!("avatars/projects/{}", id),
+        }
+    }
+}
+
+/// Reads the `file` field of the form.
+async fn read_file(mut multipart: Multipart) -> Result<Vec<u8>, AppError> {
+    let multipart_error = |e: axum::extract::multipart::MultipartError| {
+        let message = t_args("avatar.invalid_upload", &[("reason", &e.body_text())]);
+        match e.status() {
+            StatusCode::PAYLOAD_TOO_LARGE => AppError::PayloadTooLarge(message),
+            _ => AppError::BadRequest(message),
+        }
+    };
+
+    while let Some(field) = multipart.next_field().await.map_err(multipart_error)? {
+        if field.name() != Some("file") {
+            continue;
+        }
+
+        let bytes = field.bytes().await.map_err(multipart_error)?;
+
+        if bytes.len() > MAX_AVATAR_BYTES {
+            return Err(AppError::PayloadTooLarge(t_args(
+                "avatar.too_large",
+                &[("max", &(MAX_AVATAR_BYTES / 1024 / 1024))],
+            )));
+        }
+
+        return Ok(bytes.to_vec());
+    }
+
+    Err(AppError::bad_request(t("avatar.missing_file")))
+}
+
+/// Decodes the image and renders every size of [`THUMBNAIL_SIZES`] as PNG.
+pub fn make_thumbnails(bytes: &[u8]) -> Result<Vec<Thumbnail>, AppError> {
+    let mut reader = ImageReader::new(Cursor::new(bytes))
+        .with_guessed_format()
+        .map_err(|_| AppError::bad_request(t("avatar.unreadable")))?;
+
+    // O tipo é detectado pelo conteúdo, não pelo Content-Type enviado
+    if !reader
+        .format()
+        .is_some_and(|format| ALLOWED_FORMATS.contains(&format))
+    {
+        return Err(AppError::UnsupportedMediaType(t("avatar.unsupported_type")));
+    }
+
+    let mut limits = Limits::default();
+    limits.max_image_width = Some(MAX_SOURCE_DIMENSION);
+    limits.max_image_height = Some(MAX_SOURCE_DIMENSION);
+    reader.limits(limits);
+
+    let image = reader
+        .decode()
+        .map_err(|e| AppError::bad_request(t_args("avatar.invalid_image", &[("reason", &e)])))?;
+
+    THUMBNAIL_SIZES
+        .iter()
+        .map(|&size| {
+            let mut png = Vec::new();
+            image
+                .resize_to_fill(size, size, FilterType::Lanczos3)
+                .write_to(&mut Cursor::new(&mut png), ImageFormat::Png)
+                .map_err(|e| AppError::internal(format!("Failed to encode thumbnail: {}", e)))?;
+            Ok(Thumbnail { size, png })
+        })
+        .collect()
+}
+
+/// Runs the whole pipeline and returns the URL to save as `avatar_url`.
+pub async fn upload_avatar(
+    state: &AppState,
+    owner: AvatarOwner,
+    multipart: Multipart,
+) -> Result<String, AppError> {
+    let bytes = read_file(multipart).await?;
+
+    let thumbnails = tokio::task::spawn_blocking(move || make_thumbnails(&bytes))
+        .await
+        .map_err(|e| AppError::internal(format!("Thumbnail task failed: {}", e)))??;
+
+    let prefix = owner.key_prefix();
+    let mut urls = Vec::new();
+
+    for Thumbnail { size, png } in thumbnails {
+        let key = format!("{}/{}.png", prefix, size);
+        let url = state
+            .storage
+            .put(&key, png, "image/png")
+            .await
+            .map_err(|e| AppError::internal(format!("Failed to store avatar {}: {}", key, e)))?;
+        urls.push(url);
+    }
+
+    // As chaves são fixas, então a versão evita servir a imagem antiga do cache
+    Ok(format!(
+        "{}?v={}",
+        urls[0],
+        OffsetDateTime::now_utc().unix_timestamp()
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgb, RgbImage};
+
+    fn encode(image: RgbImage, format: ImageFormat) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        image
+            .write_to(&mut Cursor::new(&mut bytes), format)
+            .unwrap();
+        bytes
+    }
+
+    #[test]
+    fn test_thumbnails_are_square_pngs() {
+        let source = encode(
+            RgbImage::from_pixel(640, 360, Rgb([200, 10, 10])),
+            ImageFormat::Jpeg,
+        );
+
+        let thumbnails = make_thumbnails(&source).unwrap();
+
+        assert_eq!(
+            thumbnails.iter().map(|t| t.size).collect::<Vec<_>>(),
+            THUMBNAIL_SIZES
+        );
+        for Thumbnail { size, png } in thumbnails {
+            let image = image::load_from_memory_with_format(&png, ImageFormat::Png).unwrap();
+            assert_eq!((image.width(), image.height()), (size, size));
+        }
+    }
+
+    #[test]
+    fn test_rejects_unsupported_and_oversized_images() {
+        let status = make_thumbnails(b"GIF89a not really a gif")
+            .unwrap_err()
+            .status();
+        assert_eq!(status, StatusCode::UNSUPPORTED_MEDIA_TYPE);
+
+        let status = make_thumbnails(b"plain text").unwrap_err().status();
+        assert_eq!(status, StatusCode::UNSUPPORTED_MEDIA_TYPE);
+
+        let huge = encode(RgbImage::new(MAX_SOURCE_DIMENSION + 1, 1), ImageFormat::Png);
+        let status = make_thumbnails(&huge).unwrap_err().status();
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+}
@@ -1,9 +1,15 @@
-use server::routes::doc::ApiDoc;
+use server::{routes::doc::openapi_json, versioning::ApiVersion};
 use std::fs;
-use utoipa::OpenApi;
 
+/// Writes the specification served at `/<version>/openapi.json` to
+/// `openapi/<version>.json` for every version, for tools that need them
+/// without a running server.
 fn main() {
-    let doc = ApiDoc::openapi().to_pretty_json().unwrap();
-    fs::write("openapi.json", doc).unwrap();
-    println!("✅ OpenAPI specification generated to openapi.json");
+    fs::create_dir_all("openapi").unwrap();
+
+    for version in ApiVersion::ALL {
+        let path = format!("openapi/{}.json", version.as_str());
+        fs::write(&path, openapi_json(version)).unwrap();
+        println!("✅ OpenAPI specification generated to {}", path);
+    }
 }
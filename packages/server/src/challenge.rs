@@ -0,0 +1,90 @@
+//! Server-side records of the single-use tokens handed out while signing in:
+//! the MFA token and the passkey challenges. Each token carries the id of its
+//! record as `jti`. A valid signature only proves the server issued the token,
+//! so the record is deleted on use to keep a captured token from being
+//! replayed before it expires.
+
+use chrono::Utc;
+use entities::challenges;
+use sea_orm::{
+    sea_query::Expr, ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, DbErr,
+    EntityTrait, QueryFilter, Set,
+};
+use std::time::Duration;
+use tracing::{error, info};
+use uuid::Uuid;
+
+const CLEANUP_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Records a challenge valid for `ttl`, returning its id.
+pub async fn issue<C: ConnectionTrait>(db: &C, ttl: Duration) -> Result<Uuid, DbErr> {
+    let ttl = chrono::Duration::from_std(ttl).map_err(|e| DbErr::Custom(e.to_string()))?;
+
+    let challenge = challenges::ActiveModel {
+        expires_at: Set((Utc::now() + ttl).fixed_offset()),
+        ..Default::default()
+    }
+    .insert(db)
+    .await?;
+
+    Ok(challenge.id)
+}
+
+/// Counts an attempt at the challenge `id`. Returns `false` when it's unknown,
+/// expired or already had `max_attempts`.
+pub async fn attempt<C: ConnectionTrait>(
+    db: &C,
+    id: Uuid,
+    max_attempts: i32,
+) -> Result<bool, DbErr> {
+    // Um único UPDATE condicional, para requisições simultâneas não passarem do limite
+    let counted = challenges::Entity::update_many()
+        .col_expr(
+            challenges::Column::Attempts,
+            Expr::col(challenges::Column::Attempts).add(1),
+        )
+        .filter(challenges::Column::Id.eq(id))
+        .filter(challenges::Column::Attempts.lt(max_attempts))
+        .filter(challenges::Column::ExpiresAt.gt(Utc::now().fixed_offset()))
+        .exec(db)
+        .await?;
+
+    Ok(counted.rows_affected == 1)
+}
+
+/// Uses up the challenge `id`. Returns `false` when it was already used or
+/// expired, so only one of several concurrent requests gets `true`.
+pub async fn consume<C: ConnectionTrait>(db: &C, id: Uuid) -> Result<bool, DbErr> {
+    let deleted = challenges::Entity::delete_many()
+        .filter(challenges::Column::Id.eq(id))
+        .filter(challenges::Column::ExpiresAt.gt(Utc::now().fixed_offset()))
+        .exec(db)
+        .await?;
+
+    Ok(deleted.rows_affected == 1)
+}
+
+/// Deletes the challenges that expired unused, returning how many.
+pub async fn purge_expired(db: &DatabaseConnection) -> Result<u64, DbErr> {
+    let deleted = challenges::Entity::delete_many()
+        .filter(challenges::Column::ExpiresAt.lte(Utc::now().fixed_offset()))
+        .exec(db)
+        .await?;
+
+    Ok(deleted.rows_affected)
+}
+
+/// Runs [`purge_expired`] every hour, forever.
+pub async fn run_cleanup_job(db: DatabaseConnection) {
+    let mut interval = tokio::time::interval(CLEANUP_INTERVAL);
+
+    loop {
+        interval.tick().await;
+
+        match purge_expired(&db).await {
+            Ok(0) => {}
+            Ok(deleted) => info!("Deleted {} expired challenge(s)", deleted),
+            Err(e) => error!("Challenge cleanup failed: {}", e),
+        }
+    }
+}
@@ -0,0 +1,757 @@
+//! Settings of the server, read once at startup.
+//!
+//! Each setting comes from its environment variable, else the TOML file at
+//! `CONFIG_FILE` (`config.toml` when it exists), else its default. The key
+//! `rate_limit.ip_max` in the file is `RATE_LIMIT_IP_MAX` in the environment.
+//! Loading checks every setting and reports all problems at once, so a bad
+//! deploy is fixed in one go instead of one panic at a time.
+
+use crate::{
+    password_policy::{BreachedPasswords, PasswordPolicy},
+    rate_limit::{Quota, RateLimitConfig},
+    webauthn::WebauthnConfig,
+};
+use reqwest::Url;
+use std::{
+    collections::HashSet,
+    fmt,
+    path::{Component, Path, PathBuf},
+    str::FromStr,
+    time::Duration,
+};
+use toml_edit::{DocumentMut, Item, Value};
+use validator::ValidateEmail;
+
+pub const DEFAULT_CONFIG_FILE: &str = "config.toml";
+
+#[derive(Clone)]
+pub struct Config {
+    pub port: u16,
+    pub database_url: String,
+    pub database: DatabaseConfig,
+    pub jwt_secret: String,
+    pub webauthn: WebauthnConfig,
+    pub password_policy: PasswordPolicy,
+    pub rate_limit: RateLimitConfig,
+    pub uploads: UploadsConfig,
+    pub exports: ExportsConfig,
+    /// GitHub login, disabled when absent.
+    pub github: Option<GithubConfig>,
+    pub mail: Option<MailConfig>,
+    /// Browser access from other origins, denied when absent.
+    pub cors: Option<CorsConfig>,
+}
+
+/// Connection pool and startup of the database connection.
+#[derive(Debug, Clone)]
+pub struct DatabaseConfig {
+    pub max_connections: u32,
+    pub min_connections: u32,
+    pub connect_timeout: Duration,
+    /// How long a request waits for a free connection.
+    pub acquire_timeout: Duration,
+    /// Idle connections above `min_connections` are closed after this.
+    pub idle_timeout: Duration,
+    /// Log every SQL statement.
+    pub log_statements: bool,
+    /// Tries before giving up at startup, waiting longer after each failure.
+    pub connect_attempts: u32,
+}
+
+#[derive(Debug, Clone)]
+pub struct UploadsConfig {
+    /// Served publicly under `/uploads`.
+    pub dir: PathBuf,
+    /// Where `dir` is reachable, e.g. `http://localhost:3000/uploads`.
+    pub public_url: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct ExportsConfig {
+    /// Must be outside `uploads.dir`, which is public.
+    pub dir: PathBuf,
+    pub download_url: String,
+}
+
+#[derive(Clone)]
+pub struct GithubConfig {
+    pub client_id: String,
+    pub client_secret: String,
+    pub oauth_redirect_url: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct MailConfig {
+    /// Sender of every e-mail.
+    pub from: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct CorsConfig {
+    /// Origins like `https://app.example.com`, without a path.
+    pub allowed_origins: Vec<String>,
+    pub allow_credentials: bool,
+    /// How long browsers may cache a preflight response.
+    pub max_age: Duration,
+}
+
+impl Default for Config {
+    /// Every default, with the required settings left empty.
+    fn default() -> Self {
+        let port = 3000;
+
+        Config {
+            port,
+            database_url: String::new(),
+            database: DatabaseConfig {
+                max_connections: 10,
+                min_connections: 1,
+                connect_timeout: Duration::from_secs(10),
+                acquire_timeout: Duration::from_secs(10),
+                idle_timeout: Duration::from_secs(10 * 60),
+                log_statements: false,
+                connect_attempts: 5,
+            },
+            jwt_secret: String::new(),
+            webauthn: WebauthnConfig {
+                rp_id: "localhost".into(),
+                rp_name: "Rust SaaS RBAC".into(),
+                origin: "http://localhost:8080".into(),
+            },
+            password_policy: PasswordPolicy::default(),
+            rate_limit: RateLimitConfig::default(),
+            uploads: UploadsConfig {
+                dir: "uploads".into(),
+                public_url: format!("http://localhost:{}/uploads", port),
+            },
+            exports: ExportsConfig {
+                dir: "exports".into(),
+                download_url: format!("http://localhost:{}/v1/profile/export", port),
+            },
+            github: None,
+            mail: None,
+            cors: None,
+        }
+    }
+}
+
+impl Config {
+    /// Reads the environment and the configuration file, if any.
+    pub fn load() -> Result<Self, ConfigErrors> {
+        let file = match std::env::var("CONFIG_FILE") {
+            Ok(path) => Some(
+                std::fs::read_to_string(&path)
+                    .map_err(|e| ConfigErrors::file(&path, e.to_string()))?,
+            ),
+            Err(_) => std::fs::read_to_string(DEFAULT_CONFIG_FILE).ok(),
+        };
+
+        Self::from_sources(file.as_deref(), |name| std::env::var(name).ok())
+    }
+
+    /// Builds the configuration from the text of a TOML file and a lookup of
+    /// environment variables.
+    pub fn from_sources(
+        file: Option<&str>,
+        env: impl Fn(&str) -> Option<String>,
+    ) -> Result<Self, ConfigErrors> {
+        let document = match file {
+            Some(text) => text
+                .parse::<DocumentMut>()
+                .map_err(|e| ConfigErrors::file("configuration file", e.to_string()))?,
+            None => DocumentMut::new(),
+        };
+
+        let mut source = Source {
+            document: &document,
+            env: &env,
+            read: HashSet::new(),
+            errors: Vec::new(),
+        };
+        let config = read_config(&mut source);
+        source.reject_unknown_keys();
+
+        if source.errors.is_empty() {
+            Ok(config)
+        } else {
+            Err(ConfigErrors(source.errors))
+        }
+    }
+}
+
+fn read_config(source: &mut Source) -> Config {
+    let defaults = Config::default();
+    let port = source.parse("port", defaults.port);
+    let database_url = source.required("database_url");
+    let jwt_secret = source.required("jwt_secret");
+
+    let pool = defaults.database;
+    let database = DatabaseConfig {
+        max_connections: source.parse("database.max_connections", pool.max_connections),
+        min_connections: source.parse("database.min_connections", pool.min_connections),
+        connect_timeout: source.secs("database.connect_timeout_secs", pool.connect_timeout),
+        acquire_timeout: source.secs("database.acquire_timeout_secs", pool.acquire_timeout),
+        idle_timeout: source.secs("database.idle_timeout_secs", pool.idle_timeout),
+        log_statements: source.parse("database.log_statements", pool.log_statements),
+        connect_attempts: source.parse("database.connect_attempts", pool.connect_attempts),
+    };
+    source.check(
+        database.max_connections > 0,
+        "database.max_connections",
+        "must be positive",
+    );
+    source.check(
+        database.min_connections <= database.max_connections,
+        "database.min_connections",
+        "must not exceed database.max_connections",
+    );
+    source.check(
+        database.connect_attempts > 0,
+        "database.connect_attempts",
+        "must be positive",
+    );
+
+    let webauthn = WebauthnConfig {
+        rp_id: source.text_or("webauthn.rp_id", defaults.webauthn.rp_id),
+        rp_name: source.text_or("webauthn.rp_name", defaults.webauthn.rp_name),
+        origin: source.url_or("webauthn.origin", defaults.webauthn.origin),
+    };
+
+    let policy = defaults.password_policy;
+    let password_policy = PasswordPolicy {
+        min_length: source.parse("password.min_length", policy.min_length),
+        require_lowercase: source.parse("password.require_lowercase", policy.require_lowercase),
+        require_uppercase: source.parse("password.require_uppercase", policy.require_uppercase),
+        require_digit: source.parse("password.require_digit", policy.require_digit),
+        require_symbol: source.parse("password.require_symbol", policy.require_symbol),
+        reject_personal_info: source
+            .parse("password.reject_personal_info", policy.reject_personal_info),
+        breached: source
+            .text("password.breached_passwords_dir")
+            .map(BreachedPasswords::new),
+    };
+
+    let limits = defaults.rate_limit;
+    let rate_limit = RateLimitConfig {
+        per_ip: source.quota("rate_limit.ip", limits.per_ip),
+        per_email: source.quota("rate_limit.email", limits.per_email),
+        lockout_threshold: source.parse("rate_limit.lockout_threshold", limits.lockout_threshold),
+        lockout_base: source.secs("rate_limit.lockout_base_secs", limits.lockout_base),
+        lockout_max: source.secs("rate_limit.lockout_max_secs", limits.lockout_max),
+        trust_forwarded_for: source
+            .parse("rate_limit.trust_forwarded_for", limits.trust_forwarded_for),
+    };
+    source.check(
+        rate_limit.lockout_max >= rate_limit.lockout_base,
+        "rate_limit.lockout_max_secs",
+        "must not be shorter than rate_limit.lockout_base_secs",
+    );
+
+    // Os padrões das URLs acompanham a porta configurada
+    let uploads = UploadsConfig {
+        dir: source.text_or("uploads.dir", "uploads".into()).into(),
+        public_url: source.url_or(
+            "uploads.public_url",
+            format!("http://localhost:{}/uploads", port),
+        ),
+    };
+    let exports = ExportsConfig {
+        dir: source.text_or("exports.dir", "exports".into()).into(),
+        download_url: source.url_or(
+            "exports.download_url",
+            format!("http://localhost:{}/v1/profile/export", port),
+        ),
+    };
+    source.check(
+        !normalize(&exports.dir).starts_with(normalize(&uploads.dir)),
+        "exports.dir",
+        "must be outside uploads.dir, which is served publicly",
+    );
+
+    let github = source
+        .section(
+            "github",
+            &["client_id", "client_secret", "oauth_redirect_url"],
+        )
+        .then(|| GithubConfig {
+            client_id: source.required("github.client_id"),
+            client_secret: source.required("github.client_secret"),
+            oauth_redirect_url: source.required_url("github.oauth_redirect_url"),
+        });
+
+    let mail = source.section("mail", &["from"]).then(|| {
+        let from = source.required("mail.from");
+        source.check(
+            from.is_empty() || from.validate_email(),
+            "mail.from",
+            "must be an e-mail address",
+        );
+        MailConfig { from }
+    });
+
+    let cors = source
+        .section(
+            "cors",
+            &["allowed_origins", "allow_credentials", "max_age_secs"],
+        )
+        .then(|| {
+            let allowed_origins = source.list("cors.allowed_origins");
+            source.check(
+                !allowed_origins.is_empty(),
+                "cors.allowed_origins",
+                "is required",
+            );
+            for origin in &allowed_origins {
+                source.check(
+                    is_origin(origin),
+                    "cors.allowed_origins",
+                    &format!("{:?} is not an origin like https://app.example.com", origin),
+                );
+            }
+
+            CorsConfig {
+                allowed_origins,
+                allow_credentials: source.parse("cors.allow_credentials", false),
+                max_age: source.secs("cors.max_age_secs", Duration::from_secs(60 * 60)),
+            }
+        });
+
+    Config {
+        port,
+        database_url,
+        database,
+        jwt_secret,
+        webauthn,
+        password_policy,
+        rate_limit,
+        uploads,
+        exports,
+        github,
+        mail,
+        cors,
+    }
+}
+
+/// Absolute form of `path` with `.` and `..` resolved, without touching the
+/// file system since the directories may not exist yet.
+fn normalize(path: &Path) -> PathBuf {
+    let absolute = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+    let mut normalized = PathBuf::new();
+
+    for component in absolute.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+
+    normalized
+}
+
+fn is_origin(origin: &str) -> bool {
+    Url::parse(origin).is_ok_and(|url| {
+        matches!(url.scheme(), "http" | "https")
+            && url.host().is_some()
+            && url.origin().ascii_serialization() == origin
+    })
+}
+
+/// Environment variable of a key: `rate_limit.ip_max` is `RATE_LIMIT_IP_MAX`.
+pub fn env_name(key: &str) -> String {
+    key.replace('.', "_").to_uppercase()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigError {
+    /// Key and environment variable of the setting, or the file.
+    pub setting: String,
+    pub message: String,
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.setting, self.message)
+    }
+}
+
+/// Every problem found while loading the configuration.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigErrors(pub Vec<ConfigError>);
+
+impl ConfigErrors {
+    fn file(path: &str, message: String) -> Self {
+        ConfigErrors(vec![ConfigError {
+            setting: path.to_owned(),
+            message,
+        }])
+    }
+}
+
+impl fmt::Display for ConfigErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid configuration:")?;
+        for error in &self.0 {
+            write!(f, "\n  - {}", error)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ConfigErrors {}
+
+/// Looks settings up, keeping the errors instead of stopping at the first.
+struct Source<'a> {
+    document: &'a DocumentMut,
+    env: &'a dyn Fn(&str) -> Option<String>,
+    /// Keys asked for, so the file can't carry misspelled ones unnoticed.
+    read: HashSet<String>,
+    errors: Vec<ConfigError>,
+}
+
+impl Source<'_> {
+    fn error(&mut self, key: &str, message: impl Into<String>) {
+        self.errors.push(ConfigError {
+            setting: format!("{} ({})", key, env_name(key)),
+            message: message.into(),
+        });
+    }
+
+    fn check(&mut self, ok: bool, key: &str, message: &str) {
+        if !ok {
+            self.error(key, message);
+        }
+    }
+
+    fn file_item(&self, key: &str) -> Option<&Item> {
+        let mut parts = key.split('.');
+        let first = self.document.get(parts.next()?)?;
+        parts.try_fold(first, |item, part| item.get(part))
+    }
+
+    /// The raw value of `key`; empty environment variables count as unset.
+    fn text(&mut self, key: &str) -> Option<String> {
+        self.read.insert(key.to_owned());
+
+        if let Some(value) = (self.env)(&env_name(key)).filter(|value| !value.is_empty()) {
+            return Some(value);
+        }
+
+        let value = match self.file_item(key)?.as_value() {
+            Some(Value::String(s)) => s.value().clone(),
+            Some(Value::Integer(i)) => i.value().to_string(),
+            Some(Value::Boolean(b)) => b.value().to_string(),
+            _ => {
+                self.error(key, "must be a string, an integer or a boolean");
+                return None;
+            }
+        };
+        Some(value)
+    }
+
+    fn text_or(&mut self, key: &str, default: String) -> String {
+        self.text(key).unwrap_or(default)
+    }
+
+    fn required(&mut self, key: &str) -> String {
+        self.text(key).unwrap_or_else(|| {
+            self.error(key, "is required");
+            String::new()
+        })
+    }
+
+    fn parse<T>(&mut self, key: &str, default: T) -> T
+    where
+        T: FromStr,
+        T::Err: fmt::Display,
+    {
+        let Some(text) = self.text(key) else {
+            return default;
+        };
+
+        text.trim().parse().unwrap_or_else(|e| {
+            self.error(key, format!("invalid value {:?}: {}", text, e));
+            default
+        })
+    }
+
+    fn secs(&mut self, key: &str, default: Duration) -> Duration {
+        Duration::from_secs(self.parse(key, default.as_secs()))
+    }
+
+    /// `<prefix>_max` requests every `<prefix>_window_secs`, both positive.
+    fn quota(&mut self, prefix: &str, default: Quota) -> Quota {
+        let max = format!("{}_max", prefix);
+        let window = format!("{}_window_secs", prefix);
+
+        let quota = Quota {
+            limit: self.parse(&max, default.limit),
+            window: self.secs(&window, default.window),
+        };
+        self.check(quota.limit > 0, &max, "must be positive");
+        self.check(!quota.window.is_zero(), &window, "must be positive");
+        quota
+    }
+
+    fn url_or(&mut self, key: &str, default: String) -> String {
+        match self.text(key) {
+            Some(url) => {
+                self.check_url(key, &url);
+                url
+            }
+            None => default,
+        }
+    }
+
+    fn required_url(&mut self, key: &str) -> String {
+        let url = self.required(key);
+        if !url.is_empty() {
+            self.check_url(key, &url);
+        }
+        url
+    }
+
+    fn check_url(&mut self, key: &str, url: &str) {
+        if let Err(e) = Url::parse(url) {
+            self.error(key, format!("invalid URL {:?}: {}", url, e));
+        }
+    }
+
+    /// A TOML array of strings, or a comma-separated environment variable.
+    fn list(&mut self, key: &str) -> Vec<String> {
+        self.read.insert(key.to_owned());
+
+        if let Some(value) = (self.env)(&env_name(key)).filter(|value| !value.is_empty()) {
+            return value
+                .split(',')
+                .map(str::trim)
+                .filter(|item| !item.is_empty())
+                .map(str::to_owned)
+                .collect();
+        }
+
+        let Some(item) = self.file_item(key) else {
+            return Vec::new();
+        };
+        let items = item.as_array().map(|array| {
+            array
+                .iter()
+                .map(|value| value.as_str().map(str::to_owned))
+                .collect::<Option<Vec<_>>>()
+        });
+
+        items.flatten().unwrap_or_else(|| {
+            self.error(key, "must be an array of strings");
+            Vec::new()
+        })
+    }
+
+    /// Whether an optional section is given, in the file or by any of its
+    /// environment variables.
+    fn section(&mut self, name: &str, keys: &[&str]) -> bool {
+        self.read.insert(name.to_owned());
+
+        self.document.contains_key(name)
+            || keys
+                .iter()
+                .any(|key| (self.env)(&env_name(&format!("{}.{}", name, key))).is_some())
+    }
+
+    fn reject_unknown_keys(&mut self) {
+        let mut unknown = Vec::new();
+
+        for (name, item) in self.document.iter() {
+            let prefix = format!("{}.", name);
+            let is_section =
+                self.read.contains(name) || self.read.iter().any(|key| key.starts_with(&prefix));
+
+            match item.as_table_like() {
+                Some(table) if is_section => unknown.extend(
+                    table
+                        .iter()
+                        .map(|(key, _)| format!("{}.{}", name, key))
+                        .filter(|key| !self.read.contains(key)),
+                ),
+                Some(_) => unknown.push(name.to_owned()),
+                None if !self.read.contains(name) => unknown.push(name.to_owned()),
+                None => {}
+            }
+        }
+
+        for key in unknown {
+            self.error(&key, "unknown setting");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn load(file: &str, env: &[(&str, &str)]) -> Result<Config, ConfigErrors> {
+        let env: HashMap<String, String> = env
+            .iter()
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect();
+
+        Config::from_sources(Some(file), |name| env.get(name).cloned())
+    }
+
+    /// Settings named by the errors of an invalid configuration.
+    fn invalid_settings(result: Result<Config, ConfigErrors>) -> Vec<String> {
+        match result {
+            Ok(_) => panic!("the configuration should be invalid"),
+            Err(errors) => errors.0.into_iter().map(|e| e.setting).collect(),
+        }
+    }
+
+    #[test]
+    fn test_environment_overrides_file_over_defaults() {
+        let config = load(
+            r#"
+            database_url = "postgres://localhost/app"
+            jwt_secret = "from-file"
+            port = 8000
+
+            [database]
+            max_connections = 20
+
+            [rate_limit]
+            ip_max = 50
+            "#,
+            &[
+                ("JWT_SECRET", "from-env"),
+                ("RATE_LIMIT_IP_WINDOW_SECS", "10"),
+            ],
+        )
+        .unwrap();
+
+        assert_eq!(config.jwt_secret, "from-env");
+        assert_eq!(config.port, 8000);
+        assert_eq!(config.database.max_connections, 20);
+        assert_eq!(config.database.min_connections, 1);
+        assert_eq!(config.rate_limit.per_ip.limit, 50);
+        assert_eq!(config.rate_limit.per_ip.window, Duration::from_secs(10));
+        assert_eq!(config.rate_limit.per_email.limit, 5);
+        assert_eq!(config.uploads.public_url, "http://localhost:8000/uploads");
+        assert!(config.github.is_none());
+        assert!(config.cors.is_none());
+    }
+
+    #[test]
+    fn test_every_error_is_reported_at_once() {
+        let result = load(
+            r#"
+            port = "eighty"
+            colour = "blue"
+
+            [rate_limit]
+            ip_max = 0
+            "#,
+            &[("GITHUB_CLIENT_ID", "id"), ("WEBAUTHN_ORIGIN", "localhost")],
+        );
+
+        assert_eq!(
+            invalid_settings(result),
+            [
+                "port (PORT)",
+                "database_url (DATABASE_URL)",
+                "jwt_secret (JWT_SECRET)",
+                "webauthn.origin (WEBAUTHN_ORIGIN)",
+                "rate_limit.ip_max (RATE_LIMIT_IP_MAX)",
+                "github.client_secret (GITHUB_CLIENT_SECRET)",
+                "github.oauth_redirect_url (GITHUB_OAUTH_REDIRECT_URL)",
+                "colour (COLOUR)",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_optional_sections() {
+        let required = [
+            ("DATABASE_URL", "sqlite::memory:"),
+            ("JWT_SECRET", "secret"),
+        ];
+
+        let config = load(
+            r#"
+            [github]
+            client_id = "id"
+            client_secret = "secret"
+            oauth_redirect_url = "http://localhost:8080/callback"
+
+            [mail]
+            from = "no-reply@example.com"
+
+            [cors]
+            allowed_origins = ["https://app.example.com"]
+            allow_credentials = true
+            "#,
+            &required,
+        )
+        .unwrap();
+
+        assert_eq!(config.github.unwrap().client_id, "id");
+        assert_eq!(config.mail.unwrap().from, "no-reply@example.com");
+        let cors = config.cors.unwrap();
+        assert_eq!(cors.allowed_origins, ["https://app.example.com"]);
+        assert!(cors.allow_credentials);
+
+        let result = load(
+            "",
+            &[
+                required[0],
+                required[1],
+                ("MAIL_FROM", "nobody"),
+                ("CORS_ALLOWED_ORIGINS", "https://app.example.com/path"),
+            ],
+        );
+        assert_eq!(
+            invalid_settings(result),
+            [
+                "mail.from (MAIL_FROM)",
+                "cors.allowed_origins (CORS_ALLOWED_ORIGINS)"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_exports_dir_must_be_outside_uploads_dir() {
+        let required = [
+            ("DATABASE_URL", "sqlite::memory:"),
+            ("JWT_SECRET", "secret"),
+        ];
+        let exports_in = |uploads: &str, exports: &str| {
+            load(
+                "",
+                &[
+                    required[0],
+                    required[1],
+                    ("UPLOADS_DIR", uploads),
+                    ("EXPORTS_DIR", exports),
+                ],
+            )
+        };
+
+        for (uploads, exports) in [
+            ("uploads", "uploads"),
+            ("uploads", "./uploads/exports"),
+            ("data/uploads", "data/exports/../uploads/private"),
+            ("/srv/uploads/", "/srv/uploads/exports"),
+        ] {
+            assert_eq!(
+                invalid_settings(exports_in(uploads, exports)),
+                ["exports.dir (EXPORTS_DIR)"],
+                "{} inside {}",
+                exports,
+                uploads
+            );
+        }
+
+        assert!(exports_in("data/uploads", "data/uploads-private").is_ok());
+        assert!(exports_in("/srv/uploads", "/srv/exports").is_ok());
+    }
+}
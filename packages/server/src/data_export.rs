@@ -0,0 +1,514 @@
+//! GDPR data export. `POST /profile/export` records a pending export token
+//! and spawns [`run_export`], which gathers everything tied to the user, packs
+//! it as `export.json` plus one CSV per section in a zip, keeps it in private
+//! storage and mails a link that works once. While the token exists, building
+//! or waiting to be downloaded, further requests are refused.
+//!
+//! Secrets (password hash, TOTP secret, recovery codes, passkey keys, pending
+//! tokens) are never exported. Sessions are stateless JWTs and aren't stored,
+//! so the user's `sessionVersion` and passkey usage are what the export can
+//! show about them.
+
+use crate::{
+    mailer::{send_best_effort, Email},
+    storage::{Storage, StorageError},
+    AppState,
+};
+use chrono::{Duration, Utc};
+use entities::{
+    accounts, invites, members, organizations, passkeys, projects,
+    sea_orm_active_enums::{AccountProvider, Role, TokenType},
+    tokens, users,
+};
+use sea_orm::{
+    prelude::DateTimeWithTimeZone, sea_query::Expr, ColumnTrait, DatabaseConnection, DbErr,
+    EntityTrait, QueryFilter,
+};
+use serde::Serialize;
+use std::{
+    fmt,
+    io::{self, Cursor, Write},
+    sync::Arc,
+};
+use tracing::{error, info, warn};
+use uuid::Uuid;
+use zip::{write::SimpleFileOptions, ZipWriter};
+
+/// How long the download link stays valid.
+pub const EXPORT_TTL_HOURS: i64 = 48;
+const CLEANUP_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60 * 60);
+
+/// Exports requested before this have expired.
+pub fn expiry_cutoff() -> DateTimeWithTimeZone {
+    (Utc::now() - Duration::hours(EXPORT_TTL_HOURS)).fixed_offset()
+}
+
+#[derive(Clone)]
+pub struct DataExportConfig {
+    /// Where archives are kept until downloaded. Must not be publicly served.
+    pub storage: Arc<dyn Storage>,
+    /// Base URL of the download route, e.g. `http://localhost:3000/v1/profile/export`.
+    pub download_url: String,
+}
+
+#[derive(Debug)]
+pub struct ExportError(pub String);
+
+impl fmt::Display for ExportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl From<DbErr> for ExportError {
+    fn from(e: DbErr) -> Self {
+        ExportError(e.to_string())
+    }
+}
+
+impl From<StorageError> for ExportError {
+    fn from(e: StorageError) -> Self {
+        ExportError(e.0)
+    }
+}
+
+impl From<io::Error> for ExportError {
+    fn from(e: io::Error) -> Self {
+        ExportError(e.to_string())
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserRecord {
+    pub id: Uuid,
+    pub name: Option<String>,
+    pub email: String,
+    pub avatar_url: Option<String>,
+    pub has_password: bool,
+    pub two_factor_enabled: bool,
+    pub session_version: i32,
+    pub deletion_scheduled_at: Option<String>,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+impl From<users::Model> for UserRecord {
+    fn from(user: users::Model) -> Self {
+        Self {
+            id: user.id,
+            name: user.name,
+            email: user.email,
+            avatar_url: user.avatar_url,
+            has_password: user.password_hash.is_some(),
+            two_factor_enabled: user.totp_enabled,
+            session_version: user.session_version,
+            deletion_scheduled_at: user.deletion_scheduled_at.map(|at| at.to_rfc3339()),
+            created_at: user.created_at.and_utc().to_rfc3339(),
+            updated_at: user.updated_at.and_utc().to_rfc3339(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountRecord {
+    pub id: Uuid,
+    pub provider: AccountProvider,
+    pub provider_account_id: String,
+    pub provider_login: Option<String>,
+    pub created_at: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MembershipRecord {
+    pub id: Uuid,
+    pub role: Role,
+    pub organization_id: Uuid,
+    pub organization_name: Option<String>,
+    pub created_at: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InviteRecord {
+    pub id: Uuid,
+    pub email: String,
+    pub role: Role,
+    pub organization_id: Uuid,
+    pub organization_name: Option<String>,
+    pub created_at: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectRecord {
+    pub id: Uuid,
+    pub name: String,
+    pub slug: String,
+    pub description: String,
+    pub avatar_url: Option<String>,
+    pub organization_id: Uuid,
+    pub created_at: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PasskeyRecord {
+    pub id: Uuid,
+    pub name: Option<String>,
+    pub last_used_at: Option<String>,
+    pub created_at: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserExport {
+    pub exported_at: String,
+    pub user: UserRecord,
+    pub accounts: Vec<AccountRecord>,
+    pub memberships: Vec<MembershipRecord>,
+    pub invites: Vec<InviteRecord>,
+    pub projects: Vec<ProjectRecord>,
+    pub passkeys: Vec<PasskeyRecord>,
+}
+
+/// Reads everything the export covers, or `None` if the user is gone.
+pub async fn collect_export(
+    db: &DatabaseConnection,
+    user_id: Uuid,
+) -> Result<Option<UserExport>, DbErr> {
+    let Some(user) = users::Entity::find_by_id(user_id).one(db).await? else {
+        return Ok(None);
+    };
+
+    let accounts = accounts::Entity::find()
+        .filter(accounts::Column::UserId.eq(user_id))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|account| AccountRecord {
+            id: account.id,
+            provider: account.provider,
+            provider_account_id: account.provider_account_id,
+            provider_login: account.provider_login,
+            created_at: account.created_at.and_utc().to_rfc3339(),
+        })
+        .collect();
+
+    let memberships = members::Entity::find()
+        .filter(members::Column::UserId.eq(user_id))
+        .find_also_related(organizations::Entity)
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|(member, organization)| MembershipRecord {
+            id: member.id,
+            role: member.role,
+            organization_id: member.organization_id,
+            organization_name: organization.map(|org| org.name),
+            created_at: member.created_at.and_utc().to_rfc3339(),
+        })
+        .collect();
+
+    let invites = invites::Entity::find()
+        .filter(invites::Column::AuthorId.eq(user_id))
+        .find_also_related(organizations::Entity)
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|(invite, organization)| InviteRecord {
+            id: invite.id,
+            email: invite.email,
+            role: invite.role,
+            organization_id: invite.organization_id,
+            organization_name: organization.map(|org| org.name),
+            created_at: invite.created_at.to_rfc3339(),
+        })
+        .collect();
+
+    let projects = projects::Entity::find()
+        .filter(projects::Column::OwnerId.eq(user_id))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|project| ProjectRecord {
+            id: project.id,
+            name: project.name,
+            slug: project.slug,
+            description: project.description,
+            avatar_url: project.avatar_url,
+            organization_id: project.organization_id,
+            created_at: project.created_at.and_utc().to_rfc3339(),
+        })
+        .collect();
+
+    let passkeys = passkeys::Entity::find()
+        .filter(passkeys::Column::UserId.eq(user_id))
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|passkey| PasskeyRecord {
+            id: passkey.id,
+            name: passkey.name,
+            last_used_at: passkey.last_used_at.map(|at| at.to_rfc3339()),
+            created_at: passkey.created_at.and_utc().to_rfc3339(),
+        })
+        .collect();
+
+    Ok(Some(UserExport {
+        exported_at: Utc::now().to_rfc3339(),
+        user: user.into(),
+        accounts,
+        memberships,
+        invites,
+        projects,
+        passkeys,
+    }))
+}
+
+fn to_csv<T: Serialize>(records: &[T]) -> io::Result<Vec<u8>> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+
+    for record in records {
+        writer.serialize(record)?;
+    }
+
+    writer.into_inner().map_err(|e| e.into_error())
+}
+
+/// Packs the export as a zip with `export.json` and one CSV per section.
+pub fn build_archive(export: &UserExport) -> io::Result<Vec<u8>> {
+    let files = [
+        ("export.json", serde_json::to_vec_pretty(export)?),
+        ("user.csv", to_csv(std::slice::from_ref(&export.user))?),
+        ("accounts.csv", to_csv(&export.accounts)?),
+        ("memberships.csv", to_csv(&export.memberships)?),
+        ("invites.csv", to_csv(&export.invites)?),
+        ("projects.csv", to_csv(&export.projects)?),
+        ("passkeys.csv", to_csv(&export.passkeys)?),
+    ];
+
+    let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+
+    for (name, contents) in files {
+        zip.start_file(name, SimpleFileOptions::default())?;
+        zip.write_all(&contents)?;
+    }
+
+    Ok(zip.finish()?.into_inner())
+}
+
+async fn export(state: &AppState, user_id: Uuid, code: Uuid) -> Result<(), ExportError> {
+    let Some(export) = collect_export(&state.db, user_id).await? else {
+        return Ok(());
+    };
+
+    let email = export.user.email.clone();
+    let archive = tokio::task::spawn_blocking(move || build_archive(&export))
+        .await
+        .map_err(|e| ExportError(e.to_string()))??;
+
+    // O id do token é o segredo do link, então a chave não precisa ser adivinhável
+    let key = format!("{}.zip", code);
+
+    state
+        .exports
+        .storage
+        .put(&key, archive, "application/zip")
+        .await?;
+
+    // O prazo do link conta de quando ele é enviado
+    let ready = tokens::Entity::update_many()
+        .col_expr(tokens::Column::Payload, Expr::value(key.clone()))
+        .col_expr(
+            tokens::Column::CreatedAt,
+            Expr::value(Utc::now().fixed_offset()),
+        )
+        .filter(tokens::Column::Id.eq(code))
+        .exec(&state.db)
+        .await;
+
+    match ready {
+        Ok(ready) if ready.rows_affected == 1 => {}
+        // A conta foi excluída enquanto o arquivo era montado
+        Ok(_) => {
+            let _ = state.exports.storage.delete(&key).await;
+            return Ok(());
+        }
+        Err(e) => {
+            let _ = state.exports.storage.delete(&key).await;
+            return Err(e.into());
+        }
+    }
+
+    send_best_effort(
+        state.mailer.as_ref(),
+        Email::from_template(
+            email,
+            "data_export_ready",
+            &[
+                ("url", &format!("{}/{}", state.exports.download_url, code)),
+                ("hours", &EXPORT_TTL_HOURS),
+            ],
+        ),
+    )
+    .await;
+
+    Ok(())
+}
+
+/// Builds the user's archive for the pending token `code` and mails the
+/// download link. Meant to be spawned.
+pub async fn run_export(state: AppState, user_id: Uuid, code: Uuid) {
+    match export(&state, user_id, code).await {
+        Ok(()) => info!("Data export for user {} is ready", user_id),
+        Err(e) => {
+            error!("Data export for user {} failed: {}", user_id, e);
+
+            // Libera um novo pedido
+            if let Err(e) = tokens::Entity::delete_by_id(code).exec(&state.db).await {
+                error!("Failed to delete data export token {}: {}", code, e);
+            }
+        }
+    }
+}
+
+/// Deletes archives whose link expired without being used, returning how
+/// many were removed.
+pub async fn purge_expired_exports(
+    db: &DatabaseConnection,
+    storage: &dyn Storage,
+) -> Result<usize, DbErr> {
+    let expired = tokens::Entity::find()
+        .filter(tokens::Column::Type.eq(TokenType::DataExport))
+        .filter(tokens::Column::CreatedAt.lt(expiry_cutoff()))
+        .all(db)
+        .await?;
+
+    let mut purged = 0;
+
+    for token in expired {
+        if let Some(key) = &token.payload {
+            if let Err(e) = storage.delete(key).await {
+                // Mantém o token para tentar apagar o arquivo na próxima rodada
+                warn!("Couldn't delete data export {}: {}", key, e);
+                continue;
+            }
+        }
+
+        tokens::Entity::delete_by_id(token.id).exec(db).await?;
+        purged += 1;
+    }
+
+    Ok(purged)
+}
+
+/// Runs [`purge_expired_exports`] every hour, forever.
+pub async fn run_cleanup_job(db: DatabaseConnection, storage: Arc<dyn Storage>) {
+    let mut interval = tokio::time::interval(CLEANUP_INTERVAL);
+
+    loop {
+        interval.tick().await;
+
+        match purge_expired_exports(&db, storage.as_ref()).await {
+            Ok(0) => {}
+            Ok(purged) => info!("Deleted {} expired data export(s)", purged),
+            Err(e) => error!("Data export cleanup failed: {}", e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Read;
+    use zip::ZipArchive;
+
+    fn sample_export() -> UserExport {
+        let now = Utc::now();
+        let user = users::Model {
+            created_at: now.naive_utc(),
+            updated_at: now.naive_utc(),
+            id: Uuid::new_v4(),
+            name: Some("Jane, \"JD\" Doe".into()),
+            email: "jane@example.com".into(),
+            password_hash: Some("$argon2id$secret-hash".into()),
+            avatar_url: None,
+            totp_secret: Some("TOTPSECRET".into()),
+            totp_enabled: true,
+            totp_last_step: Some(59_000_000),
+            session_version: 3,
+            deletion_scheduled_at: None,
+        };
+
+        UserExport {
+            exported_at: now.to_rfc3339(),
+            user: user.into(),
+            accounts: vec![],
+            memberships: vec![MembershipRecord {
+                id: Uuid::new_v4(),
+                role: Role::Admin,
+                organization_id: Uuid::new_v4(),
+                organization_name: Some("Acme".into()),
+                created_at: now.to_rfc3339(),
+            }],
+            invites: vec![],
+            projects: vec![],
+            passkeys: vec![],
+        }
+    }
+
+    fn read_entry(archive: &mut ZipArchive<Cursor<Vec<u8>>>, name: &str) -> String {
+        let mut contents = String::new();
+        archive
+            .by_name(name)
+            .unwrap()
+            .read_to_string(&mut contents)
+            .unwrap();
+        contents
+    }
+
+    #[test]
+    fn test_archive_has_json_and_csv_without_secrets() {
+        let archive = build_archive(&sample_export()).unwrap();
+        let mut archive = ZipArchive::new(Cursor::new(archive)).unwrap();
+
+        let mut names: Vec<_> = archive.file_names().collect();
+        names.sort();
+        assert_eq!(
+            names,
+            [
+                "accounts.csv",
+                "export.json",
+                "invites.csv",
+                "memberships.csv",
+                "passkeys.csv",
+                "projects.csv",
+                "user.csv"
+            ]
+        );
+
+        let json: serde_json::Value =
+            serde_json::from_str(&read_entry(&mut archive, "export.json")).unwrap();
+        assert_eq!(json["user"]["email"], "jane@example.com");
+        assert_eq!(json["user"]["hasPassword"], true);
+        assert_eq!(json["memberships"][0]["organizationName"], "Acme");
+
+        let user_csv = read_entry(&mut archive, "user.csv");
+        assert!(user_csv.starts_with("id,name,email,avatarUrl,hasPassword,"));
+        assert!(user_csv.contains("\"Jane, \"\"JD\"\" Doe\""));
+
+        for i in 0..archive.len() {
+            let mut contents = String::new();
+            archive
+                .by_index(i)
+                .unwrap()
+                .read_to_string(&mut contents)
+                .unwrap();
+            assert!(!contents.contains("secret-hash"));
+            assert!(!contents.contains("TOTPSECRET"));
+        }
+    }
+}
@@ -1,9 +1,167 @@
-use sea_orm::{Database, DatabaseConnection, DbErr};
+use crate::config::Config;
+use migration::{
+    Alias, Expr, IntoSchemaManagerConnection, Migrator, MigratorTrait, Query, Seeder,
+    LEGACY_SEED_VERSION,
+};
+use sea_orm::{
+    ConnectOptions, ConnectionTrait, Database, DatabaseBackend, DatabaseConnection, DbErr,
+    TransactionTrait,
+};
+use std::{collections::HashSet, time::Duration};
+use tracing::warn;
 
-use std::env;
+const RETRY_BASE_DELAY: Duration = Duration::from_secs(1);
+const RETRY_MAX_DELAY: Duration = Duration::from_secs(30);
 
-pub async fn connect_db() -> Result<DatabaseConnection, DbErr> {
-    let database_url = env::var("DATABASE_URL").expect("DATABASE_URL não definido no .env");
+/// Key of the Postgres advisory lock held while migrating, "migrate" in ASCII.
+/// Any constant shared by every instance works.
+const MIGRATION_LOCK_KEY: i64 = 0x006d_6967_7261_7465;
 
-    Database::connect(&database_url).await
+/// Opens the pool, retrying with exponential backoff while the database is
+/// still starting, e.g. when both come up together.
+pub async fn connect_db(config: &Config) -> Result<DatabaseConnection, DbErr> {
+    let pool = &config.database;
+    let mut options = ConnectOptions::new(&config.database_url);
+    options
+        .max_connections(pool.max_connections)
+        .min_connections(pool.min_connections)
+        .connect_timeout(pool.connect_timeout)
+        .acquire_timeout(pool.acquire_timeout)
+        .idle_timeout(pool.idle_timeout)
+        .sqlx_logging(pool.log_statements);
+
+    let mut attempt = 1;
+    loop {
+        match Database::connect(options.clone()).await {
+            Ok(db) => return Ok(db),
+            Err(e) if attempt < pool.connect_attempts => {
+                let delay = retry_delay(attempt);
+                warn!(
+                    "Couldn't connect to the database (attempt {}/{}), retrying in {:?}: {}",
+                    attempt, pool.connect_attempts, delay, e
+                );
+                tokio::time::sleep(delay).await;
+                attempt += 1;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+/// Wait after the failed `attempt`: 1s, 2s, 4s… up to 30s.
+fn retry_delay(attempt: u32) -> Duration {
+    RETRY_BASE_DELAY
+        .saturating_mul(2u32.saturating_pow(attempt - 1))
+        .min(RETRY_MAX_DELAY)
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Migrate {
+    /// Applies every pending migration
+    Up,
+    /// Rolls back the last `steps` applied migrations
+    Down { steps: u32 },
+}
+
+/// Runs the migrations of this build. On Postgres the instance first takes an
+/// advisory lock, so when several start with `--migrate` only one migrates and
+/// the others wait and then find nothing pending.
+pub async fn migrate(db: &DatabaseConnection, direction: Migrate) -> Result<(), DbErr> {
+    // SQLite só serve para uma instância local, não há com quem disputar
+    if db.get_database_backend() == DatabaseBackend::Sqlite {
+        forget_legacy_seed(db).await?;
+        return run(db, direction).await;
+    }
+
+    // O lock é liberado junto com a transação, mesmo se o processo morrer
+    let txn = db.begin().await?;
+    txn.execute_unprepared(&format!(
+        "SELECT pg_advisory_xact_lock({})",
+        MIGRATION_LOCK_KEY
+    ))
+    .await?;
+    forget_legacy_seed(&txn).await?;
+    run(&txn, direction).await?;
+    txn.commit().await
+}
+
+async fn run<'c, C>(db: C, direction: Migrate) -> Result<(), DbErr>
+where
+    C: IntoSchemaManagerConnection<'c>,
+{
+    match direction {
+        Migrate::Up => Migrator::up(db, None).await,
+        Migrate::Down { steps } => Migrator::down(db, Some(steps)).await,
+    }
+}
+
+/// Loads the development data, wiping every user and organization first.
+/// Unlike a migration it's reapplied on every call.
+pub async fn seed(db: &DatabaseConnection) -> Result<(), DbErr> {
+    let pending = pending_migrations(db).await?;
+    if !pending.is_empty() {
+        return Err(DbErr::Custom(format!(
+            "the schema is behind, pending migrations: {}",
+            pending.join(", ")
+        )));
+    }
+
+    Seeder::refresh(db).await
+}
+
+/// Drops the record of the seed from databases migrated while it was still
+/// part of [`Migrator`], which otherwise refuses to run over a migration it
+/// doesn't know. The seeded rows stay.
+async fn forget_legacy_seed<C: ConnectionTrait>(db: &C) -> Result<(), DbErr> {
+    Migrator::install(db).await?;
+
+    let stmt = Query::delete()
+        .from_table(Migrator::migration_table_name())
+        .and_where(Expr::col(Alias::new("version")).eq(LEGACY_SEED_VERSION))
+        .to_owned();
+    db.execute(db.get_database_backend().build(&stmt)).await?;
+
+    Ok(())
+}
+
+/// Every migration of this build with whether it's applied, oldest first.
+pub async fn migration_status<C: ConnectionTrait>(db: &C) -> Result<Vec<(String, bool)>, DbErr> {
+    let applied: HashSet<String> = Migrator::get_migration_models(db)
+        .await?
+        .into_iter()
+        .map(|migration| migration.version)
+        .collect();
+
+    Ok(Migrator::migrations()
+        .iter()
+        .map(|migration| {
+            let name = migration.name().to_owned();
+            let is_applied = applied.contains(&name);
+            (name, is_applied)
+        })
+        .collect())
+}
+
+/// Migrations of this build not applied to the database yet. Ones applied by
+/// a newer build don't count, so old instances keep serving during a rolling
+/// deploy.
+pub async fn pending_migrations<C: ConnectionTrait>(db: &C) -> Result<Vec<String>, DbErr> {
+    Ok(migration_status(db)
+        .await?
+        .into_iter()
+        .filter(|(_, applied)| !applied)
+        .map(|(name, _)| name)
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_retry_delay_doubles_up_to_the_max() {
+        let delays: Vec<u64> = (1..=7).map(|n| retry_delay(n).as_secs()).collect();
+
+        assert_eq!(delays, [1, 2, 4, 8, 16, 30, 30]);
+    }
 }
@@ -0,0 +1,312 @@
+//! Recognizes constraint violations in [`DbErr`]s so they turn into client
+//! errors naming the offending field, instead of a generic `500`. Handlers
+//! rely on the constraints rather than checking before writing, which races.
+
+use crate::{
+    error::{json_field_name, AppError, ErrorDetail, FieldErrors},
+    i18n::{t, t_args},
+};
+use sea_orm::{
+    sqlx::{self, error::ErrorKind},
+    DbErr, RuntimeErr,
+};
+
+/// Constraints whose field can't be told from the name: `(constraint, field)`.
+/// Each also has a `constraint.<name>` message.
+const KNOWN_CONSTRAINTS: &[(&str, &str)] = &[
+    ("users_email_key", "email"),
+    ("accounts_provider_user_unique", "provider"),
+    ("accounts_provider_account_id_key", "providerAccountId"),
+    ("passkeys_credential_id_key", "credentialId"),
+    ("members_org_user_unique", "userId"),
+    ("invites_email_org_unique", "email"),
+];
+
+/// Columns of the multi-column unique indexes as SQLite lists them, since it
+/// reports the columns instead of the index: `(constraint, columns)`.
+const UNIQUE_INDEX_COLUMNS: &[(&str, &str)] = &[
+    (
+        "accounts_provider_user_unique",
+        "accounts.provider, accounts.user_id",
+    ),
+    (
+        "members_org_user_unique",
+        "members.organization_id, members.user_id",
+    ),
+    (
+        "invites_email_org_unique",
+        "invites.email, invites.organization_id",
+    ),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConstraintKind {
+    Unique,
+    ForeignKey,
+    Check,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConstraintViolation {
+    pub kind: ConstraintKind,
+    pub constraint: Option<String>,
+    pub table: Option<String>,
+}
+
+impl ConstraintViolation {
+    /// Classifies `e`, returning `None` for anything but a unique, foreign key
+    /// or check violation.
+    pub fn from_db_err(e: &DbErr) -> Option<Self> {
+        let (DbErr::Exec(RuntimeErr::SqlxError(sqlx::Error::Database(e)))
+        | DbErr::Query(RuntimeErr::SqlxError(sqlx::Error::Database(e)))) = e
+        else {
+            return None;
+        };
+
+        let kind = match e.kind() {
+            ErrorKind::UniqueViolation => ConstraintKind::Unique,
+            ErrorKind::ForeignKeyViolation => ConstraintKind::ForeignKey,
+            ErrorKind::CheckViolation => ConstraintKind::Check,
+            _ => return None,
+        };
+
+        let (constraint, table) = match e.constraint() {
+            Some(constraint) => (Some(constraint.to_owned()), e.table().map(str::to_owned)),
+            None if kind == ConstraintKind::Unique => sqlite_unique_constraint(e.message())
+                .map_or((None, None), |(constraint, table)| {
+                    (Some(constraint), Some(table))
+                }),
+            None => (None, None),
+        };
+
+        Some(ConstraintViolation {
+            kind,
+            constraint,
+            table,
+        })
+    }
+
+    fn known(&self) -> Option<&'static (&'static str, &'static str)> {
+        let constraint = self.constraint.as_deref()?;
+        KNOWN_CONSTRAINTS
+            .iter()
+            .find(|(name, ..)| *name == constraint)
+    }
+
+    /// JSON name of the field behind the constraint. Besides the known ones,
+    /// relies on the Postgres default names: `<table>_<column>_key`,
+    /// `<table>_<column>_fkey` and `<table>_<column>_check`.
+    pub fn field(&self) -> Option<String> {
+        if let Some((_, field)) = self.known() {
+            return Some((*field).to_owned());
+        }
+
+        let column = self
+            .constraint
+            .as_deref()?
+            .strip_prefix(self.table.as_deref()?)?
+            .strip_prefix('_')?;
+
+        let column = match self.kind {
+            ConstraintKind::Unique => column.strip_suffix("_key")?,
+            ConstraintKind::ForeignKey => column.strip_suffix("_fkey")?,
+            ConstraintKind::Check => column.strip_suffix("_check")?,
+        };
+
+        Some(json_field_name(column))
+    }
+}
+
+/// Postgres name and table of the unique constraint in a SQLite message like
+/// `UNIQUE constraint failed: users.email`, which only lists the columns.
+fn sqlite_unique_constraint(message: &str) -> Option<(String, String)> {
+    let columns = message.strip_prefix("UNIQUE constraint failed: ")?;
+    let (table, column) = columns.split_once('.')?;
+
+    if let Some((constraint, _)) = UNIQUE_INDEX_COLUMNS
+        .iter()
+        .find(|(_, indexed)| *indexed == columns)
+    {
+        return Some(((*constraint).to_owned(), table.to_owned()));
+    }
+
+    (!column.contains(',')).then(|| (format!("{}_{}_key", table, column), table.to_owned()))
+}
+
+impl From<ConstraintViolation> for AppError {
+    fn from(violation: ConstraintViolation) -> Self {
+        let field = violation.field();
+
+        let (rule, key) = match violation.kind {
+            ConstraintKind::Unique => ("unique", "constraint.unique"),
+            ConstraintKind::ForeignKey => ("foreignKey", "constraint.foreign_key"),
+            ConstraintKind::Check => ("check", "constraint.check"),
+        };
+
+        let detail = match (violation.known(), &field) {
+            (Some((name, _)), _) => t(&format!("constraint.{}", name)),
+            (None, Some(field)) if violation.kind == ConstraintKind::Unique => {
+                t_args(key, &[("field", field)])
+            }
+            (None, None) if violation.kind == ConstraintKind::Unique => {
+                t("constraint.unique.unknown")
+            }
+            _ => t(key),
+        };
+
+        let errors: FieldErrors = field
+            .into_iter()
+            .map(|field| {
+                let detail = ErrorDetail::new(rule, t(&format!("{}.field", key)));
+                (field, vec![detail])
+            })
+            .collect();
+
+        match violation.kind {
+            ConstraintKind::Unique => AppError::Conflict {
+                message: detail,
+                details: Vec::new(),
+                errors,
+            },
+            ConstraintKind::ForeignKey | ConstraintKind::Check => AppError::UnprocessableEntity {
+                message: detail,
+                errors,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::StatusCode;
+    use sea_orm::sqlx::error::DatabaseError;
+    use std::{error::Error, fmt};
+
+    #[derive(Debug)]
+    struct FakeDbError {
+        /// SQLSTATE, as Postgres reports it.
+        code: &'static str,
+        /// Empty for SQLite, which only lists the columns in the message.
+        constraint: &'static str,
+        table: &'static str,
+        message: &'static str,
+    }
+
+    impl fmt::Display for FakeDbError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "violates constraint {}", self.constraint)
+        }
+    }
+
+    impl Error for FakeDbError {}
+
+    impl DatabaseError for FakeDbError {
+        fn message(&self) -> &str {
+            self.message
+        }
+
+        fn as_error(&self) -> &(dyn Error + Send + Sync + 'static) {
+            self
+        }
+
+        fn as_error_mut(&mut self) -> &mut (dyn Error + Send + Sync + 'static) {
+            self
+        }
+
+        fn into_error(self: Box<Self>) -> Box<dyn Error + Send + Sync + 'static> {
+            self
+        }
+
+        fn constraint(&self) -> Option<&str> {
+            Some(self.constraint).filter(|c| !c.is_empty())
+        }
+
+        fn table(&self) -> Option<&str> {
+            Some(self.table).filter(|t| !t.is_empty())
+        }
+
+        fn kind(&self) -> ErrorKind {
+            match self.code {
+                "23505" => ErrorKind::UniqueViolation,
+                "23503" => ErrorKind::ForeignKeyViolation,
+                "23502" => ErrorKind::NotNullViolation,
+                "23514" => ErrorKind::CheckViolation,
+                _ => ErrorKind::Other,
+            }
+        }
+    }
+
+    fn db_err(code: &'static str, constraint: &'static str, table: &'static str) -> DbErr {
+        DbErr::Exec(RuntimeErr::SqlxError(sqlx::Error::Database(Box::new(
+            FakeDbError {
+                code,
+                constraint,
+                table,
+                message: "violates constraint",
+            },
+        ))))
+    }
+
+    fn sqlite_err(code: &'static str, message: &'static str) -> DbErr {
+        DbErr::Exec(RuntimeErr::SqlxError(sqlx::Error::Database(Box::new(
+            FakeDbError {
+                code,
+                constraint: "",
+                table: "",
+                message,
+            },
+        ))))
+    }
+
+    #[test]
+    fn test_unique_violations_are_conflicts_on_the_field() {
+        let e = AppError::from(db_err("23505", "users_email_key", "users"));
+
+        assert_eq!(e.status(), StatusCode::CONFLICT);
+        let body = e.body();
+        assert_eq!(body.detail, "E-mail already in use.");
+        assert_eq!(body.errors["email"][0].rule, "unique");
+
+        let e = AppError::from(db_err("23505", "organizations_slug_key", "organizations"));
+        assert_eq!(e.body().detail, "The slug is already in use.");
+    }
+
+    #[test]
+    fn test_foreign_key_and_check_violations_are_unprocessable() {
+        let e = AppError::from(db_err("23503", "members_organization_id_fkey", "members"));
+
+        assert_eq!(e.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(e.body().errors["organizationId"][0].rule, "foreignKey");
+
+        let e = AppError::from(db_err("23514", "weird_name", "members"));
+
+        assert_eq!(e.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        assert!(e.body().errors.is_empty());
+    }
+
+    #[test]
+    fn test_sqlite_violations_are_named_like_postgres() {
+        let e = AppError::from(sqlite_err("23505", "UNIQUE constraint failed: users.email"));
+        assert_eq!(e.body().detail, "E-mail already in use.");
+
+        let e = AppError::from(sqlite_err(
+            "23505",
+            "UNIQUE constraint failed: members.organization_id, members.user_id",
+        ));
+        assert_eq!(e.body().errors["userId"][0].rule, "unique");
+
+        let e = AppError::from(sqlite_err("23503", "FOREIGN KEY constraint failed"));
+        assert_eq!(e.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        assert!(e.body().errors.is_empty());
+    }
+
+    #[test]
+    fn test_other_errors_stay_internal() {
+        let e = AppError::from(db_err("23502", "", "users"));
+        assert_eq!(e.status(), StatusCode::INTERNAL_SERVER_ERROR);
+
+        let e = AppError::from(DbErr::Custom("boom".into()));
+        assert_eq!(e.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    }
+}
@@ -1,36 +1,422 @@
-use axum::{http::StatusCode, Json};
-use serde::Serialize;
+//! [`AppError`] is the error type of every handler and helper. It turns into
+//! an RFC 7807 `application/problem+json` [`ErrorResponse`] with a stable
+//! `code` for clients to match on, and logs server-side failures on the way
+//! out so call sites can just use `?`.
 
-#[derive(Serialize)]
-pub struct ErrorResponse {
-    pub error: String,
+use crate::{
+    db_error::ConstraintViolation,
+    i18n::{t, t_args},
+    request_id,
+    storage::StorageError,
+};
+use axum::{
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use sea_orm::{DbErr, TransactionError};
+use std::time::Duration;
+use tracing::{debug, error};
+use validator::{ValidationError, ValidationErrors, ValidationErrorsKind};
+
+pub use api_types::error::{
+    ErrorCode, ErrorDetail, ErrorResponse, FieldErrors, PROBLEM_CONTENT_TYPE,
+};
+
+#[derive(Debug)]
+pub enum AppError {
+    BadRequest(String),
+    /// The request is well-formed but some fields break their rules.
+    Validation {
+        message: String,
+        errors: FieldErrors,
+    },
+    Unauthorized(String),
+    Forbidden(String),
+    NotFound(String),
+    Conflict {
+        message: String,
+        details: Vec<ErrorDetail>,
+        errors: FieldErrors,
+    },
+    /// The request refers to data that doesn't fit, e.g. a missing record.
+    UnprocessableEntity {
+        message: String,
+        errors: FieldErrors,
+    },
+    PayloadTooLarge(String),
+    UnsupportedMediaType(String),
+    TooManyRequests {
+        retry_after: Duration,
+    },
+    Db(DbErr),
+    /// A third-party service (e.g. GitHub) failed. The message is logged, not
+    /// returned.
+    Upstream(String),
+    /// Anything else that's our fault. The message is logged, not returned.
+    Internal(String),
 }
 
-impl ErrorResponse {
-    pub fn new(status: StatusCode, error: impl Into<String>) -> (StatusCode, Json<Self>) {
-        (
-            status,
-            Json(Self {
-                error: error.into(),
-            }),
-        )
+impl AppError {
+    pub fn bad_request(message: impl Into<String>) -> Self {
+        AppError::BadRequest(message.into())
+    }
+
+    pub fn validation(message: impl Into<String>, errors: FieldErrors) -> Self {
+        AppError::Validation {
+            message: message.into(),
+            errors,
+        }
+    }
+
+    pub fn unauthorized() -> Self {
+        AppError::Unauthorized(t("problem.unauthorized.detail"))
+    }
+
+    pub fn forbidden(message: impl Into<String>) -> Self {
+        AppError::Forbidden(message.into())
+    }
+
+    pub fn not_found(message: impl Into<String>) -> Self {
+        AppError::NotFound(message.into())
+    }
+
+    pub fn conflict(message: impl Into<String>) -> Self {
+        Self::conflict_with_details(message, Vec::new())
+    }
+
+    pub fn conflict_with_details(message: impl Into<String>, details: Vec<ErrorDetail>) -> Self {
+        AppError::Conflict {
+            message: message.into(),
+            details,
+            errors: FieldErrors::new(),
+        }
+    }
+
+    pub fn internal(message: impl Into<String>) -> Self {
+        AppError::Internal(message.into())
+    }
+
+    pub fn status(&self) -> StatusCode {
+        match self {
+            AppError::BadRequest(_) | AppError::Validation { .. } => StatusCode::BAD_REQUEST,
+            AppError::Unauthorized(_) => StatusCode::UNAUTHORIZED,
+            AppError::Forbidden(_) => StatusCode::FORBIDDEN,
+            AppError::NotFound(_) => StatusCode::NOT_FOUND,
+            AppError::Conflict { .. } => StatusCode::CONFLICT,
+            AppError::UnprocessableEntity { .. } => StatusCode::UNPROCESSABLE_ENTITY,
+            AppError::PayloadTooLarge(_) => StatusCode::PAYLOAD_TOO_LARGE,
+            AppError::UnsupportedMediaType(_) => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            AppError::TooManyRequests { .. } => StatusCode::TOO_MANY_REQUESTS,
+            AppError::Upstream(_) => StatusCode::BAD_GATEWAY,
+            AppError::Db(_) | AppError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
     }
 
-    pub fn internal_error() -> (StatusCode, Json<Self>) {
-        (
-            StatusCode::INTERNAL_SERVER_ERROR,
-            Json(Self {
-                error: "Internal Server Error".to_string(),
-            }),
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            AppError::BadRequest(_) => ErrorCode::BadRequest,
+            AppError::Validation { .. } => ErrorCode::ValidationFailed,
+            AppError::Unauthorized(_) => ErrorCode::Unauthorized,
+            AppError::Forbidden(_) => ErrorCode::Forbidden,
+            AppError::NotFound(_) => ErrorCode::NotFound,
+            AppError::Conflict { .. } => ErrorCode::Conflict,
+            AppError::UnprocessableEntity { .. } => ErrorCode::UnprocessableEntity,
+            AppError::PayloadTooLarge(_) => ErrorCode::PayloadTooLarge,
+            AppError::UnsupportedMediaType(_) => ErrorCode::UnsupportedMediaType,
+            AppError::TooManyRequests { .. } => ErrorCode::TooManyRequests,
+            AppError::Db(_) => ErrorCode::DatabaseError,
+            AppError::Upstream(_) => ErrorCode::UpstreamError,
+            AppError::Internal(_) => ErrorCode::InternalError,
+        }
+    }
+
+    /// The body sent to the client, without anything internal.
+    pub fn body(&self) -> ErrorResponse {
+        let (detail, errors, details) = match self {
+            AppError::BadRequest(message)
+            | AppError::Unauthorized(message)
+            | AppError::Forbidden(message)
+            | AppError::NotFound(message)
+            | AppError::PayloadTooLarge(message)
+            | AppError::UnsupportedMediaType(message) => {
+                (message.clone(), FieldErrors::new(), Vec::new())
+            }
+            AppError::Validation { message, errors }
+            | AppError::UnprocessableEntity { message, errors } => {
+                (message.clone(), errors.clone(), Vec::new())
+            }
+            AppError::Conflict {
+                message,
+                details,
+                errors,
+            } => (message.clone(), errors.clone(), details.clone()),
+            AppError::TooManyRequests { .. } => (
+                t("problem.too_many_requests.detail"),
+                FieldErrors::new(),
+                Vec::new(),
+            ),
+            AppError::Upstream(_) => (
+                t("problem.upstream_error.detail"),
+                FieldErrors::new(),
+                Vec::new(),
+            ),
+            AppError::Db(_) | AppError::Internal(_) => (
+                t("problem.internal_error.detail"),
+                FieldErrors::new(),
+                Vec::new(),
+            ),
+        };
+
+        ErrorResponse {
+            problem_type: format!("/problems/{}", self.code()),
+            title: t(&format!("problem.{}.title", self.code())),
+            status: self.status().as_u16(),
+            detail,
+            code: self.code(),
+            request_id: request_id::current(),
+            errors,
+            details,
+        }
+    }
+}
+
+impl IntoResponse for AppError {
+    fn into_response(self) -> Response {
+        match &self {
+            AppError::Db(e) => error!("Db query error: {}", e),
+            AppError::Upstream(message) => error!("Upstream error: {}", message),
+            AppError::Internal(message) => error!("{}", message),
+            _ => debug!("Request failed with {}: {:?}", self.code(), self),
+        }
+
+        // O header explícito sobrescreve o application/json do Json
+        let mut response = (
+            self.status(),
+            [(header::CONTENT_TYPE, PROBLEM_CONTENT_TYPE)],
+            Json(self.body()),
         )
+            .into_response();
+
+        if let AppError::TooManyRequests { retry_after } = self {
+            // Arredonda para cima, o cliente não deve tentar antes da hora
+            let seconds = retry_after.as_secs() + u64::from(retry_after.subsec_nanos() > 0);
+            response
+                .headers_mut()
+                .insert(header::RETRY_AFTER, seconds.into());
+        }
+
+        response
+    }
+}
+
+impl From<DbErr> for AppError {
+    fn from(e: DbErr) -> Self {
+        match ConstraintViolation::from_db_err(&e) {
+            Some(violation) => {
+                debug!("Constraint violation: {}", e);
+                violation.into()
+            }
+            None => AppError::Db(e),
+        }
+    }
+}
+
+impl From<TransactionError<DbErr>> for AppError {
+    fn from(e: TransactionError<DbErr>) -> Self {
+        match e {
+            TransactionError::Connection(e) | TransactionError::Transaction(e) => e.into(),
+        }
+    }
+}
+
+/// `new_password` -> `newPassword`, the name the field has in the JSON body.
+pub(crate) fn json_field_name(field: &str) -> String {
+    let mut name = String::with_capacity(field.len());
+    let mut upper = false;
+
+    for c in field.chars() {
+        if c == '_' {
+            upper = true;
+        } else if upper {
+            name.extend(c.to_uppercase());
+            upper = false;
+        } else {
+            name.push(c);
+        }
     }
 
-    pub fn unauthorized() -> (StatusCode, Json<Self>) {
-        (
-            StatusCode::UNAUTHORIZED,
-            Json(Self {
-                error: "Unauthorized".to_string(),
-            }),
+    name
+}
+
+fn default_message(error: &ValidationError) -> String {
+    let param = |name: &str| error.params.get(name).map(|value| value.to_string());
+
+    match error.code.as_ref() {
+        "email" => t("validation.email"),
+        "url" => t("validation.url"),
+        "length" => match (param("min"), param("max")) {
+            (Some(min), Some(max)) => {
+                t_args("validation.length_between", &[("min", &min), ("max", &max)])
+            }
+            (Some(min), None) => t_args("validation.length_min", &[("min", &min)]),
+            (None, Some(max)) => t_args("validation.length_max", &[("max", &max)]),
+            (None, None) => t("validation.length"),
+        },
+        _ => t("validation.invalid"),
+    }
+}
+
+fn collect_field_errors(prefix: &str, errors: &ValidationErrors, fields: &mut FieldErrors) {
+    for (field, kind) in errors.errors() {
+        let path = match prefix {
+            "" => json_field_name(field),
+            _ => format!("{}.{}", prefix, json_field_name(field)),
+        };
+
+        match kind {
+            ValidationErrorsKind::Field(errors) => {
+                fields
+                    .entry(path)
+                    .or_default()
+                    .extend(errors.iter().map(|error| {
+                        let message = match &error.message {
+                            Some(message) => message.to_string(),
+                            None => default_message(error),
+                        };
+
+                        ErrorDetail::new(error.code.clone(), message)
+                    }));
+            }
+            ValidationErrorsKind::Struct(errors) => collect_field_errors(&path, errors, fields),
+            ValidationErrorsKind::List(items) => {
+                for (index, errors) in items {
+                    collect_field_errors(&format!("{}[{}]", path, index), errors, fields);
+                }
+            }
+        }
+    }
+}
+
+impl From<ValidationErrors> for AppError {
+    fn from(e: ValidationErrors) -> Self {
+        let mut errors = FieldErrors::new();
+        collect_field_errors("", &e, &mut errors);
+
+        AppError::validation(t("problem.validation_failed.detail"), errors)
+    }
+}
+
+impl From<StorageError> for AppError {
+    fn from(e: StorageError) -> Self {
+        AppError::Internal(format!("Storage error: {}", e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::i18n::Locale;
+    use axum::body::to_bytes;
+    use validator::Validate;
+
+    async fn json_body(response: Response) -> serde_json::Value {
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        serde_json::from_slice(&bytes).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_internal_errors_hide_their_cause() {
+        let response = AppError::Db(DbErr::Custom("relation users is gone".into())).into_response();
+
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(
+            response.headers()[header::CONTENT_TYPE],
+            PROBLEM_CONTENT_TYPE
+        );
+        assert_eq!(
+            json_body(response).await,
+            serde_json::json!({
+                "type": "/problems/database_error",
+                "title": "Internal Server Error",
+                "status": 500,
+                "detail": "Internal Server Error",
+                "code": "database_error",
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_client_errors_keep_message_and_details() {
+        let response = AppError::conflict_with_details(
+            "Transfer your organizations first.",
+            vec![ErrorDetail::new("ownsOrganization", "acme")],
         )
+        .into_response();
+
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+        assert_eq!(
+            json_body(response).await,
+            serde_json::json!({
+                "type": "/problems/conflict",
+                "title": "Conflict",
+                "status": 409,
+                "detail": "Transfer your organizations first.",
+                "code": "conflict",
+                "details": [{ "rule": "ownsOrganization", "message": "acme" }],
+            })
+        );
+    }
+
+    #[derive(Validate)]
+    struct Body {
+        #[validate(email)]
+        new_email: String,
+        #[validate(length(min = 6, max = 8, message = "Too long."))]
+        password: String,
+    }
+
+    #[tokio::test]
+    async fn test_validation_errors_are_keyed_by_json_field() {
+        let e = Body {
+            new_email: "nope".into(),
+            password: "123456789".into(),
+        }
+        .validate()
+        .unwrap_err();
+
+        let body = json_body(AppError::from(e).into_response()).await;
+
+        assert_eq!(body["status"], 400);
+        assert_eq!(body["code"], "validation_failed");
+        assert_eq!(
+            body["errors"],
+            serde_json::json!({
+                "newEmail": [{ "rule": "email", "message": "Must be a valid e-mail address." }],
+                "password": [{ "rule": "length", "message": "Too long." }],
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_title_and_generic_details_are_localized() {
+        let body = Locale::PtBr
+            .scope(async { AppError::internal("boom").body() })
+            .await;
+
+        assert_eq!(body.title, "Erro interno do servidor");
+        assert_eq!(body.detail, "Erro interno do servidor");
+        assert_eq!(body.code, ErrorCode::InternalError);
+    }
+
+    #[test]
+    fn test_too_many_requests_rounds_retry_after_up() {
+        let response = AppError::TooManyRequests {
+            retry_after: Duration::from_millis(1500),
+        }
+        .into_response();
+
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers()[header::RETRY_AFTER], "2");
     }
 }
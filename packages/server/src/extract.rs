@@ -0,0 +1,153 @@
+//! Extractors shared by the handlers.
+
+use crate::{
+    error::AppError,
+    i18n::{t, t_args},
+};
+use axum::{
+    extract::{rejection::JsonRejection, FromRequest, FromRequestParts, Query, Request},
+    http::request::Parts,
+    Json,
+};
+use serde::de::DeserializeOwned;
+use validator::Validate;
+
+/// Like [`Json`], but runs the body's [`Validate`] rules before the handler
+/// and rejects with [`AppError`] problem details instead of plain text.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ValidatedJson<T>(pub T);
+
+impl<T, S> FromRequest<S> for ValidatedJson<T>
+where
+    T: DeserializeOwned + Validate,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let Json(value) = Json::<T>::from_request(req, state)
+            .await
+            .map_err(|rejection| match rejection {
+                JsonRejection::MissingJsonContentType(_) => {
+                    AppError::UnsupportedMediaType(t("request.expected_json"))
+                }
+                e => AppError::bad_request(t_args(
+                    "request.invalid_body",
+                    &[("reason", &e.body_text())],
+                )),
+            })?;
+
+        value.validate()?;
+
+        Ok(ValidatedJson(value))
+    }
+}
+
+/// Like [`Query`], with the same validation and rejections as
+/// [`ValidatedJson`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ValidatedQuery<T>(pub T);
+
+impl<T, S> FromRequestParts<S> for ValidatedQuery<T>
+where
+    T: DeserializeOwned + Validate,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let Query(value) = Query::<T>::from_request_parts(parts, state)
+            .await
+            .map_err(|e| {
+                AppError::bad_request(t_args(
+                    "request.invalid_query",
+                    &[("reason", &e.body_text())],
+                ))
+            })?;
+
+        value.validate()?;
+
+        Ok(ValidatedQuery(value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorCode;
+    use axum::{body::Body, http::header};
+    use serde::Deserialize;
+
+    #[derive(Debug, Deserialize, Validate)]
+    struct TestBody {
+        #[serde(rename = "newEmail")]
+        #[validate(email)]
+        new_email: String,
+    }
+
+    fn request(content_type: &str, body: &'static str) -> Request {
+        Request::post("/")
+            .header(header::CONTENT_TYPE, content_type)
+            .body(Body::from(body))
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_invalid_fields_are_rejected_before_the_handler() {
+        let e = ValidatedJson::<TestBody>::from_request(
+            request("application/json", r#"{ "newEmail": "nope" }"#),
+            &(),
+        )
+        .await
+        .unwrap_err();
+
+        let AppError::Validation { errors, .. } = e else {
+            panic!("expected a validation error, got {:?}", e);
+        };
+        assert_eq!(errors["newEmail"][0].rule, "email");
+    }
+
+    #[tokio::test]
+    async fn test_json_rejections_become_problems() {
+        let e = ValidatedJson::<TestBody>::from_request(request("text/plain", "{}"), &())
+            .await
+            .unwrap_err();
+        assert_eq!(e.code(), ErrorCode::UnsupportedMediaType);
+
+        let e = ValidatedJson::<TestBody>::from_request(request("application/json", "{"), &())
+            .await
+            .unwrap_err();
+        assert_eq!(e.code(), ErrorCode::BadRequest);
+
+        let ValidatedJson(body) = ValidatedJson::<TestBody>::from_request(
+            request("application/json", r#"{ "newEmail": "john@acme.com" }"#),
+            &(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(body.new_email, "john@acme.com");
+    }
+
+    #[derive(Debug, Deserialize, Validate)]
+    struct TestQuery {
+        #[validate(range(min = 1, max = 100))]
+        limit: Option<u64>,
+    }
+
+    async fn query(uri: &str) -> Result<ValidatedQuery<TestQuery>, AppError> {
+        let (mut parts, _) = Request::get(uri).body(()).unwrap().into_parts();
+        ValidatedQuery::<TestQuery>::from_request_parts(&mut parts, &()).await
+    }
+
+    #[tokio::test]
+    async fn test_query_strings_are_parsed_and_validated() {
+        let ValidatedQuery(value) = query("/?limit=20").await.unwrap();
+        assert_eq!(value.limit, Some(20));
+
+        let e = query("/?limit=abc").await.unwrap_err();
+        assert_eq!(e.code(), ErrorCode::BadRequest);
+
+        let e = query("/?limit=500").await.unwrap_err();
+        assert_eq!(e.code(), ErrorCode::ValidationFailed);
+    }
+}
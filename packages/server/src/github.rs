@@ -0,0 +1,355 @@
+use crate::{challenge, error::AppError, i18n::t, AppState};
+use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
+use serde::{Deserialize, Serialize};
+use time::{Duration, OffsetDateTime};
+use uuid::Uuid;
+
+/// Scopes requested on the GitHub authorize page. `user:email` is required to
+/// read private e-mail addresses through `/user/emails`.
+pub const GITHUB_OAUTH_SCOPES: &str = "read:user user:email";
+const STATE_TTL: Duration = Duration::minutes(10);
+
+/// What the user went to the GitHub authorize page for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OauthFlow {
+    Login,
+    Link,
+}
+
+impl OauthFlow {
+    fn audience(self) -> &'static str {
+        match self {
+            OauthFlow::Login => "github.login",
+            OauthFlow::Link => "github.link",
+        }
+    }
+}
+
+/// Claims of the OAuth `state`. `jti` is the [`challenge`] used up when the
+/// code comes back, and `sub` the user who started a link, so a code can't be
+/// replayed or planted in someone else's browser.
+#[derive(Debug, Serialize, Deserialize)]
+struct StateClaims {
+    sub: Option<String>,
+    jti: String,
+    exp: i64,
+    aud: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct GithubAccessTokenResponse {
+    access_token: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct GithubUserResponse {
+    id: i64,
+    login: String,
+    name: Option<String>,
+    email: Option<String>,
+    avatar_url: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct GithubEmailResponse {
+    email: String,
+    primary: bool,
+    verified: bool,
+}
+
+/// GitHub profile with an e-mail address GitHub asserts is verified.
+#[derive(Debug)]
+pub struct GithubProfile {
+    pub id: String,
+    pub login: String,
+    pub name: Option<String>,
+    pub email: String,
+    pub avatar_url: String,
+}
+
+#[derive(Clone)]
+pub struct GithubClient {
+    client_id: String,
+    client_secret: String,
+    redirect_url: String,
+    oauth_url: String,
+    api_url: String,
+    http: reqwest::Client,
+}
+
+impl GithubClient {
+    pub fn new(client_id: String, client_secret: String, redirect_url: String) -> Self {
+        Self {
+            client_id,
+            client_secret,
+            redirect_url,
+            oauth_url: "https://github.com".into(),
+            api_url: "https://api.github.com".into(),
+            http: reqwest::Client::new(),
+        }
+    }
+
+    /// Points the client at another GitHub host, e.g. a mock server in tests.
+    pub fn with_base_urls(
+        mut self,
+        oauth_url: impl Into<String>,
+        api_url: impl Into<String>,
+    ) -> Self {
+        self.oauth_url = oauth_url.into();
+        self.api_url = api_url.into();
+        self
+    }
+
+    /// URL of the GitHub authorize page the frontend should redirect to,
+    /// carrying an `oauth_state` from [`issue_state`].
+    pub fn authorize_url(&self, oauth_state: &str) -> String {
+        let mut url = reqwest::Url::parse(&format!("{}/login/oauth/authorize", self.oauth_url))
+            .expect("invalid GitHub OAuth URL");
+        url.query_pairs_mut()
+            .append_pair("client_id", &self.client_id)
+            .append_pair("redirect_uri", &self.redirect_url)
+            .append_pair("scope", GITHUB_OAUTH_SCOPES)
+            .append_pair("state", oauth_state);
+        url.into()
+    }
+
+    /// Exchanges an OAuth `code` and loads the GitHub profile of its owner.
+    pub async fn fetch_profile(&self, code: &str) -> Result<GithubProfile, AppError> {
+        let access_token = self.exchange_code(code).await?;
+        let user = self.fetch_user(&access_token).await?;
+        let emails = self.fetch_emails(&access_token).await?;
+
+        let email = match select_verified_email(user.email.as_deref(), &emails) {
+            Some(email) => email,
+            None => {
+                return Err(AppError::bad_request(t("auth.github_unverified_email")));
+            }
+        };
+
+        Ok(GithubProfile {
+            id: user.id.to_string(),
+            login: user.login,
+            name: user.name,
+            email,
+            avatar_url: user.avatar_url,
+        })
+    }
+
+    async fn exchange_code(&self, code: &str) -> Result<String, AppError> {
+        let res = self
+            .http
+            .post(format!("{}/login/oauth/access_token", self.oauth_url))
+            .header("Accept", "application/json")
+            .form(&[
+                ("client_id", self.client_id.as_str()),
+                ("client_secret", self.client_secret.as_str()),
+                ("redirect_uri", self.redirect_url.as_str()),
+                ("code", code),
+            ])
+            .send()
+            .await
+            .map_err(|e| AppError::Upstream(format!("GitHub token request failed: {}", e)))?;
+
+        let data: GithubAccessTokenResponse = res
+            .json()
+            .await
+            .map_err(|e| AppError::Upstream(format!("Invalid GitHub token response: {}", e)))?;
+
+        Ok(data.access_token)
+    }
+
+    async fn fetch_user(&self, access_token: &str) -> Result<GithubUserResponse, AppError> {
+        let res = self
+            .http
+            .get(format!("{}/user", self.api_url))
+            .header("Authorization", format!("Bearer {}", access_token))
+            .header("User-Agent", "axum-app")
+            .send()
+            .await
+            .map_err(|e| AppError::Upstream(format!("GitHub user request failed: {}", e)))?;
+
+        res.json()
+            .await
+            .map_err(|e| AppError::Upstream(format!("Invalid GitHub user response: {}", e)))
+    }
+
+    async fn fetch_emails(&self, access_token: &str) -> Result<Vec<GithubEmailResponse>, AppError> {
+        let res = self
+            .http
+            .get(format!("{}/user/emails", self.api_url))
+            .header("Authorization", format!("Bearer {}", access_token))
+            .header("User-Agent", "axum-app")
+            .send()
+            .await
+            .map_err(|e| AppError::Upstream(format!("GitHub emails request failed: {}", e)))?;
+
+        res.json()
+            .await
+            .map_err(|e| AppError::Upstream(format!("Invalid GitHub emails response: {}", e)))
+    }
+}
+
+/// Issues the `state` for a `flow` started by `user_id`, if signed in.
+pub async fn issue_state(
+    flow: OauthFlow,
+    user_id: Option<Uuid>,
+    state: &AppState,
+) -> Result<String, AppError> {
+    let challenge_id = challenge::issue(&state.db, STATE_TTL.unsigned_abs()).await?;
+
+    let claims = StateClaims {
+        sub: user_id.map(|id| id.to_string()),
+        jti: challenge_id.to_string(),
+        exp: (OffsetDateTime::now_utc() + STATE_TTL).unix_timestamp(),
+        aud: flow.audience().to_string(),
+    };
+
+    encode(
+        &Header::default(),
+        &claims,
+        &EncodingKey::from_secret(state.config.jwt_secret.as_ref()),
+    )
+    .map_err(|e| AppError::internal(format!("Failed to generate OAuth state: {}", e)))
+}
+
+/// Checks the `state` returned with a code and uses it up. Runs before the
+/// code is exchanged, so an unsolicited code never reaches GitHub.
+pub async fn consume_state(
+    flow: OauthFlow,
+    oauth_state: &str,
+    user_id: Option<Uuid>,
+    state: &AppState,
+) -> Result<(), AppError> {
+    let invalid = || AppError::forbidden(t("auth.invalid_oauth_state"));
+
+    let mut validation = Validation::default();
+    validation.set_audience(&[flow.audience()]);
+
+    let claims = decode::<StateClaims>(
+        oauth_state,
+        &DecodingKey::from_secret(state.config.jwt_secret.as_ref()),
+        &validation,
+    )
+    .map_err(|_| invalid())?
+    .claims;
+
+    if claims.sub != user_id.map(|id| id.to_string()) {
+        return Err(invalid());
+    }
+
+    let challenge_id: Uuid = claims.jti.parse().map_err(|_| invalid())?;
+    if !challenge::consume(&state.db, challenge_id).await? {
+        return Err(invalid());
+    }
+
+    Ok(())
+}
+
+/// Prefers the public profile e-mail when it is verified, falling back to the
+/// primary verified address.
+fn select_verified_email(
+    profile_email: Option<&str>,
+    emails: &[GithubEmailResponse],
+) -> Option<String> {
+    let verified = |email: &str| {
+        emails
+            .iter()
+            .any(|e| e.verified && e.email.eq_ignore_ascii_case(email))
+    };
+
+    if let Some(email) = profile_email.filter(|email| verified(email)) {
+        return Some(email.to_string());
+    }
+
+    emails
+        .iter()
+        .find(|e| e.primary && e.verified)
+        .map(|e| e.email.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{http::StatusCode, routing::get, routing::post, Json, Router};
+    use serde_json::{json, Value};
+
+    async fn mock_github(user: Value, emails: Value) -> GithubClient {
+        let app = Router::new()
+            .route(
+                "/login/oauth/access_token",
+                post(|| async { Json(json!({ "access_token": "gho_test" })) }),
+            )
+            .route("/user", get(move || async move { Json(user) }))
+            .route("/user/emails", get(move || async move { Json(emails) }));
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        GithubClient::new(
+            "id".into(),
+            "secret".into(),
+            "http://localhost/callback".into(),
+        )
+        .with_base_urls(url.clone(), url)
+    }
+
+    fn github_user(email: Option<&str>) -> Value {
+        json!({
+            "id": 42,
+            "login": "octocat",
+            "name": "The Octocat",
+            "email": email,
+            "avatar_url": "https://avatars.githubusercontent.com/u/42",
+        })
+    }
+
+    #[tokio::test]
+    async fn test_private_email_uses_primary_verified_address() {
+        let client = mock_github(
+            github_user(None),
+            json!([
+                { "email": "old@example.com", "primary": false, "verified": true },
+                { "email": "octocat@example.com", "primary": true, "verified": true },
+            ]),
+        )
+        .await;
+
+        let profile = client.fetch_profile("code").await.unwrap();
+
+        assert_eq!(profile.id, "42");
+        assert_eq!(profile.login, "octocat");
+        assert_eq!(profile.email, "octocat@example.com");
+    }
+
+    #[tokio::test]
+    async fn test_public_email_is_used_when_verified() {
+        let client = mock_github(
+            github_user(Some("public@example.com")),
+            json!([
+                { "email": "octocat@example.com", "primary": true, "verified": true },
+                { "email": "public@example.com", "primary": false, "verified": true },
+            ]),
+        )
+        .await;
+
+        let profile = client.fetch_profile("code").await.unwrap();
+
+        assert_eq!(profile.email, "public@example.com");
+    }
+
+    #[tokio::test]
+    async fn test_unverified_emails_are_rejected() {
+        let client = mock_github(
+            github_user(Some("public@example.com")),
+            json!([
+                { "email": "public@example.com", "primary": true, "verified": false },
+            ]),
+        )
+        .await;
+
+        let status = client.fetch_profile("code").await.unwrap_err().status();
+
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+}
@@ -0,0 +1,216 @@
+//! Localized messages. Catalogs live in `locales/<tag>.json` as flat maps
+//! from message key to text, with `{name}` placeholders. The locale of a
+//! request is negotiated from `Accept-Language`, falling back to English, and
+//! is available to everything running inside [`negotiate_locale`].
+
+use axum::{
+    extract::Request,
+    http::{header, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+use std::{collections::HashMap, fmt, future::Future, sync::LazyLock};
+use tracing::warn;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Locale {
+    #[default]
+    En,
+    PtBr,
+}
+
+tokio::task_local! {
+    static LOCALE: Locale;
+}
+
+type Catalog = HashMap<String, String>;
+
+/// Values of the `{name}` placeholders of a message. `Sync` so messages can
+/// be built across `.await`s.
+pub type Args<'a> = &'a [(&'a str, &'a (dyn fmt::Display + Sync))];
+
+fn parse_catalog(source: &str) -> Catalog {
+    serde_json::from_str(source).expect("Catálogo de mensagens inválido")
+}
+
+static EN: LazyLock<Catalog> = LazyLock::new(|| parse_catalog(include_str!("../locales/en.json")));
+static PT_BR: LazyLock<Catalog> =
+    LazyLock::new(|| parse_catalog(include_str!("../locales/pt-BR.json")));
+
+impl Locale {
+    pub const ALL: [Locale; 2] = [Locale::En, Locale::PtBr];
+
+    /// BCP 47 tag, as sent in `Content-Language`.
+    pub fn tag(self) -> &'static str {
+        match self {
+            Locale::En => "en",
+            Locale::PtBr => "pt-BR",
+        }
+    }
+
+    /// Matches a language tag by its primary language, so `pt`, `pt-PT` and
+    /// `en-GB` resolve to the closest catalog.
+    pub fn from_tag(tag: &str) -> Option<Locale> {
+        let language = tag.split('-').next()?.trim().to_ascii_lowercase();
+
+        match language.as_str() {
+            "en" => Some(Locale::En),
+            "pt" => Some(Locale::PtBr),
+            _ => None,
+        }
+    }
+
+    /// Picks the supported locale the client prefers the most from an
+    /// `Accept-Language` value, e.g. `pt-BR,pt;q=0.9,en;q=0.8`.
+    pub fn negotiate(accept_language: &str) -> Locale {
+        let mut ranges: Vec<(&str, f32)> = accept_language
+            .split(',')
+            .filter_map(|range| {
+                let mut parts = range.split(';');
+                let tag = parts.next()?.trim();
+                let quality = parts
+                    .find_map(|param| param.trim().strip_prefix("q="))
+                    .map_or(Some(1.0), |q| q.trim().parse().ok())?;
+
+                (!tag.is_empty() && quality > 0.0).then_some((tag, quality))
+            })
+            .collect();
+
+        // Estável: empates mantêm a ordem do cliente
+        ranges.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+        ranges
+            .into_iter()
+            .find_map(|(tag, _)| Locale::from_tag(tag))
+            .unwrap_or_default()
+    }
+
+    /// Locale of the request being handled, English outside of one.
+    pub fn current() -> Locale {
+        LOCALE.try_with(|locale| *locale).unwrap_or_default()
+    }
+
+    /// Runs `future` with `self` as the current locale, e.g. for jobs spawned
+    /// by a request.
+    pub async fn scope<F: Future>(self, future: F) -> F::Output {
+        LOCALE.scope(self, future).await
+    }
+
+    fn catalog(self) -> &'static Catalog {
+        match self {
+            Locale::En => &EN,
+            Locale::PtBr => &PT_BR,
+        }
+    }
+
+    /// The message `key` in this locale, falling back to English and then to
+    /// the key itself.
+    pub fn message(self, key: &str) -> &str {
+        match self.catalog().get(key).or_else(|| EN.get(key)) {
+            Some(message) => message,
+            None => {
+                warn!("Missing message {}", key);
+                key
+            }
+        }
+    }
+
+    /// Same as [`Self::message`], replacing each `{name}` placeholder.
+    pub fn format(self, key: &str, args: Args) -> String {
+        args.iter()
+            .fold(self.message(key).to_owned(), |message, (name, value)| {
+                message.replace(&format!("{{{}}}", name), &value.to_string())
+            })
+    }
+}
+
+/// The message `key` in the current locale.
+pub fn t(key: &str) -> String {
+    Locale::current().message(key).to_owned()
+}
+
+/// The message `key` in the current locale, with its placeholders replaced.
+pub fn t_args(key: &str, args: Args) -> String {
+    Locale::current().format(key, args)
+}
+
+/// Middleware running the rest of the stack in the locale negotiated from
+/// `Accept-Language`, which is also sent back in `Content-Language`.
+pub async fn negotiate_locale(request: Request, next: Next) -> Response {
+    let locale = request
+        .headers()
+        .get(header::ACCEPT_LANGUAGE)
+        .and_then(|value| value.to_str().ok())
+        .map(Locale::negotiate)
+        .unwrap_or_default();
+
+    let mut response = locale.scope(next.run(request)).await;
+
+    let headers = response.headers_mut();
+    headers.insert(
+        header::CONTENT_LANGUAGE,
+        HeaderValue::from_static(locale.tag()),
+    );
+    headers.append(header::VARY, HeaderValue::from_static("accept-language"));
+
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeSet;
+
+    fn placeholders(message: &str) -> BTreeSet<&str> {
+        message
+            .split('{')
+            .skip(1)
+            .filter_map(|part| part.split_once('}').map(|(name, _)| name))
+            .collect()
+    }
+
+    #[test]
+    fn test_catalogs_have_the_same_keys_and_placeholders() {
+        for locale in Locale::ALL {
+            let catalog = locale.catalog();
+
+            let missing: Vec<_> = EN.keys().filter(|k| !catalog.contains_key(*k)).collect();
+            let extra: Vec<_> = catalog.keys().filter(|k| !EN.contains_key(*k)).collect();
+            assert!(missing.is_empty(), "{} lacks {:?}", locale.tag(), missing);
+            assert!(extra.is_empty(), "{} has unknown {:?}", locale.tag(), extra);
+
+            for (key, message) in catalog {
+                assert_eq!(
+                    placeholders(message),
+                    placeholders(&EN[key]),
+                    "{} of {}",
+                    key,
+                    locale.tag()
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_negotiate_honors_quality_and_falls_back_to_english() {
+        assert_eq!(Locale::negotiate("pt-BR,pt;q=0.9,en;q=0.8"), Locale::PtBr);
+        assert_eq!(Locale::negotiate("en-US,pt;q=0.5"), Locale::En);
+        assert_eq!(
+            Locale::negotiate("fr;q=1,pt-PT;q=0.7,en;q=0.3"),
+            Locale::PtBr
+        );
+        assert_eq!(Locale::negotiate("pt;q=0,en;q=0.1"), Locale::En);
+        assert_eq!(Locale::negotiate("de, fr"), Locale::En);
+        assert_eq!(Locale::negotiate(""), Locale::En);
+    }
+
+    #[tokio::test]
+    async fn test_messages_follow_the_current_locale() {
+        assert_eq!(t("auth.invalid_credentials"), "Invalid credentials.");
+
+        let message = Locale::PtBr
+            .scope(async { t_args("avatar.too_large", &[("max", &5)]) })
+            .await;
+        assert_eq!(message, "O avatar deve ter no máximo 5 MiB.");
+    }
+}
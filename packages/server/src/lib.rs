@@ -1,15 +1,88 @@
+use config::Config;
+use data_export::DataExportConfig;
+use error::AppError;
+use github::GithubClient;
+use mailer::Mailer;
+use rate_limit::RateLimiter;
 use sea_orm::DatabaseConnection;
+use std::sync::Arc;
+use storage::Storage;
 
+pub mod account_deletion;
+pub mod audit;
 pub mod auth;
+pub mod avatar;
+pub mod challenge;
+pub mod config;
+pub mod data_export;
 pub mod db;
+pub mod db_error;
 pub mod error;
+pub mod extract;
+pub mod github;
+pub mod i18n;
+pub mod mailer;
+pub mod password_policy;
+pub mod rate_limit;
+pub mod request_id;
 pub mod routes;
+pub mod storage;
+pub mod two_factor;
+pub mod versioning;
+pub mod webauthn;
 
 #[derive(Clone)]
 pub struct AppState {
     pub db: DatabaseConnection,
-    pub jwt_secret: String,
-    pub github_client_id: String,
-    pub github_client_secret: String,
-    pub github_oauth_redirect_url: String,
+    pub config: Arc<Config>,
+    /// Only set when the `github` section is configured.
+    pub github: Option<GithubClient>,
+    pub rate_limiter: RateLimiter,
+    pub mailer: Arc<dyn Mailer>,
+    pub storage: Arc<dyn Storage>,
+    pub exports: DataExportConfig,
+}
+
+impl AppState {
+    /// The GitHub client, or a `400` when GitHub login is disabled.
+    pub fn github_client(&self) -> Result<&GithubClient, AppError> {
+        self.github
+            .as_ref()
+            .ok_or_else(|| AppError::bad_request(i18n::t("auth.provider_not_configured")))
+    }
+}
+
+#[cfg(test)]
+pub(crate) fn test_state() -> AppState {
+    use rate_limit::RateLimitConfig;
+
+    AppState {
+        db: DatabaseConnection::Disconnected,
+        config: Arc::new(Config {
+            jwt_secret: "secret".into(),
+            uploads: config::UploadsConfig {
+                dir: std::env::temp_dir().join("uploads"),
+                public_url: "http://localhost:3000/uploads".into(),
+            },
+            ..Config::default()
+        }),
+        github: Some(GithubClient::new(
+            String::new(),
+            String::new(),
+            String::new(),
+        )),
+        rate_limiter: RateLimiter::in_memory(RateLimitConfig::default()),
+        mailer: Arc::new(mailer::LogMailer::default()),
+        storage: Arc::new(storage::LocalStorage::new(
+            std::env::temp_dir().join("uploads"),
+            "http://localhost:3000/uploads",
+        )),
+        exports: DataExportConfig {
+            storage: Arc::new(storage::LocalStorage::new(
+                std::env::temp_dir().join("exports"),
+                "",
+            )),
+            download_url: "http://localhost:3000/v1/profile/export".into(),
+        },
+    }
 }
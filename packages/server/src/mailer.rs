@@ -0,0 +1,70 @@
+//! Outgoing e-mail. The default [`LogMailer`] only logs messages; a real
+//! transport (SMTP, an e-mail API) can be plugged in through [`Mailer`].
+
+use crate::i18n::{Args, Locale};
+use std::{fmt, future::Future, pin::Pin};
+use tracing::{error, info};
+
+pub type MailFuture<'a> = Pin<Box<dyn Future<Output = Result<(), MailError>> + Send + 'a>>;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Email {
+    pub to: String,
+    pub subject: String,
+    pub body: String,
+}
+
+#[derive(Debug)]
+pub struct MailError(pub String);
+
+impl Email {
+    /// Builds the e-mail from the `email.<template>.subject` and `.body`
+    /// messages, in the locale of the current request.
+    pub fn from_template(to: impl Into<String>, template: &str, args: Args) -> Self {
+        let locale = Locale::current();
+
+        Email {
+            to: to.into(),
+            subject: locale.format(&format!("email.{}.subject", template), args),
+            body: locale.format(&format!("email.{}.body", template), args),
+        }
+    }
+}
+
+impl fmt::Display for MailError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+pub trait Mailer: Send + Sync {
+    fn send(&self, email: Email) -> MailFuture<'_>;
+}
+
+/// Writes the messages to the log, for development.
+#[derive(Debug, Default)]
+pub struct LogMailer {
+    /// `mail.from` of the config, when set.
+    pub from: Option<String>,
+}
+
+impl Mailer for LogMailer {
+    fn send(&self, email: Email) -> MailFuture<'_> {
+        let from = self.from.as_deref().unwrap_or("-");
+        info!(
+            "E-mail from {} to {} | {}\n{}",
+            from, email.to, email.subject, email.body
+        );
+        Box::pin(async { Ok(()) })
+    }
+}
+
+/// Sends a notification whose failure must not fail the request that
+/// triggered it.
+pub async fn send_best_effort(mailer: &dyn Mailer, email: Email) {
+    let to = email.to.clone();
+
+    if let Err(e) = mailer.send(email).await {
+        error!("Failed to send e-mail to {}: {}", to, e);
+    }
+}
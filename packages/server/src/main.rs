@@ -1,68 +1,129 @@
+use clap::{Parser, Subcommand};
+use sea_orm::DatabaseConnection;
 use server::{
-    db,
-    routes::{
-        auth::{
-            authenticate_with_github::authenticate_with_github,
-            authenticate_with_password::authenticate_with_password, create_account::create_account,
-            get_profile::get_profile, request_password_recover::request_password_recover,
-            reset_password::reset_password,
-        },
-        doc::{doc, openapi_spec_handler},
-        members::get_members::get_members,
-    },
+    account_deletion, challenge,
+    config::Config,
+    data_export::{self, DataExportConfig},
+    db::{self, Migrate},
+    github::GithubClient,
+    mailer::LogMailer,
+    rate_limit::RateLimiter,
+    routes::{app, doc::openapi_json},
+    storage::LocalStorage,
+    versioning::ApiVersion,
     AppState,
 };
-use std::net::SocketAddr;
+use std::{net::SocketAddr, sync::Arc};
 use tracing::info;
 
-use axum::{
-    routing::{get, post},
-    Router,
-};
+#[derive(Parser)]
+#[command(about = "Serves the API")]
+struct Cli {
+    /// Apply pending migrations before serving
+    #[arg(long)]
+    migrate: bool,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Manage the database schema, then exit
+    #[command(subcommand)]
+    Migrate(MigrateCommand),
+}
+
+#[derive(Subcommand)]
+enum MigrateCommand {
+    /// Apply every pending migration
+    Up,
+    /// Roll back the last applied migrations
+    Down {
+        #[arg(default_value_t = 1)]
+        steps: u32,
+    },
+    /// List the migrations and whether they're applied
+    Status,
+    /// Replace every user and organization with the development data
+    Seed,
+}
 
 #[tokio::main]
 async fn main() {
+    let cli = Cli::parse();
+
     tracing_subscriber::fmt()
         .with_env_filter(tracing_subscriber::EnvFilter::from_default_env())
         .init();
 
-    let db_pool = db::connect_db()
+    let config = Config::load().unwrap_or_else(|errors| {
+        eprintln!("{}", errors);
+        std::process::exit(1);
+    });
+
+    let db_pool = db::connect_db(&config)
         .await
         .expect("Couldn't connect to database");
 
-    let jwt_secret = std::env::var("JWT_SECRET").expect("JWT_SECRET must be set");
-    let github_client_id = std::env::var("GITHUB_CLIENT_ID").expect("GITHUB_CLIENT_ID must be set");
-    let github_client_secret =
-        std::env::var("GITHUB_CLIENT_SECRET").expect("GITHUB_CLIENT_SECRET must be set");
-    let github_oauth_redirect_url =
-        std::env::var("GITHUB_OAUTH_REDIRECT_URL").expect("GITHUB_OAUTH_REDIRECT_URL must be set");
+    if let Some(Command::Migrate(command)) = cli.command {
+        return run_migrate(&db_pool, command).await;
+    }
+
+    if cli.migrate {
+        migrate_or_exit(&db_pool, Migrate::Up).await;
+    }
+
+    let pending = db::pending_migrations(&db_pool)
+        .await
+        .expect("Couldn't read the applied migrations");
+    if !pending.is_empty() {
+        eprintln!(
+            "The database schema is behind, pending migrations: {}\n\
+             Run `server migrate up` or start with `--migrate`.",
+            pending.join(", ")
+        );
+        std::process::exit(1);
+    }
+
+    let exports = DataExportConfig {
+        storage: Arc::new(LocalStorage::new(&config.exports.dir, "")),
+        download_url: config.exports.download_url.clone(),
+    };
+
+    tokio::spawn(account_deletion::run_purge_job(db_pool.clone()));
+    tokio::spawn(challenge::run_cleanup_job(db_pool.clone()));
+    tokio::spawn(data_export::run_cleanup_job(
+        db_pool.clone(),
+        exports.storage.clone(),
+    ));
 
     let app_state = AppState {
         db: db_pool,
-        jwt_secret,
-        github_client_id,
-        github_client_secret,
-        github_oauth_redirect_url,
+        github: config.github.as_ref().map(|github| {
+            GithubClient::new(
+                github.client_id.clone(),
+                github.client_secret.clone(),
+                github.oauth_redirect_url.clone(),
+            )
+        }),
+        rate_limiter: RateLimiter::in_memory(config.rate_limit.clone()),
+        mailer: Arc::new(LogMailer {
+            from: config.mail.as_ref().map(|mail| mail.from.clone()),
+        }),
+        storage: Arc::new(LocalStorage::new(
+            &config.uploads.dir,
+            &config.uploads.public_url,
+        )),
+        exports,
+        config: Arc::new(config),
     };
+    let port = app_state.config.port;
 
-    let port: u16 = std::env::var("PORT")
-        .unwrap_or("3000".into())
-        .parse()
-        .expect("PORT not found");
-
-    // build our application with a single route
-    let app = Router::new()
-        .route("/", get(|| async { "Hello, World!" }))
-        .route("/doc", get(doc))
-        .route("/openapi.json", get(openapi_spec_handler))
-        .route("/users", post(create_account))
-        .route("/sessions/password", post(authenticate_with_password))
-        .route("/sessions/github", post(authenticate_with_github))
-        .route("/profile", get(get_profile))
-        .route("/password/recover", post(request_password_recover))
-        .route("/password/reset", post(reset_password))
-        .route("/organizations/{slug}/members", get(get_members))
-        .with_state(app_state);
+    // Gera as especificações antes de aceitar requisições
+    openapi_json(ApiVersion::LATEST);
+
+    let app = app(app_state);
 
     // run our app with hyper, listening globally on port 3000
     let addr = SocketAddr::from(([0, 0, 0, 0], port));
@@ -71,5 +132,42 @@ async fn main() {
 
     // run our app with hyper, listening globally on port 3000
     let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
-    axum::serve(listener, app).await.unwrap();
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .await
+    .unwrap();
+}
+
+async fn run_migrate(db: &DatabaseConnection, command: MigrateCommand) {
+    match command {
+        MigrateCommand::Up => migrate_or_exit(db, Migrate::Up).await,
+        MigrateCommand::Down { steps } => migrate_or_exit(db, Migrate::Down { steps }).await,
+        MigrateCommand::Seed => {
+            if let Err(e) = db::seed(db).await {
+                eprintln!("Seeding failed: {}", e);
+                std::process::exit(1);
+            }
+        }
+        MigrateCommand::Status => {
+            let status = db::migration_status(db)
+                .await
+                .expect("Couldn't read the applied migrations");
+            for (name, applied) in status {
+                println!(
+                    "{:<8} {}",
+                    if applied { "applied" } else { "pending" },
+                    name
+                );
+            }
+        }
+    }
+}
+
+async fn migrate_or_exit(db: &DatabaseConnection, direction: Migrate) {
+    if let Err(e) = db::migrate(db, direction).await {
+        eprintln!("Migration failed: {}", e);
+        std::process::exit(1);
+    }
 }
@@ -0,0 +1,265 @@
+//! Rules applied whenever a user picks a new password.
+
+use crate::{
+    error::{AppError, ErrorDetail, FieldErrors},
+    i18n::{t, t_args},
+};
+use sha1::{Digest, Sha1};
+use std::{io::ErrorKind, path::PathBuf};
+use tracing::error;
+
+/// Known-breached passwords stored as k-anonymity range files: one file per
+/// 5-character SHA-1 prefix (`<PREFIX>.txt`), each line holding the remaining
+/// 35 characters of a hash, optionally followed by `:<count>`. This is the
+/// layout produced by the Have I Been Pwned range downloader.
+#[derive(Debug, Clone)]
+pub struct BreachedPasswords {
+    dir: PathBuf,
+}
+
+impl BreachedPasswords {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// Only the range file of the hash prefix is read, so the list can be far
+    /// bigger than what fits in memory.
+    pub async fn contains(&self, password: &str) -> bool {
+        let hash = Sha1::digest(password.as_bytes())
+            .iter()
+            .map(|b| format!("{:02X}", b))
+            .collect::<String>();
+        let (prefix, suffix) = hash.split_at(5);
+
+        let range = match tokio::fs::read_to_string(self.dir.join(format!("{}.txt", prefix))).await
+        {
+            Ok(range) => range,
+            Err(e) if e.kind() == ErrorKind::NotFound => return false,
+            Err(e) => {
+                // Não bloqueia o cadastro se a lista estiver indisponível
+                error!("Failed to read breached passwords range {}: {}", prefix, e);
+                return false;
+            }
+        };
+
+        range.lines().any(|line| {
+            line.split(':')
+                .next()
+                .is_some_and(|candidate| candidate.trim().eq_ignore_ascii_case(suffix))
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PasswordPolicy {
+    pub min_length: usize,
+    pub require_lowercase: bool,
+    pub require_uppercase: bool,
+    pub require_digit: bool,
+    pub require_symbol: bool,
+    /// Reject passwords containing the user's e-mail or name.
+    pub reject_personal_info: bool,
+    pub breached: Option<BreachedPasswords>,
+}
+
+impl Default for PasswordPolicy {
+    fn default() -> Self {
+        Self {
+            min_length: 8,
+            require_lowercase: true,
+            require_uppercase: true,
+            require_digit: true,
+            require_symbol: false,
+            reject_personal_info: true,
+            breached: None,
+        }
+    }
+}
+
+fn violation(rule: &str, message: impl Into<String>) -> ErrorDetail {
+    ErrorDetail {
+        rule: rule.to_string(),
+        message: message.into(),
+    }
+}
+
+/// Parts of the e-mail and name that shouldn't appear in the password.
+fn personal_terms(email: &str, name: Option<&str>) -> Vec<String> {
+    let email = email.to_lowercase();
+    let local_part = email.split('@').next().unwrap_or_default().to_string();
+
+    let mut terms = vec![email.clone(), local_part];
+    if let Some(name) = name {
+        terms.extend(name.split_whitespace().map(str::to_lowercase));
+    }
+
+    terms.retain(|term| term.chars().count() >= 3);
+    terms
+}
+
+impl PasswordPolicy {
+    /// Returns every rule the password breaks.
+    pub async fn violations(
+        &self,
+        password: &str,
+        email: &str,
+        name: Option<&str>,
+    ) -> Vec<ErrorDetail> {
+        let mut violations = Vec::new();
+
+        if password.chars().count() < self.min_length {
+            violations.push(violation(
+                "minLength",
+                t_args("password_policy.min_length", &[("min", &self.min_length)]),
+            ));
+        }
+
+        let classes = [
+            (
+                self.require_lowercase,
+                "lowercase",
+                "password_policy.lowercase",
+                password.chars().any(char::is_lowercase),
+            ),
+            (
+                self.require_uppercase,
+                "uppercase",
+                "password_policy.uppercase",
+                password.chars().any(char::is_uppercase),
+            ),
+            (
+                self.require_digit,
+                "digit",
+                "password_policy.digit",
+                password.chars().any(|c| c.is_ascii_digit()),
+            ),
+            (
+                self.require_symbol,
+                "symbol",
+                "password_policy.symbol",
+                password.chars().any(|c| !c.is_alphanumeric()),
+            ),
+        ];
+
+        for (required, rule, key, present) in classes {
+            if required && !present {
+                violations.push(violation(rule, t(key)));
+            }
+        }
+
+        if self.reject_personal_info {
+            let password = password.to_lowercase();
+
+            if personal_terms(email, name)
+                .iter()
+                .any(|term| password.contains(term.as_str()))
+            {
+                violations.push(violation(
+                    "personalInfo",
+                    t("password_policy.personal_info"),
+                ));
+            }
+        }
+
+        if let Some(breached) = &self.breached {
+            if breached.contains(password).await {
+                violations.push(violation("breached", t("password_policy.breached")));
+            }
+        }
+
+        violations
+    }
+
+    /// Same as [`Self::violations`], as a `400` listing the broken rules
+    /// under `field`, the name of the password in the request body.
+    pub async fn enforce(
+        &self,
+        field: &str,
+        password: &str,
+        email: &str,
+        name: Option<&str>,
+    ) -> Result<(), AppError> {
+        let violations = self.violations(password, email, name).await;
+
+        if violations.is_empty() {
+            return Ok(());
+        }
+
+        Err(AppError::validation(
+            t("password_policy.failed"),
+            FieldErrors::from([(field.to_owned(), violations)]),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules(violations: Vec<ErrorDetail>) -> Vec<String> {
+        violations.into_iter().map(|v| v.rule).collect()
+    }
+
+    #[tokio::test]
+    async fn test_reports_every_broken_rule() {
+        let policy = PasswordPolicy {
+            require_symbol: true,
+            ..Default::default()
+        };
+
+        let violations = policy.violations("john", "john@acme.com", None).await;
+
+        assert_eq!(
+            rules(violations),
+            ["minLength", "uppercase", "digit", "symbol", "personalInfo"]
+        );
+        assert!(policy
+            .violations("Str0ng!Passw0rd", "john@acme.com", Some("John Doe"))
+            .await
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_rejects_name_and_email() {
+        let policy = PasswordPolicy::default();
+
+        for password in ["MyDoe2024pass", "Xjohn.doe@acme.com1"] {
+            assert_eq!(
+                rules(
+                    policy
+                        .violations(password, "john.doe@acme.com", Some("John Doe"))
+                        .await
+                ),
+                ["personalInfo"]
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_breached_range_lookup() {
+        let dir = std::env::temp_dir().join(format!("breached-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        // SHA-1("Password1") = 70CCD9007338D6D81DD3B6271621B9CF9A97EA00
+        std::fs::write(
+            dir.join("70CCD.txt"),
+            "0000000000000000000000000000000000A:1\r\n9007338d6d81dd3b6271621b9cf9a97ea00:111658\r\n",
+        )
+        .unwrap();
+
+        let policy = PasswordPolicy {
+            breached: Some(BreachedPasswords::new(&dir)),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            rules(policy.violations("Password1", "john@acme.com", None).await),
+            ["breached"]
+        );
+        assert!(policy
+            .violations("Password2", "john@acme.com", None)
+            .await
+            .is_empty());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,438 @@
+//! Throttling for the unauthenticated auth endpoints: fixed-window limits keyed
+//! by client IP and by target e-mail, plus an exponential lockout after
+//! repeated failed password or two-factor verifications.
+
+use crate::{error::AppError, i18n::t, AppState};
+use axum::{
+    body::{to_bytes, Body},
+    extract::{ConnectInfo, Request, State},
+    http::{Extensions, HeaderMap},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use std::{
+    collections::HashMap,
+    future::Future,
+    net::SocketAddr,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+use tracing::warn;
+
+/// Bodies of the throttled routes are tiny JSON objects.
+const MAX_BODY_BYTES: usize = 64 * 1024;
+/// How often the in-memory store drops expired entries.
+const PRUNE_INTERVAL: Duration = Duration::from_secs(60);
+
+pub type StoreFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// Backend holding the counters. The default is [`MemoryStore`]; a shared
+/// backend (e.g. Redis) can be plugged in when running several instances.
+pub trait RateLimitStore: Send + Sync {
+    /// Counts a hit on `key` inside a fixed window, returning the number of
+    /// hits so far and the time left until the window resets.
+    fn hit<'a>(&'a self, key: &'a str, window: Duration) -> StoreFuture<'a, (u32, Duration)>;
+
+    /// Counts a consecutive failure on `key`, forgotten after `ttl` without
+    /// new failures. Returns the number of consecutive failures.
+    fn record_failure<'a>(&'a self, key: &'a str, ttl: Duration) -> StoreFuture<'a, u32>;
+
+    /// Locks `key` for `duration`.
+    fn lock<'a>(&'a self, key: &'a str, duration: Duration) -> StoreFuture<'a, ()>;
+
+    /// Returns how long `key` is still locked, if it is.
+    fn locked_for<'a>(&'a self, key: &'a str) -> StoreFuture<'a, Option<Duration>>;
+
+    /// Forgets the failures and lock of `key`.
+    fn clear<'a>(&'a self, key: &'a str) -> StoreFuture<'a, ()>;
+}
+
+#[derive(Default)]
+struct MemoryState {
+    windows: HashMap<String, (u32, Instant)>,
+    failures: HashMap<String, (u32, Instant)>,
+    locks: HashMap<String, Instant>,
+    last_prune: Option<Instant>,
+}
+
+impl MemoryState {
+    fn prune(&mut self, now: Instant) {
+        if self
+            .last_prune
+            .is_some_and(|at| now.duration_since(at) < PRUNE_INTERVAL)
+        {
+            return;
+        }
+
+        self.windows.retain(|_, (_, resets_at)| *resets_at > now);
+        self.failures.retain(|_, (_, expires_at)| *expires_at > now);
+        self.locks.retain(|_, until| *until > now);
+        self.last_prune = Some(now);
+    }
+}
+
+/// Process-local store. Counters are lost on restart and not shared between
+/// instances.
+#[derive(Default)]
+pub struct MemoryStore {
+    state: Mutex<MemoryState>,
+}
+
+impl MemoryStore {
+    fn with_state<T>(&self, f: impl FnOnce(&mut MemoryState, Instant) -> T) -> T {
+        let now = Instant::now();
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.prune(now);
+        f(&mut state, now)
+    }
+}
+
+impl RateLimitStore for MemoryStore {
+    fn hit<'a>(&'a self, key: &'a str, window: Duration) -> StoreFuture<'a, (u32, Duration)> {
+        let result = self.with_state(|state, now| {
+            let entry = state
+                .windows
+                .entry(key.to_string())
+                .or_insert((0, now + window));
+            if entry.1 <= now {
+                *entry = (0, now + window);
+            }
+            entry.0 += 1;
+            (entry.0, entry.1 - now)
+        });
+        Box::pin(async move { result })
+    }
+
+    fn record_failure<'a>(&'a self, key: &'a str, ttl: Duration) -> StoreFuture<'a, u32> {
+        let result = self.with_state(|state, now| {
+            let entry = state.failures.entry(key.to_string()).or_insert((0, now));
+            if entry.1 <= now {
+                entry.0 = 0;
+            }
+            *entry = (entry.0 + 1, now + ttl);
+            entry.0
+        });
+        Box::pin(async move { result })
+    }
+
+    fn lock<'a>(&'a self, key: &'a str, duration: Duration) -> StoreFuture<'a, ()> {
+        self.with_state(|state, now| {
+            state.locks.insert(key.to_string(), now + duration);
+        });
+        Box::pin(async {})
+    }
+
+    fn locked_for<'a>(&'a self, key: &'a str) -> StoreFuture<'a, Option<Duration>> {
+        let result = self.with_state(|state, now| {
+            state
+                .locks
+                .get(key)
+                .filter(|until| **until > now)
+                .map(|until| *until - now)
+        });
+        Box::pin(async move { result })
+    }
+
+    fn clear<'a>(&'a self, key: &'a str) -> StoreFuture<'a, ()> {
+        self.with_state(|state, _| {
+            state.failures.remove(key);
+            state.locks.remove(key);
+        });
+        Box::pin(async {})
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Quota {
+    pub limit: u32,
+    pub window: Duration,
+}
+
+#[derive(Debug, Clone)]
+pub struct RateLimitConfig {
+    /// Requests per client IP on each throttled route.
+    pub per_ip: Quota,
+    /// Requests per target e-mail on each throttled route.
+    pub per_email: Quota,
+    /// Failed password or two-factor checks tolerated before the account
+    /// gets locked.
+    pub lockout_threshold: u32,
+    /// First lockout duration, doubled on every further failure.
+    pub lockout_base: Duration,
+    pub lockout_max: Duration,
+    /// Use the first `X-Forwarded-For` address as the client IP. Only enable
+    /// behind a proxy that sets the header.
+    pub trust_forwarded_for: bool,
+}
+
+impl Default for RateLimitConfig {
+    fn default() -> Self {
+        Self {
+            per_ip: Quota {
+                limit: 20,
+                window: Duration::from_secs(60),
+            },
+            per_email: Quota {
+                limit: 5,
+                window: Duration::from_secs(60),
+            },
+            lockout_threshold: 5,
+            lockout_base: Duration::from_secs(30),
+            lockout_max: Duration::from_secs(60 * 60),
+            trust_forwarded_for: false,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct RateLimiter {
+    store: Arc<dyn RateLimitStore>,
+    config: RateLimitConfig,
+}
+
+impl RateLimiter {
+    pub fn new(store: Arc<dyn RateLimitStore>, config: RateLimitConfig) -> Self {
+        Self { store, config }
+    }
+
+    pub fn in_memory(config: RateLimitConfig) -> Self {
+        Self::new(Arc::new(MemoryStore::default()), config)
+    }
+
+    /// Counts a request against `quota`, returning the time to wait if the
+    /// quota is exhausted.
+    async fn check(&self, key: &str, quota: Quota) -> Option<Duration> {
+        let (hits, resets_in) = self.store.hit(key, quota.window).await;
+        (hits > quota.limit).then_some(resets_in)
+    }
+
+    /// Returns how long logins for `email` are still locked.
+    pub async fn lockout(&self, email: &str) -> Option<Duration> {
+        self.store.locked_for(&lockout_key(email)).await
+    }
+
+    /// Registers a failed password or two-factor check, locking the account
+    /// once the threshold is reached. Returns the lockout duration, if any.
+    pub async fn record_login_failure(&self, email: &str) -> Option<Duration> {
+        let key = lockout_key(email);
+        let failures = self
+            .store
+            .record_failure(&key, self.config.lockout_max)
+            .await;
+
+        if failures < self.config.lockout_threshold {
+            return None;
+        }
+
+        let exponent = (failures - self.config.lockout_threshold).min(16);
+        let duration = self
+            .config
+            .lockout_base
+            .saturating_mul(1 << exponent)
+            .min(self.config.lockout_max);
+
+        warn!(
+            "Locking login for {} after {} failed attempts",
+            email, failures
+        );
+        self.store.lock(&key, duration).await;
+
+        Some(duration)
+    }
+
+    pub async fn record_login_success(&self, email: &str) {
+        self.store.clear(&lockout_key(email)).await;
+    }
+
+    /// Address of the client, from `X-Forwarded-For` when behind a trusted
+    /// proxy and from the connection otherwise.
+    pub(crate) fn client_ip(&self, headers: &HeaderMap, extensions: &Extensions) -> Option<String> {
+        let forwarded = self
+            .config
+            .trust_forwarded_for
+            .then(|| {
+                headers
+                    .get("x-forwarded-for")
+                    .and_then(|value| value.to_str().ok())
+                    .and_then(|value| value.split(',').next())
+                    .map(|ip| ip.trim().to_string())
+            })
+            .flatten();
+
+        forwarded.or_else(|| {
+            extensions
+                .get::<ConnectInfo<SocketAddr>>()
+                .map(|ConnectInfo(addr)| addr.ip().to_string())
+        })
+    }
+}
+
+fn lockout_key(email: &str) -> String {
+    format!("lockout:{}", email.to_lowercase())
+}
+
+/// Buffers the JSON body to read its `email` field, handing back an
+/// equivalent request.
+async fn take_email(req: Request) -> Result<(Request, Option<String>), Response> {
+    let (parts, body) = req.into_parts();
+
+    let bytes = to_bytes(body, MAX_BODY_BYTES)
+        .await
+        .map_err(|_| AppError::PayloadTooLarge(t("request.body_too_large")).into_response())?;
+
+    let email = serde_json::from_slice::<serde_json::Value>(&bytes)
+        .ok()
+        .and_then(|json| json.get("email")?.as_str().map(str::to_lowercase));
+
+    Ok((Request::from_parts(parts, Body::from(bytes)), email))
+}
+
+/// Limits requests to the wrapped route per client IP and per target e-mail.
+pub async fn rate_limit(State(state): State<AppState>, req: Request, next: Next) -> Response {
+    let limiter = &state.rate_limiter;
+    // Sem o prefixo da versão dentro de `nest`: `/v1` e o alias antigo
+    // dividem a mesma cota
+    let path = req.uri().path().to_string();
+
+    let ip = limiter
+        .client_ip(req.headers(), req.extensions())
+        .unwrap_or_else(|| "unknown".to_string());
+    let ip_key = format!("ip:{}:{}", path, ip);
+    if let Some(retry_after) = limiter.check(&ip_key, limiter.config.per_ip).await {
+        return AppError::TooManyRequests { retry_after }.into_response();
+    }
+
+    let (req, email) = match take_email(req).await {
+        Ok(taken) => taken,
+        Err(response) => return response,
+    };
+
+    if let Some(email) = email {
+        let email_key = format!("email:{}:{}", path, email);
+        if let Some(retry_after) = limiter.check(&email_key, limiter.config.per_email).await {
+            return AppError::TooManyRequests { retry_after }.into_response();
+        }
+    }
+
+    next.run(req).await
+}
+
+/// Rejects password logins for accounts locked by repeated failures.
+pub async fn login_lockout(State(state): State<AppState>, req: Request, next: Next) -> Response {
+    let (req, email) = match take_email(req).await {
+        Ok(taken) => taken,
+        Err(response) => return response,
+    };
+
+    if let Some(email) = email {
+        if let Some(retry_after) = state.rate_limiter.lockout(&email).await {
+            return AppError::TooManyRequests { retry_after }.into_response();
+        }
+    }
+
+    next.run(req).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_state;
+    use axum::{http::StatusCode, middleware::from_fn_with_state, routing::post, Router};
+    use serde_json::json;
+
+    fn limiter() -> RateLimiter {
+        RateLimiter::in_memory(RateLimitConfig {
+            per_ip: Quota {
+                limit: 3,
+                window: Duration::from_secs(60),
+            },
+            per_email: Quota {
+                limit: 2,
+                window: Duration::from_secs(60),
+            },
+            lockout_threshold: 3,
+            lockout_base: Duration::from_secs(10),
+            lockout_max: Duration::from_secs(35),
+            trust_forwarded_for: true,
+        })
+    }
+
+    #[tokio::test]
+    async fn test_window_resets_after_expiry() {
+        let store = MemoryStore::default();
+        let window = Duration::from_millis(50);
+
+        assert_eq!(store.hit("key", window).await.0, 1);
+        assert_eq!(store.hit("key", window).await.0, 2);
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        assert_eq!(store.hit("key", window).await.0, 1);
+    }
+
+    #[tokio::test]
+    async fn test_lockout_grows_exponentially_up_to_max() {
+        let limiter = limiter();
+        let email = "John@Acme.com";
+
+        assert_eq!(limiter.record_login_failure(email).await, None);
+        assert_eq!(limiter.record_login_failure(email).await, None);
+        assert_eq!(
+            limiter.record_login_failure(email).await,
+            Some(Duration::from_secs(10))
+        );
+        assert_eq!(
+            limiter.record_login_failure(email).await,
+            Some(Duration::from_secs(20))
+        );
+        assert_eq!(
+            limiter.record_login_failure(email).await,
+            Some(Duration::from_secs(35))
+        );
+        assert!(limiter.lockout("john@acme.com").await.is_some());
+
+        limiter.record_login_success(email).await;
+        assert_eq!(limiter.lockout(email).await, None);
+        assert_eq!(limiter.record_login_failure(email).await, None);
+    }
+
+    #[tokio::test]
+    async fn test_middleware_limits_by_ip_and_email() {
+        let state = AppState {
+            rate_limiter: limiter(),
+            ..test_state()
+        };
+
+        let app = Router::new().route(
+            "/sessions/password",
+            post(|| async { StatusCode::NO_CONTENT })
+                .layer(from_fn_with_state(state.clone(), rate_limit)),
+        );
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!(
+            "http://{}/sessions/password",
+            listener.local_addr().unwrap()
+        );
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let client = reqwest::Client::new();
+        let send = |ip: &'static str, email: &'static str| {
+            client
+                .post(&url)
+                .header("x-forwarded-for", ip)
+                .json(&json!({ "email": email, "password": "123456" }))
+                .send()
+        };
+
+        assert_eq!(send("10.0.0.1", "a@acme.com").await.unwrap().status(), 204);
+        assert_eq!(send("10.0.0.2", "a@acme.com").await.unwrap().status(), 204);
+
+        let response = send("10.0.0.3", "A@acme.com").await.unwrap();
+        assert_eq!(response.status(), 429);
+        assert!(response.headers().contains_key("retry-after"));
+
+        assert_eq!(send("10.0.0.1", "b@acme.com").await.unwrap().status(), 204);
+        assert_eq!(send("10.0.0.1", "c@acme.com").await.unwrap().status(), 204);
+        assert_eq!(send("10.0.0.1", "d@acme.com").await.unwrap().status(), 429);
+    }
+}
@@ -0,0 +1,92 @@
+//! Gives every request an id, taken from a sane incoming `X-Request-Id` or
+//! generated, echoed back in the response and in problem details.
+
+use axum::{
+    extract::Request,
+    http::{HeaderName, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+use tracing::Instrument;
+use uuid::Uuid;
+
+pub const REQUEST_ID_HEADER: HeaderName = HeaderName::from_static("x-request-id");
+
+/// Longer ids are replaced, they'd only bloat the logs.
+const MAX_LEN: usize = 128;
+
+tokio::task_local! {
+    static REQUEST_ID: String;
+}
+
+/// Id of the request being handled, if called inside [`request_id`].
+pub fn current() -> Option<String> {
+    REQUEST_ID.try_with(|id| id.clone()).ok()
+}
+
+fn incoming_id(request: &Request) -> Option<String> {
+    let id = request.headers().get(&REQUEST_ID_HEADER)?.to_str().ok()?;
+
+    let sane = !id.is_empty()
+        && id.len() <= MAX_LEN
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+
+    sane.then(|| id.to_owned())
+}
+
+/// Middleware running the rest of the stack with the request id in scope.
+/// Should be the outermost layer so every error carries it.
+pub async fn request_id(request: Request, next: Next) -> Response {
+    let id = incoming_id(&request).unwrap_or_else(|| Uuid::new_v4().to_string());
+    let span = tracing::info_span!("request", id = %id);
+
+    let mut response = REQUEST_ID
+        .scope(id.clone(), next.run(request).instrument(span))
+        .await;
+
+    // Só caracteres visíveis passam por incoming_id, então o header é válido
+    if let Ok(value) = HeaderValue::from_str(&id) {
+        response.headers_mut().insert(REQUEST_ID_HEADER, value);
+    }
+
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::AppError;
+    use axum::{
+        body::{to_bytes, Body},
+        response::IntoResponse,
+    };
+
+    fn request(id: &str) -> Request {
+        Request::get("/")
+            .header(&REQUEST_ID_HEADER, id)
+            .body(Body::empty())
+            .unwrap()
+    }
+
+    #[test]
+    fn test_only_sane_incoming_ids_are_kept() {
+        assert_eq!(incoming_id(&request("abc-123")).as_deref(), Some("abc-123"));
+        assert_eq!(incoming_id(&request("no spaces allowed")), None);
+        assert_eq!(incoming_id(&request(&"a".repeat(MAX_LEN + 1))), None);
+    }
+
+    #[tokio::test]
+    async fn test_problem_details_carry_the_request_id() {
+        let response = REQUEST_ID
+            .scope("abc-123".into(), async {
+                AppError::not_found("Nothing here.").into_response()
+            })
+            .await;
+
+        let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(body["requestId"], "abc-123");
+    }
+}
@@ -1,210 +1,199 @@
-use crate::{auth::Claims, error::ErrorResponse, AppState};
-use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
+use crate::{
+    audit::{AuditContext, AuditEvent},
+    auth::create_session,
+    error::AppError,
+    extract::ValidatedJson,
+    github::{self, OauthFlow},
+    two_factor, AppState,
+};
+use api_types::auth::{
+    AuthenticateWithGithubBody, AuthenticateWithGithubResponse, TwoFactorChallengeResponse,
+};
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Redirect},
+    Json,
+};
 use entities::{accounts, sea_orm_active_enums::AccountProvider, users};
-use jsonwebtoken::{encode, EncodingKey, Header};
-use sea_orm::{ActiveModelTrait, ActiveValue::Set, ColumnTrait, EntityTrait, QueryFilter};
-use serde::{Deserialize, Serialize};
-use time::{Duration, OffsetDateTime};
-use tracing::error;
-use utoipa::ToSchema;
-
-#[derive(Serialize, Deserialize, Debug, Clone, ToSchema)]
-pub struct AuthenticateWithGithubBody {
-    pub code: String,
-}
+use sea_orm::{
+    ActiveModelTrait, ActiveValue::Set, ColumnTrait, DbErr, EntityTrait, QueryFilter,
+    TransactionTrait,
+};
+use serde_json::json;
 
-#[derive(Serialize, ToSchema)]
-pub struct AuthenticateWithGithubResponse {
-    pub token: String,
-}
+const GITHUB_AVATAR_HOST: &str = "https://avatars.githubusercontent.com/";
 
-#[derive(Debug, Deserialize)]
-struct GithubAccessTokenResponse {
-    access_token: String,
-}
+#[utoipa::path(
+    get,
+    path = "/sessions/github",
+    tag = "Auth",
+    responses(
+        (status = 307, description = "Redirect to the GitHub authorize page"),
+        (status = 400, description = "GitHub login not enabled"),
+        (status = 500, description = "Internal server error"),
+    ),
+    security(())
+)]
+/// Start GitHub login
+pub async fn github_authorize(State(state): State<AppState>) -> Result<Redirect, AppError> {
+    let client = state.github_client()?;
+    let oauth_state = github::issue_state(OauthFlow::Login, None, &state).await?;
 
-#[derive(Debug, Deserialize)]
-struct GithubUserResponse {
-    id: i64,
-    name: Option<String>,
-    email: Option<String>,
-    avatar_url: String,
+    Ok(Redirect::temporary(&client.authorize_url(&oauth_state)))
 }
 
 #[utoipa::path(
     post,
     path = "/sessions/github",
     tag = "Auth",
-    request_body = AuthenticateWithGithubBody,
+    request_body(content = AuthenticateWithGithubBody, example = json!({ "code": "e72e16c7e42f292c6912", "state": "eyJhbGciOiJIUzI1NiJ9..." })),
     responses(
         (status = 201, description = "Authenticated successfully", body = AuthenticateWithGithubResponse),
-        (status = 400, description = "Validation error or user without Github"),
-        (status = 403, description = "Invalid credentials"),
-        (status = 500, description = "Internal server error")
-    )
+        (status = 202, description = "Two-factor code required, exchange the MFA token at /sessions/two-factor", body = TwoFactorChallengeResponse),
+        (status = 400, description = "GitHub account without a verified e-mail, or GitHub login not enabled"),
+        (status = 403, description = "Invalid credentials or OAuth state"),
+        (status = 500, description = "Internal server error"),
+        (status = 502, description = "GitHub request failed")
+    ),
+    security(())
 )]
 /// Login with Github
 pub async fn authenticate_with_github(
     State(state): State<AppState>,
-    Json(body): Json<AuthenticateWithGithubBody>,
-) -> impl IntoResponse {
-    let github_token_res = reqwest::Client::new()
-        .post("https://github.com/login/oauth/access_token")
-        .header("Accept", "application/json")
-        .form(&[
-            ("client_id", &state.github_client_id),
-            ("client_secret", &state.github_client_secret),
-            ("redirect_uri", &state.github_oauth_redirect_url),
-            ("code", &body.code),
-        ])
-        .send()
-        .await;
-
-    let github_token_res = match github_token_res {
-        Ok(res) => res,
-        Err(e) => {
-            error!("GitHub token request failed: {}", e);
-            return Err(ErrorResponse::new(
-                StatusCode::INTERNAL_SERVER_ERROR,
-                "GitHub authentication failed",
-            ));
-        }
-    };
+    audit: AuditContext,
+    ValidatedJson(body): ValidatedJson<AuthenticateWithGithubBody>,
+) -> Result<impl IntoResponse, AppError> {
+    let client = state.github_client()?;
+    github::consume_state(OauthFlow::Login, &body.state, None, &state).await?;
+    let github_user = client.fetch_profile(&body.code).await?;
 
-    let token_data: GithubAccessTokenResponse = match github_token_res.json().await {
-        Ok(data) => data,
-        Err(e) => {
-            error!("Invalid GitHub token response: {}", e);
-            return Err(ErrorResponse::new(
-                StatusCode::INTERNAL_SERVER_ERROR,
-                "GitHub authentication failed",
-            ));
-        }
-    };
+    // ======================
+    // 3. Buscar account GitHub já vinculada
+    // ======================
+    let account = accounts::Entity::find()
+        .filter(accounts::Column::Provider.eq(AccountProvider::Github))
+        .filter(accounts::Column::ProviderAccountId.eq(github_user.id.clone()))
+        .find_also_related(users::Entity)
+        .one(&state.db)
+        .await?;
 
-    let github_user_res = reqwest::Client::new()
-        .get("https://api.github.com/user")
-        .header(
-            "Authorization",
-            format!("Bearer {}", token_data.access_token),
-        )
-        .header("User-Agent", "axum-app")
-        .send()
-        .await;
-
-    let github_user_res = match github_user_res {
-        Ok(res) => res,
-        Err(e) => {
-            error!("GitHub user request failed: {}", e);
-            return Err(ErrorResponse::new(
-                StatusCode::INTERNAL_SERVER_ERROR,
-                "GitHub authentication failed",
-            ));
-        }
-    };
+    let user = match account {
+        Some((account, Some(user))) => {
+            // Mantém login e avatar sincronizados com o GitHub a cada login
+            if account.provider_login.as_deref() != Some(github_user.login.as_str()) {
+                let mut account: accounts::ActiveModel = account.into();
+                account.provider_login = Set(Some(github_user.login));
 
-    let github_user: GithubUserResponse = match github_user_res.json().await {
-        Ok(data) => data,
-        Err(e) => {
-            error!("Invalid GitHub user response: {}", e);
-            return Err(ErrorResponse::new(
-                StatusCode::INTERNAL_SERVER_ERROR,
-                "GitHub authentication failed",
-            ));
-        }
-    };
+                account.update(&state.db).await?;
+            }
 
-    let email = match github_user.email {
-        Some(email) => email,
-        None => {
-            return Err(ErrorResponse::new(
-                StatusCode::BAD_REQUEST,
-                "Your GitHub account does not have a public email address",
-            ));
-        }
-    };
+            let uses_github_avatar = user
+                .avatar_url
+                .as_deref()
+                .is_none_or(|url| url.starts_with(GITHUB_AVATAR_HOST));
 
-    let github_id = github_user.id.to_string();
+            if uses_github_avatar && user.avatar_url.as_ref() != Some(&github_user.avatar_url) {
+                let mut user: users::ActiveModel = user.into();
+                user.avatar_url = Set(Some(github_user.avatar_url));
 
-    // ======================
-    // 3. Buscar ou criar usuário
-    // ======================
-    let user = match users::Entity::find()
-        .filter(users::Column::Email.eq(email.clone()))
-        .one(&state.db)
-        .await
-    {
-        Ok(Some(user)) => user,
-        Ok(None) => {
-            let new_user = users::ActiveModel {
-                email: Set(email),
-                name: Set(github_user.name),
-                avatar_url: Set(Some(github_user.avatar_url)),
-                ..Default::default()
-            };
-
-            match new_user.insert(&state.db).await {
-                Ok(user) => user,
-                Err(e) => {
-                    error!("Failed to create user: {}", e);
-                    return Err(ErrorResponse::internal_error());
-                }
+                user.update(&state.db).await?
+            } else {
+                user
             }
         }
-        Err(e) => {
-            error!("User query failed: {}", e);
-            return Err(ErrorResponse::internal_error());
+        Some((account, None)) => {
+            return Err(AppError::internal(format!(
+                "Account {} has no user",
+                account.id
+            )))
+        }
+        None => {
+            // ======================
+            // 4. Buscar ou criar usuário e vincular account GitHub
+            // ======================
+            // O e-mail retornado por `fetch_profile` é sempre verificado pelo
+            // GitHub, então é seguro vincular a um usuário existente.
+            let existing_user = users::Entity::find()
+                .filter(users::Column::Email.eq(github_user.email.clone()))
+                .one(&state.db)
+                .await?;
+
+            let audit = audit.clone();
+
+            state
+                .db
+                .transaction(|txn| {
+                    Box::pin(async move {
+                        let user = match existing_user {
+                            Some(user) => user,
+                            None => {
+                                let new_user = users::ActiveModel {
+                                    email: Set(github_user.email),
+                                    name: Set(github_user.name),
+                                    avatar_url: Set(Some(github_user.avatar_url)),
+                                    ..Default::default()
+                                };
+
+                                let user = new_user.insert(txn).await?;
+
+                                AuditEvent::new("user.created")
+                                    .actor(user.id)
+                                    .target("user", user.id)
+                                    .after(json!({ "email": user.email, "provider": "github" }))
+                                    .record(txn, &audit)
+                                    .await?;
+
+                                user
+                            }
+                        };
+
+                        let new_account = accounts::ActiveModel {
+                            provider: Set(AccountProvider::Github),
+                            provider_account_id: Set(github_user.id),
+                            provider_login: Set(Some(github_user.login)),
+                            user_id: Set(user.id),
+                            ..Default::default()
+                        };
+
+                        let account = new_account.insert(txn).await?;
+
+                        AuditEvent::new("account.linked")
+                            .actor(user.id)
+                            .target("account", account.id)
+                            .after(json!({
+                                "provider": "github",
+                                "login": account.provider_login,
+                            }))
+                            .record(txn, &audit)
+                            .await?;
+
+                        Ok::<_, DbErr>(user)
+                    })
+                })
+                .await?
         }
     };
 
-    // ======================
-    // 4. Buscar ou criar account GitHub
-    // ======================
-    let account = accounts::Entity::find()
-        .filter(accounts::Column::Provider.eq(AccountProvider::Github))
-        .filter(accounts::Column::ProviderAccountId.eq(github_id.clone()))
-        .one(&state.db)
-        .await;
-
-    if let Ok(None) = account {
-        let new_account = accounts::ActiveModel {
-            provider: Set(AccountProvider::Github),
-            provider_account_id: Set(github_id),
-            user_id: Set(user.id),
-            ..Default::default()
-        };
-
-        if let Err(e) = new_account.insert(&state.db).await {
-            error!("Failed to create account: {}", e);
-            return Err(ErrorResponse::internal_error());
-        }
+    // O GitHub substitui só a senha, o segundo fator continua valendo
+    if user.totp_enabled {
+        let mfa_token = two_factor::encode_mfa_token(user.id, &state).await?;
+
+        return Ok((
+            StatusCode::ACCEPTED,
+            Json(TwoFactorChallengeResponse { mfa_token }),
+        )
+            .into_response());
     }
 
     // ======================
     // 5. Gerar JWT
     // ======================
-    let now = OffsetDateTime::now_utc();
-    let exp = now + Duration::days(7);
-
-    let claims = Claims {
-        sub: user.id.to_string(),
-        exp: exp.unix_timestamp(),
-    };
-
-    let token = match encode(
-        &Header::default(),
-        &claims,
-        &EncodingKey::from_secret(state.jwt_secret.as_ref()),
-    ) {
-        Ok(token) => token,
-        Err(e) => {
-            error!("JWT generation failed: {}", e);
-            return Err(ErrorResponse::internal_error());
-        }
-    };
+    let token = create_session(&user, "github", &audit, &state).await?;
 
     Ok((
         StatusCode::CREATED,
         Json(AuthenticateWithGithubResponse { token }),
-    ))
+    )
+        .into_response())
 }
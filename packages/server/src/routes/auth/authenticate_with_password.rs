@@ -1,121 +1,108 @@
-use crate::{auth::Claims, error::ErrorResponse, AppState};
+use crate::{
+    audit::{AuditContext, AuditEvent},
+    auth::create_session,
+    error::AppError,
+    extract::ValidatedJson,
+    i18n::t,
+    two_factor, AppState,
+};
+use api_types::auth::{
+    AuthenticateWithPasswordBody, AuthenticateWithPasswordResponse, TwoFactorChallengeResponse,
+};
 use argon2::{password_hash::PasswordHash, Argon2, PasswordVerifier};
 use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
 use entities::users;
-use jsonwebtoken::{encode, EncodingKey, Header};
 use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
-use serde::{Deserialize, Serialize};
-use time::{Duration, OffsetDateTime};
-use tracing::error;
-use utoipa::ToSchema;
-use validator::Validate;
-
-#[derive(Serialize, Deserialize, Validate, Debug, Clone, ToSchema)]
-pub struct AuthenticateWithPasswordBody {
-    #[validate(email)]
-    pub email: String,
-    #[validate(length(min = 6))]
-    pub password: String,
-}
-
-#[derive(Serialize, ToSchema)]
-pub struct AuthenticateWithPasswordResponse {
-    pub token: String,
-}
+use serde_json::json;
 
 #[utoipa::path(
     post,
     path = "/sessions/password",
     tag = "Auth",
-    request_body = AuthenticateWithPasswordBody,
+    request_body(content = AuthenticateWithPasswordBody, example = json!({ "email": "jane@acme.com", "password": "correct-horse-battery-staple" })),
     responses(
         (status = 201, description = "Authenticated successfully", body = AuthenticateWithPasswordResponse),
+        (status = 202, description = "Two-factor code required, exchange the MFA token at /sessions/two-factor", body = TwoFactorChallengeResponse),
         (status = 400, description = "Validation error or user without password"),
         (status = 403, description = "Invalid credentials"),
+        (status = 429, description = "Too many attempts, retry after the `Retry-After` seconds"),
         (status = 500, description = "Internal server error")
-    )
+    ),
+    security(())
 )]
 /// Login with password & email
 pub async fn authenticate_with_password(
     State(state): State<AppState>,
-    Json(body): Json<AuthenticateWithPasswordBody>,
-) -> impl IntoResponse {
-    if let Err(e) = body.validate() {
-        error!("Validation error: {}", e);
-        return Err(ErrorResponse::new(
-            StatusCode::BAD_REQUEST,
-            format!("Validation error: {}", e),
-        ));
-    }
-
+    audit: AuditContext,
+    ValidatedJson(body): ValidatedJson<AuthenticateWithPasswordBody>,
+) -> Result<impl IntoResponse, AppError> {
     let user = match users::Entity::find()
         .filter(users::Column::Email.eq(body.email.clone()))
         .one(&state.db)
-        .await
+        .await?
     {
-        Ok(Some(user)) => user,
-        Ok(None) => {
-            return Err(ErrorResponse::new(
-                StatusCode::FORBIDDEN,
-                "Invalid credentials.",
-            ));
-        }
-        Err(e) => {
-            error!("Db query error: {}", e);
-            return Err(ErrorResponse::internal_error());
+        Some(user) => user,
+        None => {
+            state.rate_limiter.record_login_failure(&body.email).await;
+
+            AuditEvent::new("session.failed")
+                .after(json!({ "method": "password", "email": body.email }))
+                .record(&state.db, &audit)
+                .await?;
+
+            return Err(AppError::forbidden(t("auth.invalid_credentials")));
         }
     };
 
-    let password_hash = match user.password_hash {
+    let password_hash = match &user.password_hash {
         Some(hash) => hash,
         None => {
-            return Err(ErrorResponse::new(
-                StatusCode::BAD_REQUEST,
-                "User does not have a password, use social login.",
-            ));
+            return Err(AppError::bad_request(t("auth.no_password")));
         }
     };
 
-    let parsed_hash = match PasswordHash::new(&password_hash) {
-        Ok(hash) => hash,
-        Err(e) => {
-            error!("Failed to parse password hash from database: {}", e);
-            return Err(ErrorResponse::internal_error());
-        }
-    };
+    let parsed_hash = PasswordHash::new(password_hash).map_err(|e| {
+        AppError::internal(format!(
+            "Failed to parse password hash from database: {}",
+            e
+        ))
+    })?;
 
     if Argon2::default()
         .verify_password(body.password.as_bytes(), &parsed_hash)
         .is_err()
     {
-        return Err(ErrorResponse::new(
-            StatusCode::FORBIDDEN,
-            "Invalid credentials.",
-        ));
+        state.rate_limiter.record_login_failure(&body.email).await;
+
+        AuditEvent::new("session.failed")
+            .actor(user.id)
+            .target("user", user.id)
+            .after(json!({ "method": "password" }))
+            .record(&state.db, &audit)
+            .await?;
+
+        return Err(AppError::forbidden(t("auth.invalid_credentials")));
     }
 
-    let now = OffsetDateTime::now_utc();
-    let exp = now + Duration::days(7);
+    // Com 2FA as falhas só são esquecidas depois do segundo fator, senão quem
+    // tem a senha zeraria o contador a cada novo MFA token
+    if user.totp_enabled {
+        let mfa_token = two_factor::encode_mfa_token(user.id, &state).await?;
 
-    let claims = Claims {
-        sub: user.id.to_string(),
-        exp: exp.unix_timestamp(),
-    };
+        return Ok((
+            StatusCode::ACCEPTED,
+            Json(TwoFactorChallengeResponse { mfa_token }),
+        )
+            .into_response());
+    }
 
-    let token = match encode(
-        &Header::default(),
-        &claims,
-        &EncodingKey::from_secret(state.jwt_secret.as_ref()),
-    ) {
-        Ok(t) => t,
-        Err(e) => {
-            error!("Failed to generate JWT: {}", e);
-            return Err(ErrorResponse::internal_error());
-        }
-    };
+    state.rate_limiter.record_login_success(&body.email).await;
+
+    let token = create_session(&user, "password", &audit, &state).await?;
 
     Ok((
         StatusCode::CREATED,
         Json(AuthenticateWithPasswordResponse { token }),
-    ))
+    )
+        .into_response())
 }
@@ -0,0 +1,245 @@
+use crate::{
+    audit::{AuditContext, AuditEvent},
+    auth::{encode_session_token, get_current_session, get_current_user_id, is_recent_session},
+    error::AppError,
+    extract::ValidatedJson,
+    i18n::t,
+    mailer::{send_best_effort, Email},
+    AppState,
+};
+use api_types::auth::{ConfirmEmailChangeBody, ConfirmEmailChangeResponse, RequestEmailChangeBody};
+use argon2::{password_hash::PasswordHash, Argon2, PasswordVerifier};
+use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
+use axum_auth::AuthBearer;
+use entities::{sea_orm_active_enums::TokenType, tokens, users};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DbErr, EntityTrait, PaginatorTrait, QueryFilter, Set,
+    TransactionTrait,
+};
+use serde_json::json;
+use time::{Duration, OffsetDateTime};
+
+const CONFIRMATION_TTL_HOURS: i64 = 24;
+
+async fn email_taken(state: &AppState, email: &str) -> Result<bool, AppError> {
+    let count = users::Entity::find()
+        .filter(users::Column::Email.eq(email))
+        .count(&state.db)
+        .await?;
+
+    Ok(count > 0)
+}
+
+#[utoipa::path(
+    post,
+    path = "/profile/email",
+    tag = "Auth",
+    request_body(content = RequestEmailChangeBody, example = json!({ "newEmail": "jane.doe@acme.com", "currentPassword": "correct-horse-battery-staple" })),
+    responses(
+        (status = 202, description = "Confirmation code sent to the new e-mail"),
+        (status = 400, description = "Validation error"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Current password is wrong, or the session of a user without a password is too old"),
+        (status = 429, description = "Too many wrong passwords, retry after the `Retry-After` seconds"),
+        (status = 409, description = "E-mail already in use"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("token" = [])
+    )
+)]
+/// Request an e-mail change
+pub async fn request_email_change(
+    State(state): State<AppState>,
+    audit: AuditContext,
+    AuthBearer(token): AuthBearer,
+    ValidatedJson(body): ValidatedJson<RequestEmailChangeBody>,
+) -> Result<impl IntoResponse, AppError> {
+    let (user_id, claims) = get_current_session(&token, &state).await?;
+
+    let user = match users::Entity::find_by_id(user_id).one(&state.db).await? {
+        Some(user) => user,
+        None => return Err(AppError::unauthorized()),
+    };
+
+    if let Some(password_hash) = &user.password_hash {
+        let parsed_hash = PasswordHash::new(password_hash).map_err(|e| {
+            AppError::internal(format!(
+                "Failed to parse password hash from database: {}",
+                e
+            ))
+        })?;
+
+        // Mesmo bloqueio de change_password
+        if let Some(retry_after) = state.rate_limiter.lockout(&user.email).await {
+            return Err(AppError::TooManyRequests { retry_after });
+        }
+
+        let current_password = body.current_password.as_deref().unwrap_or_default();
+
+        if Argon2::default()
+            .verify_password(current_password.as_bytes(), &parsed_hash)
+            .is_err()
+        {
+            state.rate_limiter.record_login_failure(&user.email).await;
+
+            return Err(AppError::forbidden(t("auth.current_password_wrong")));
+        }
+    } else if !is_recent_session(&claims) {
+        return Err(AppError::forbidden(t("auth.sign_in_again")));
+    }
+
+    // Só evita mandar um código inútil, a confirmação conta com users_email_key
+    if email_taken(&state, &body.new_email).await? {
+        return Err(AppError::conflict(t("email_change.email_taken")));
+    }
+
+    let new_email = body.new_email.clone();
+    let current_email = user.email.clone();
+
+    // Só o pedido mais recente continua válido
+    let confirmation = state
+        .db
+        .transaction(|txn| {
+            Box::pin(async move {
+                tokens::Entity::delete_many()
+                    .filter(tokens::Column::UserId.eq(user_id))
+                    .filter(tokens::Column::Type.eq(TokenType::EmailChange))
+                    .exec(txn)
+                    .await?;
+
+                let confirmation = tokens::ActiveModel {
+                    user_id: Set(user_id),
+                    r#type: Set(TokenType::EmailChange),
+                    payload: Set(Some(new_email.clone())),
+                    ..Default::default()
+                }
+                .insert(txn)
+                .await?;
+
+                AuditEvent::new("email.change_requested")
+                    .actor(user_id)
+                    .target("user", user_id)
+                    .before(json!({ "email": current_email }))
+                    .after(json!({ "email": new_email }))
+                    .record(txn, &audit)
+                    .await?;
+
+                Ok::<_, DbErr>(confirmation)
+            })
+        })
+        .await?;
+
+    state
+        .mailer
+        .send(Email::from_template(
+            body.new_email.clone(),
+            "email_change_confirm",
+            &[
+                ("code", &confirmation.id),
+                ("hours", &CONFIRMATION_TTL_HOURS),
+            ],
+        ))
+        .await
+        .map_err(|e| AppError::internal(format!("Failed to send e-mail confirmation: {}", e)))?;
+
+    send_best_effort(
+        state.mailer.as_ref(),
+        Email::from_template(
+            user.email,
+            "email_change_requested",
+            &[("email", &body.new_email)],
+        ),
+    )
+    .await;
+
+    Ok(StatusCode::ACCEPTED)
+}
+
+#[utoipa::path(
+    post,
+    path = "/profile/email/confirm",
+    tag = "Auth",
+    request_body(content = ConfirmEmailChangeBody, example = json!({ "code": "3f2b8c1e-6d4a-4f7e-9b1a-2c5d8e7f6a90" })),
+    responses(
+        (status = 200, description = "E-mail changed and other sessions revoked", body = ConfirmEmailChangeResponse),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Invalid or expired confirmation code"),
+        (status = 409, description = "E-mail already in use"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("token" = [])
+    )
+)]
+/// Confirm an e-mail change
+pub async fn confirm_email_change(
+    State(state): State<AppState>,
+    audit: AuditContext,
+    AuthBearer(token): AuthBearer,
+    ValidatedJson(body): ValidatedJson<ConfirmEmailChangeBody>,
+) -> Result<impl IntoResponse, AppError> {
+    let user_id = get_current_user_id(&token, &state).await?;
+
+    let invalid_code = || AppError::forbidden(t("email_change.invalid_code"));
+
+    let (confirmation, user) = match tokens::Entity::find_by_id(body.code)
+        .filter(tokens::Column::UserId.eq(user_id))
+        .filter(tokens::Column::Type.eq(TokenType::EmailChange))
+        .find_also_related(users::Entity)
+        .one(&state.db)
+        .await?
+    {
+        Some((confirmation, Some(user))) => (confirmation, user),
+        _ => return Err(invalid_code()),
+    };
+
+    let expires_at = confirmation.created_at.timestamp()
+        + Duration::hours(CONFIRMATION_TTL_HOURS).whole_seconds();
+
+    let new_email = match confirmation.payload.clone() {
+        Some(email) if expires_at > OffsetDateTime::now_utc().unix_timestamp() => email,
+        _ => return Err(invalid_code()),
+    };
+
+    let old_email = user.email.clone();
+    let previous_email = old_email.clone();
+    let session_version = user.session_version + 1;
+
+    let user = state
+        .db
+        .transaction(|txn| {
+            Box::pin(async move {
+                let mut user: users::ActiveModel = user.into();
+                user.email = Set(new_email.clone());
+                // Encerra as outras sessões
+                user.session_version = Set(session_version);
+                let user = user.update(txn).await?;
+
+                tokens::Entity::delete_by_id(confirmation.id)
+                    .exec(txn)
+                    .await?;
+
+                AuditEvent::new("email.changed")
+                    .actor(user.id)
+                    .target("user", user.id)
+                    .before(json!({ "email": previous_email }))
+                    .after(json!({ "email": new_email }))
+                    .record(txn, &audit)
+                    .await?;
+
+                Ok::<_, DbErr>(user)
+            })
+        })
+        .await?;
+
+    send_best_effort(
+        state.mailer.as_ref(),
+        Email::from_template(old_email, "email_changed", &[("email", &user.email)]),
+    )
+    .await;
+
+    let token = encode_session_token(&user, &state)?;
+
+    Ok((StatusCode::OK, Json(ConfirmEmailChangeResponse { token })))
+}
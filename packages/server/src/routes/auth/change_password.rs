@@ -0,0 +1,134 @@
+use crate::{
+    audit::{AuditContext, AuditEvent},
+    auth::{encode_session_token, get_current_session, is_recent_session},
+    error::AppError,
+    extract::ValidatedJson,
+    i18n::t,
+    mailer::{send_best_effort, Email},
+    AppState,
+};
+use api_types::auth::{ChangePasswordBody, ChangePasswordResponse};
+use argon2::{
+    password_hash::{rand_core::OsRng, PasswordHash, SaltString},
+    Argon2, PasswordHasher, PasswordVerifier,
+};
+use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
+use axum_auth::AuthBearer;
+use entities::users;
+use sea_orm::{ActiveModelTrait, DbErr, EntityTrait, Set, TransactionTrait};
+use serde_json::json;
+
+#[utoipa::path(
+    put,
+    path = "/profile/password",
+    tag = "Auth",
+    request_body(content = ChangePasswordBody, example = json!({ "currentPassword": "correct-horse-battery-staple", "newPassword": "tr0ub4dor-and-3-more-words" })),
+    responses(
+        (status = 200, description = "Password changed and other sessions revoked", body = ChangePasswordResponse),
+        (status = 400, description = "Password does not meet the password policy"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Current password is wrong, or the session of a user without a password is too old"),
+        (status = 429, description = "Too many wrong passwords, retry after the `Retry-After` seconds"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("token" = [])
+    )
+)]
+/// Change or set the password
+pub async fn change_password(
+    State(state): State<AppState>,
+    audit: AuditContext,
+    AuthBearer(token): AuthBearer,
+    ValidatedJson(body): ValidatedJson<ChangePasswordBody>,
+) -> Result<impl IntoResponse, AppError> {
+    let (user_id, claims) = get_current_session(&token, &state).await?;
+
+    let user = match users::Entity::find_by_id(user_id).one(&state.db).await? {
+        Some(user) => user,
+        None => return Err(AppError::unauthorized()),
+    };
+
+    // Usuários só com login social definem a senha sem informar a atual, mas
+    // com uma sessão recente, senão um token roubado bastaria para tomar a conta
+    if let Some(password_hash) = &user.password_hash {
+        let parsed_hash = PasswordHash::new(password_hash).map_err(|e| {
+            AppError::internal(format!(
+                "Failed to parse password hash from database: {}",
+                e
+            ))
+        })?;
+
+        // Erros aqui contam para o mesmo bloqueio do login, senão um token
+        // roubado serviria para adivinhar a senha sem limite
+        if let Some(retry_after) = state.rate_limiter.lockout(&user.email).await {
+            return Err(AppError::TooManyRequests { retry_after });
+        }
+
+        let current_password = body.current_password.as_deref().unwrap_or_default();
+
+        if Argon2::default()
+            .verify_password(current_password.as_bytes(), &parsed_hash)
+            .is_err()
+        {
+            state.rate_limiter.record_login_failure(&user.email).await;
+
+            return Err(AppError::forbidden(t("auth.current_password_wrong")));
+        }
+    } else if !is_recent_session(&claims) {
+        return Err(AppError::forbidden(t("auth.sign_in_again")));
+    }
+
+    state
+        .config
+        .password_policy
+        .enforce(
+            "newPassword",
+            &body.new_password,
+            &user.email,
+            user.name.as_deref(),
+        )
+        .await?;
+
+    let salt = SaltString::generate(&mut OsRng);
+
+    let password_hash = Argon2::default()
+        .hash_password(body.new_password.as_bytes(), &salt)
+        .map_err(|e| AppError::internal(format!("Error hashing password: {}", e)))?
+        .to_string();
+
+    let had_password = user.password_hash.is_some();
+    let session_version = user.session_version + 1;
+    let mut user: users::ActiveModel = user.into();
+    user.password_hash = Set(Some(password_hash));
+    // Encerra as outras sessões
+    user.session_version = Set(session_version);
+
+    let user = state
+        .db
+        .transaction(|txn| {
+            Box::pin(async move {
+                let user = user.update(txn).await?;
+
+                AuditEvent::new("password.changed")
+                    .actor(user.id)
+                    .target("user", user.id)
+                    .after(json!({ "hadPassword": had_password }))
+                    .record(txn, &audit)
+                    .await?;
+
+                Ok::<_, DbErr>(user)
+            })
+        })
+        .await?;
+
+    send_best_effort(
+        state.mailer.as_ref(),
+        Email::from_template(user.email.clone(), "password_changed", &[]),
+    )
+    .await;
+
+    let token = encode_session_token(&user, &state)?;
+
+    Ok((StatusCode::OK, Json(ChangePasswordResponse { token })))
+}
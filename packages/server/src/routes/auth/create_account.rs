@@ -1,101 +1,62 @@
-use crate::{error::ErrorResponse, AppState};
+use crate::{
+    audit::{AuditContext, AuditEvent},
+    error::AppError,
+    extract::ValidatedJson,
+    AppState,
+};
+use api_types::auth::{CreateAccountBody, CreateAccountResponse};
 use argon2::{
     password_hash::{rand_core::OsRng, SaltString},
     Argon2, PasswordHasher,
 };
 use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
 use entities::{members, organizations, sea_orm_active_enums::Role, users};
-use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, Set};
-use serde::{Deserialize, Serialize};
-use tracing::error;
-use utoipa::ToSchema;
-use uuid::Uuid;
-use validator::Validate;
-
-#[derive(Serialize, Deserialize, Validate, Debug, Clone, ToSchema)]
-pub struct CreateAccountBody {
-    pub name: String,
-    #[validate(email)]
-    pub email: String,
-    #[validate(length(min = 6))]
-    pub password: String,
-}
-
-#[derive(Serialize, Deserialize, ToSchema)]
-pub struct CreateAccountResponse {
-    #[serde(rename = "userId")]
-    pub user_id: Uuid,
-}
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DbErr, EntityTrait, QueryFilter, Set, TransactionTrait,
+};
+use serde_json::json;
 
 #[utoipa::path(
     post,
     path = "/users",
     tag = "Auth",
-    request_body = CreateAccountBody,
+    request_body(content = CreateAccountBody, example = json!({ "name": "Jane Doe", "email": "jane@acme.com", "password": "correct-horse-battery-staple" })),
     responses(
         (status = 201, description = "User created successfully", body = CreateAccountResponse),
-        (status = 400, description = "Validation error"),
+        (status = 400, description = "Validation error or password does not meet the password policy"),
         (status = 409, description = "User with same e-mail already exists"),
+        (status = 429, description = "Too many requests, retry after the `Retry-After` seconds"),
         (status = 500, description = "Internal server error")
-    )
+    ),
+    security(())
 )]
 /// Create account
 pub async fn create_account(
     State(state): State<AppState>,
-    Json(body): Json<CreateAccountBody>,
-) -> impl IntoResponse {
-    if let Err(e) = body.validate() {
-        error!("Validation error: {}", e);
-        return Err(ErrorResponse::new(
-            StatusCode::BAD_REQUEST,
-            format!("Validation error: {}", e),
-        ));
-    }
-
-    let user_exists = match users::Entity::find()
-        .filter(users::Column::Email.eq(body.email.clone()))
-        .one(&state.db)
-        .await
-    {
-        Ok(user) => user,
-        Err(e) => {
-            error!("Db query error: {}", e);
-            return Err(ErrorResponse::internal_error());
-        }
-    };
-
-    if user_exists.is_some() {
-        return Err(ErrorResponse::new(
-            StatusCode::CONFLICT,
-            "User with same e-mail already exists.",
-        ));
-    }
+    audit: AuditContext,
+    ValidatedJson(body): ValidatedJson<CreateAccountBody>,
+) -> Result<impl IntoResponse, AppError> {
+    state
+        .config
+        .password_policy
+        .enforce("password", &body.password, &body.email, Some(&body.name))
+        .await?;
 
     let domain = body.email.split('@').nth(1).unwrap_or("");
 
-    let auto_join_organization = match organizations::Entity::find()
+    let auto_join_organization = organizations::Entity::find()
         .filter(organizations::Column::Domain.eq(domain))
         .filter(organizations::Column::ShouldAttachUsersByDomain.eq(true))
         .one(&state.db)
-        .await
-    {
-        Ok(org) => org,
-        Err(e) => {
-            error!("Error fetching organization: {}", e);
-            return Err(ErrorResponse::internal_error());
-        }
-    };
+        .await?;
 
     let salt = SaltString::generate(&mut OsRng);
     let argon2 = Argon2::default();
 
-    let password_hash = match argon2.hash_password(body.password.as_bytes(), &salt) {
-        Ok(hash) => hash.to_string(),
-        Err(e) => {
-            error!("Error generating hash: {}", e);
-            return Err(ErrorResponse::internal_error());
-        }
-    };
+    let password_hash = argon2
+        .hash_password(body.password.as_bytes(), &salt)
+        .map_err(|e| AppError::internal(format!("Error generating hash: {}", e)))?
+        .to_string();
 
     let new_user = users::ActiveModel {
         name: Set(Some(body.name.clone())),
@@ -104,27 +65,43 @@ pub async fn create_account(
         ..Default::default()
     };
 
-    let inserted_user = match new_user.insert(&state.db).await {
-        Ok(user) => user,
-        Err(e) => {
-            error!("Error creating user: {}", e);
-            return Err(ErrorResponse::internal_error());
-        }
-    };
+    let inserted_user = state
+        .db
+        .transaction(|txn| {
+            Box::pin(async move {
+                // E-mail repetido viola users_email_key e vira 409
+                let inserted_user = new_user.insert(txn).await?;
+
+                AuditEvent::new("user.created")
+                    .actor(inserted_user.id)
+                    .target("user", inserted_user.id)
+                    .after(json!({ "email": inserted_user.email, "provider": "password" }))
+                    .record(txn, &audit)
+                    .await?;
+
+                if let Some(org) = auto_join_organization {
+                    let new_member = members::ActiveModel {
+                        user_id: Set(inserted_user.id),
+                        organization_id: Set(org.id),
+                        role: Set(Role::Member),
+                        ..Default::default()
+                    };
+
+                    let member = new_member.insert(txn).await?;
 
-    if let Some(org) = auto_join_organization {
-        let new_member = members::ActiveModel {
-            user_id: Set(inserted_user.id),
-            organization_id: Set(org.id),
-            role: Set(Role::Member),
-            ..Default::default()
-        };
+                    AuditEvent::new("member.joined")
+                        .actor(inserted_user.id)
+                        .organization(org.id)
+                        .target("member", member.id)
+                        .after(json!({ "userId": member.user_id, "role": member.role, "via": "domain" }))
+                        .record(txn, &audit)
+                        .await?;
+                }
 
-        if let Err(e) = new_member.insert(&state.db).await {
-            error!("Error adding member: {}", e);
-            return Err(ErrorResponse::internal_error());
-        }
-    }
+                Ok::<_, DbErr>(inserted_user)
+            })
+        })
+        .await?;
 
     Ok((
         StatusCode::CREATED,
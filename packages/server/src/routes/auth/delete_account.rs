@@ -0,0 +1,213 @@
+use crate::{
+    account_deletion::DELETION_GRACE_DAYS,
+    audit::{AuditContext, AuditEvent},
+    auth::{get_current_session, is_recent_session},
+    error::{AppError, ErrorDetail},
+    extract::ValidatedJson,
+    i18n::{t, t_args},
+    mailer::{send_best_effort, Email},
+    AppState,
+};
+use api_types::auth::{DeleteAccountBody, DeleteAccountResponse};
+use argon2::{password_hash::PasswordHash, Argon2, PasswordVerifier};
+use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
+use axum_auth::AuthBearer;
+use chrono::{Duration, Utc};
+use entities::{members, organizations, sea_orm_active_enums::Role, users};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DbErr, EntityTrait, QueryFilter, Set, TransactionTrait,
+};
+use serde_json::json;
+
+#[utoipa::path(
+    delete,
+    path = "/profile",
+    tag = "Auth",
+    request_body(content = DeleteAccountBody, example = json!({ "password": "correct-horse-battery-staple", "transfers": [{ "organization": "acme", "newOwnerId": "9c1d7a4e-2b3f-4e5a-8c6d-0f1e2a3b4c5d" }] })),
+    responses(
+        (status = 202, description = "Deletion scheduled and every session revoked", body = DeleteAccountResponse),
+        (status = 400, description = "Invalid organization transfer"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Re-authentication failed"),
+        (status = 429, description = "Too many wrong passwords, retry after the `Retry-After` seconds"),
+        (status = 409, description = "User still owns organizations, listed in `details`"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("token" = [])
+    )
+)]
+/// Delete the authenticated user's account
+pub async fn delete_account(
+    State(state): State<AppState>,
+    audit: AuditContext,
+    AuthBearer(token): AuthBearer,
+    ValidatedJson(body): ValidatedJson<DeleteAccountBody>,
+) -> Result<impl IntoResponse, AppError> {
+    let (user_id, claims) = get_current_session(&token, &state).await?;
+
+    let user = match users::Entity::find_by_id(user_id).one(&state.db).await? {
+        Some(user) => user,
+        None => return Err(AppError::unauthorized()),
+    };
+
+    match &user.password_hash {
+        Some(password_hash) => {
+            let parsed_hash = PasswordHash::new(password_hash).map_err(|e| {
+                AppError::internal(format!(
+                    "Failed to parse password hash from database: {}",
+                    e
+                ))
+            })?;
+
+            if let Some(retry_after) = state.rate_limiter.lockout(&user.email).await {
+                return Err(AppError::TooManyRequests { retry_after });
+            }
+
+            let password = body.password.as_deref().unwrap_or_default();
+
+            if Argon2::default()
+                .verify_password(password.as_bytes(), &parsed_hash)
+                .is_err()
+            {
+                state.rate_limiter.record_login_failure(&user.email).await;
+
+                return Err(AppError::forbidden(t("auth.password_wrong")));
+            }
+        }
+        None => {
+            if !is_recent_session(&claims) {
+                return Err(AppError::forbidden(t("account_deletion.sign_in_again")));
+            }
+        }
+    }
+
+    let owned_organizations = organizations::Entity::find()
+        .filter(organizations::Column::OwnerId.eq(user.id))
+        .all(&state.db)
+        .await?;
+
+    let mut handoffs = Vec::new();
+
+    for transfer in &body.transfers {
+        let Some(organization) = owned_organizations
+            .iter()
+            .find(|org| org.slug == transfer.organization)
+        else {
+            return Err(AppError::bad_request(t_args(
+                "account_deletion.not_owner",
+                &[("organization", &transfer.organization)],
+            )));
+        };
+
+        let new_owner = match members::Entity::find()
+            .filter(members::Column::OrganizationId.eq(organization.id))
+            .filter(members::Column::UserId.eq(transfer.new_owner_id))
+            .filter(members::Column::UserId.ne(user.id))
+            .one(&state.db)
+            .await?
+        {
+            Some(member) => member,
+            None => {
+                return Err(AppError::bad_request(t_args(
+                    "account_deletion.new_owner_not_member",
+                    &[("organization", &organization.slug)],
+                )));
+            }
+        };
+
+        handoffs.push((organization.clone(), new_owner));
+    }
+
+    let kept: Vec<ErrorDetail> = owned_organizations
+        .iter()
+        .filter(|org| !handoffs.iter().any(|(handed, _)| handed.id == org.id))
+        .map(|org| ErrorDetail {
+            rule: "ownsOrganization".into(),
+            message: org.slug.clone(),
+        })
+        .collect();
+
+    if !kept.is_empty() {
+        return Err(AppError::conflict_with_details(
+            t("account_deletion.owns_organizations"),
+            kept,
+        ));
+    }
+
+    let deletion_scheduled_at = Utc::now() + Duration::days(DELETION_GRACE_DAYS);
+    let session_version = user.session_version + 1;
+    let email = user.email.clone();
+
+    state
+        .db
+        .transaction(|txn| {
+            Box::pin(async move {
+                for (organization, new_owner) in handoffs {
+                    let organization_id = organization.id;
+                    let previous_owner_id = organization.owner_id;
+                    let new_owner_id = new_owner.user_id;
+                    let previous_role = new_owner.role.clone();
+
+                    let mut organization: organizations::ActiveModel = organization.into();
+                    organization.owner_id = Set(new_owner_id);
+                    organization.update(txn).await?;
+
+                    let mut new_owner: members::ActiveModel = new_owner.into();
+                    new_owner.role = Set(Role::Admin);
+                    let new_owner = new_owner.update(txn).await?;
+
+                    AuditEvent::new("organization.ownership_transferred")
+                        .actor(user_id)
+                        .organization(organization_id)
+                        .target("organization", organization_id)
+                        .before(json!({ "ownerId": previous_owner_id }))
+                        .after(json!({ "ownerId": new_owner_id }))
+                        .record(txn, &audit)
+                        .await?;
+
+                    AuditEvent::new("member.role_updated")
+                        .actor(user_id)
+                        .organization(organization_id)
+                        .target("member", new_owner.id)
+                        .before(json!({ "role": previous_role }))
+                        .after(json!({ "role": new_owner.role }))
+                        .record(txn, &audit)
+                        .await?;
+                }
+
+                let mut user: users::ActiveModel = user.into();
+                user.deletion_scheduled_at = Set(Some(deletion_scheduled_at.into()));
+                // Encerra todas as sessões, inclusive a atual
+                user.session_version = Set(session_version);
+                user.update(txn).await?;
+
+                AuditEvent::new("user.deletion_scheduled")
+                    .actor(user_id)
+                    .target("user", user_id)
+                    .after(json!({ "deletionScheduledAt": deletion_scheduled_at }))
+                    .record(txn, &audit)
+                    .await?;
+
+                Ok::<(), DbErr>(())
+            })
+        })
+        .await?;
+
+    send_best_effort(
+        state.mailer.as_ref(),
+        Email::from_template(
+            email,
+            "account_deletion_scheduled",
+            &[("date", &deletion_scheduled_at.format("%Y-%m-%d"))],
+        ),
+    )
+    .await;
+
+    Ok((
+        StatusCode::ACCEPTED,
+        Json(DeleteAccountResponse {
+            deletion_scheduled_at: deletion_scheduled_at.to_rfc3339(),
+        }),
+    ))
+}
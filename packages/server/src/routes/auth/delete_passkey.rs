@@ -0,0 +1,111 @@
+use crate::{
+    audit::{AuditContext, AuditEvent},
+    auth::get_current_user_id,
+    error::AppError,
+    i18n::t,
+    AppState,
+};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+use axum_auth::AuthBearer;
+use entities::{accounts, passkeys, sea_orm_active_enums::AccountProvider, users};
+use sea_orm::{
+    ColumnTrait, DbErr, EntityTrait, ModelTrait, PaginatorTrait, QueryFilter, TransactionTrait,
+};
+use serde_json::json;
+use uuid::Uuid;
+
+#[utoipa::path(
+    delete,
+    path = "/profile/passkeys/{id}",
+    tag = "Auth",
+    params(
+        ("id" = Uuid, Path, description = "Passkey id")
+    ),
+    responses(
+        (status = 204, description = "Passkey removed successfully"),
+        (status = 400, description = "Passkey is the only sign-in method"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Passkey not found"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("token" = [])
+    )
+)]
+/// Remove a passkey
+pub async fn delete_passkey(
+    State(state): State<AppState>,
+    audit: AuditContext,
+    Path(id): Path<Uuid>,
+    AuthBearer(token): AuthBearer,
+) -> Result<impl IntoResponse, AppError> {
+    let user_id = get_current_user_id(&token, &state).await?;
+
+    let user = match users::Entity::find_by_id(user_id).one(&state.db).await? {
+        Some(user) => user,
+        None => return Err(AppError::unauthorized()),
+    };
+
+    let passkeys = passkeys::Entity::find()
+        .filter(passkeys::Column::UserId.eq(user.id))
+        .all(&state.db)
+        .await?;
+
+    let remaining = passkeys.len().saturating_sub(1);
+
+    let passkey = match passkeys.into_iter().find(|p| p.id == id) {
+        Some(passkey) => passkey,
+        None => {
+            return Err(AppError::not_found(t("passkey.not_found")));
+        }
+    };
+
+    // Removing the last passkey also removes the passkey sign-in method
+    if remaining == 0 {
+        let other_accounts = accounts::Entity::find()
+            .filter(accounts::Column::UserId.eq(user.id))
+            .filter(accounts::Column::Provider.ne(AccountProvider::Passkey))
+            .count(&state.db)
+            .await?;
+
+        if other_accounts == 0 && user.password_hash.is_none() {
+            return Err(AppError::bad_request(t("auth.last_sign_in_method_remove")));
+        }
+    }
+
+    state
+        .db
+        .transaction(|txn| {
+            Box::pin(async move {
+                let snapshot =
+                    json!({ "name": passkey.name, "credentialId": passkey.credential_id });
+                let passkey_id = passkey.id;
+
+                passkey.delete(txn).await?;
+
+                AuditEvent::new("passkey.deleted")
+                    .actor(user_id)
+                    .target("passkey", passkey_id)
+                    .before(snapshot)
+                    .record(txn, &audit)
+                    .await?;
+
+                if remaining == 0 {
+                    accounts::Entity::delete_many()
+                        .filter(accounts::Column::UserId.eq(user_id))
+                        .filter(accounts::Column::Provider.eq(AccountProvider::Passkey))
+                        .exec(txn)
+                        .await?;
+                }
+
+                Ok::<(), DbErr>(())
+            })
+        })
+        .await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
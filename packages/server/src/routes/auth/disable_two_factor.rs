@@ -0,0 +1,87 @@
+use crate::{
+    audit::{AuditContext, AuditEvent},
+    auth::get_current_user_id,
+    error::AppError,
+    extract::ValidatedJson,
+    i18n::t,
+    two_factor, AppState,
+};
+use api_types::auth::DisableTwoFactorBody;
+use axum::{extract::State, http::StatusCode, response::IntoResponse};
+use axum_auth::AuthBearer;
+use entities::{recovery_codes, users};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DbErr, EntityTrait, QueryFilter, Set, TransactionTrait,
+};
+use serde_json::json;
+
+#[utoipa::path(
+    delete,
+    path = "/profile/two-factor",
+    tag = "Auth",
+    request_body(content = DisableTwoFactorBody, example = json!({ "code": "492039" })),
+    responses(
+        (status = 204, description = "Two-factor authentication disabled"),
+        (status = 400, description = "Two-factor not enabled or invalid code"),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("token" = [])
+    )
+)]
+/// Disable two-factor authentication
+pub async fn disable_two_factor(
+    State(state): State<AppState>,
+    audit: AuditContext,
+    AuthBearer(token): AuthBearer,
+    ValidatedJson(body): ValidatedJson<DisableTwoFactorBody>,
+) -> Result<impl IntoResponse, AppError> {
+    let user_id = get_current_user_id(&token, &state).await?;
+
+    let user = match users::Entity::find_by_id(user_id).one(&state.db).await? {
+        Some(user) => user,
+        None => return Err(AppError::unauthorized()),
+    };
+
+    let secret = match (&user.totp_secret, user.totp_enabled) {
+        (Some(secret), true) => secret.clone(),
+        _ => {
+            return Err(AppError::bad_request(t("two_factor.not_enabled")));
+        }
+    };
+
+    if !two_factor::accept_code(&state.db, &user, &secret, &body.code).await? {
+        return Err(AppError::bad_request(t("two_factor.invalid_code")));
+    }
+
+    state
+        .db
+        .transaction(|txn| {
+            Box::pin(async move {
+                recovery_codes::Entity::delete_many()
+                    .filter(recovery_codes::Column::UserId.eq(user.id))
+                    .exec(txn)
+                    .await?;
+
+                let mut user: users::ActiveModel = user.into();
+                user.totp_secret = Set(None);
+                user.totp_enabled = Set(false);
+                user.totp_last_step = Set(None);
+                user.update(txn).await?;
+
+                AuditEvent::new("two_factor.disabled")
+                    .actor(user_id)
+                    .target("user", user_id)
+                    .before(json!({ "totpEnabled": true }))
+                    .after(json!({ "totpEnabled": false }))
+                    .record(txn, &audit)
+                    .await?;
+
+                Ok::<(), DbErr>(())
+            })
+        })
+        .await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,107 @@
+use crate::{
+    audit::{AuditContext, AuditEvent},
+    auth::get_current_user_id,
+    error::AppError,
+    extract::ValidatedJson,
+    i18n::t,
+    two_factor, AppState,
+};
+use api_types::auth::{EnableTwoFactorBody, EnableTwoFactorResponse};
+use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
+use axum_auth::AuthBearer;
+use entities::{recovery_codes, users};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DbErr, EntityTrait, QueryFilter, Set, TransactionTrait,
+};
+use serde_json::json;
+
+#[utoipa::path(
+    post,
+    path = "/profile/two-factor/verify",
+    tag = "Auth",
+    request_body(content = EnableTwoFactorBody, example = json!({ "code": "492039" })),
+    responses(
+        (status = 200, description = "Two-factor authentication enabled, recovery codes are only shown once", body = EnableTwoFactorResponse),
+        (status = 400, description = "Enrollment not started or invalid code"),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("token" = [])
+    )
+)]
+/// Confirm two-factor authentication enrollment
+pub async fn enable_two_factor(
+    State(state): State<AppState>,
+    audit: AuditContext,
+    AuthBearer(token): AuthBearer,
+    ValidatedJson(body): ValidatedJson<EnableTwoFactorBody>,
+) -> Result<impl IntoResponse, AppError> {
+    let user_id = get_current_user_id(&token, &state).await?;
+
+    let user = match users::Entity::find_by_id(user_id).one(&state.db).await? {
+        Some(user) => user,
+        None => return Err(AppError::unauthorized()),
+    };
+
+    let secret = match (&user.totp_secret, user.totp_enabled) {
+        (Some(secret), false) => secret.clone(),
+        _ => {
+            return Err(AppError::bad_request(t("two_factor.not_started")));
+        }
+    };
+
+    if !two_factor::accept_code(&state.db, &user, &secret, &body.code).await? {
+        return Err(AppError::bad_request(t("two_factor.invalid_code")));
+    }
+
+    let codes = two_factor::generate_recovery_codes();
+    let hashes: Vec<String> = codes
+        .iter()
+        .map(|code| two_factor::hash_recovery_code(code))
+        .collect();
+    let codes_count = codes.len();
+
+    state
+        .db
+        .transaction(|txn| {
+            Box::pin(async move {
+                recovery_codes::Entity::delete_many()
+                    .filter(recovery_codes::Column::UserId.eq(user.id))
+                    .exec(txn)
+                    .await?;
+
+                recovery_codes::Entity::insert_many(hashes.into_iter().map(|code_hash| {
+                    recovery_codes::ActiveModel {
+                        user_id: Set(user.id),
+                        code_hash: Set(code_hash),
+                        ..Default::default()
+                    }
+                }))
+                .exec(txn)
+                .await?;
+
+                let mut user: users::ActiveModel = user.into();
+                user.totp_enabled = Set(true);
+                user.update(txn).await?;
+
+                AuditEvent::new("two_factor.enabled")
+                    .actor(user_id)
+                    .target("user", user_id)
+                    .before(json!({ "totpEnabled": false }))
+                    .after(json!({ "totpEnabled": true, "recoveryCodes": codes_count }))
+                    .record(txn, &audit)
+                    .await?;
+
+                Ok::<(), DbErr>(())
+            })
+        })
+        .await?;
+
+    Ok((
+        StatusCode::OK,
+        Json(EnableTwoFactorResponse {
+            recovery_codes: codes,
+        }),
+    ))
+}
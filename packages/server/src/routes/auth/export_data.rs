@@ -0,0 +1,175 @@
+use crate::{
+    audit::{AuditContext, AuditEvent},
+    auth::get_current_user_id,
+    data_export::{expiry_cutoff, run_export, EXPORT_TTL_HOURS},
+    error::AppError,
+    i18n::{t, Locale},
+    AppState,
+};
+use axum::{
+    extract::{Path, State},
+    http::{header, StatusCode},
+    response::IntoResponse,
+};
+use axum_auth::AuthBearer;
+use entities::{sea_orm_active_enums::TokenType, tokens};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DbErr, EntityTrait, QueryFilter, Set, TransactionTrait,
+};
+use time::{Duration, OffsetDateTime};
+use tracing::error;
+use uuid::Uuid;
+
+#[utoipa::path(
+    post,
+    path = "/profile/export",
+    tag = "Auth",
+    responses(
+        (status = 202, description = "Export started, a one-time download link is mailed when it's ready"),
+        (status = 401, description = "Unauthorized"),
+        (status = 409, description = "An export is already being built or waiting to be downloaded"),
+        (status = 429, description = "Too many requests, retry after the `Retry-After` seconds"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("token" = [])
+    )
+)]
+/// Export the authenticated user's data
+pub async fn request_data_export(
+    State(state): State<AppState>,
+    audit: AuditContext,
+    AuthBearer(token): AuthBearer,
+) -> Result<impl IntoResponse, AppError> {
+    let user_id = get_current_user_id(&token, &state).await?;
+
+    // O token fica pendente, sem arquivo, até o zip ficar pronto
+    let requested = tokens::Entity::find()
+        .filter(tokens::Column::UserId.eq(user_id))
+        .filter(tokens::Column::Type.eq(TokenType::DataExport))
+        .filter(tokens::Column::CreatedAt.gte(expiry_cutoff()))
+        .one(&state.db)
+        .await?;
+
+    if requested.is_some() {
+        return Err(AppError::conflict(t("data_export.already_requested")));
+    }
+
+    let code = Uuid::new_v4();
+
+    state
+        .db
+        .transaction(|txn| {
+            Box::pin(async move {
+                tokens::ActiveModel {
+                    id: Set(code),
+                    user_id: Set(user_id),
+                    r#type: Set(TokenType::DataExport),
+                    payload: Set(None),
+                    ..Default::default()
+                }
+                .insert(txn)
+                .await?;
+
+                AuditEvent::new("user.data_export_requested")
+                    .actor(user_id)
+                    .target("user", user_id)
+                    .record(txn, &audit)
+                    .await?;
+
+                Ok::<_, DbErr>(())
+            })
+        })
+        .await?;
+
+    // O e-mail sai no idioma de quem pediu
+    tokio::spawn(Locale::current().scope(run_export(state, user_id, code)));
+
+    Ok(StatusCode::ACCEPTED)
+}
+
+#[utoipa::path(
+    get,
+    path = "/profile/export/{code}",
+    tag = "Auth",
+    params(
+        ("code" = Uuid, Path, description = "Code from the download link")
+    ),
+    responses(
+        (status = 200, description = "Zip with `export.json` and one CSV per section", content_type = "application/zip", body = Vec<u8>),
+        (status = 404, description = "Export not found, expired or already downloaded"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(())
+)]
+/// Download a data export through its one-time link
+pub async fn download_data_export(
+    State(state): State<AppState>,
+    audit: AuditContext,
+    Path(code): Path<Uuid>,
+) -> Result<impl IntoResponse, AppError> {
+    let not_found = || AppError::not_found(t("data_export.not_found"));
+
+    let export = match tokens::Entity::find_by_id(code)
+        .filter(tokens::Column::Type.eq(TokenType::DataExport))
+        .one(&state.db)
+        .await?
+    {
+        Some(export) => export,
+        None => return Err(not_found()),
+    };
+
+    let expires_at =
+        export.created_at.timestamp() + Duration::hours(EXPORT_TTL_HOURS).whole_seconds();
+
+    // Arquivos expirados ficam para o job de limpeza
+    let Some(key) = export
+        .payload
+        .filter(|_| expires_at > OffsetDateTime::now_utc().unix_timestamp())
+    else {
+        return Err(not_found());
+    };
+
+    let (export_id, owner_id) = (export.id, export.user_id);
+
+    // Apagar o token antes de ler garante que duas requisições não baixem o mesmo arquivo
+    let deleted = state
+        .db
+        .transaction(|txn| {
+            Box::pin(async move {
+                let deleted = tokens::Entity::delete_by_id(export_id).exec(txn).await?;
+
+                if deleted.rows_affected == 1 {
+                    AuditEvent::new("user.data_export_downloaded")
+                        .actor(owner_id)
+                        .target("user", owner_id)
+                        .record(txn, &audit)
+                        .await?;
+                }
+
+                Ok::<_, DbErr>(deleted.rows_affected == 1)
+            })
+        })
+        .await?;
+
+    if !deleted {
+        return Err(not_found());
+    }
+
+    let archive = state.exports.storage.get(&key).await?;
+
+    if let Err(e) = state.exports.storage.delete(&key).await {
+        error!("Failed to delete data export {}: {}", key, e);
+    }
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/zip"),
+            (
+                header::CONTENT_DISPOSITION,
+                "attachment; filename=\"export.zip\"",
+            ),
+        ],
+        archive,
+    ))
+}
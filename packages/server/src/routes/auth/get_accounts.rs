@@ -0,0 +1,70 @@
+use crate::{auth::get_current_user_id, error::AppError, AppState};
+use api_types::auth::{Account, GetAccountsResponse, Provider};
+use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
+use axum_auth::AuthBearer;
+use entities::{accounts, sea_orm_active_enums::AccountProvider, users};
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
+
+pub(crate) fn to_provider(provider: AccountProvider) -> Provider {
+    match provider {
+        AccountProvider::Github => Provider::Github,
+        AccountProvider::Google => Provider::Google,
+        AccountProvider::Passkey => Provider::Passkey,
+    }
+}
+
+pub(crate) fn to_account_provider(provider: Provider) -> AccountProvider {
+    match provider {
+        Provider::Github => AccountProvider::Github,
+        Provider::Google => AccountProvider::Google,
+        Provider::Passkey => AccountProvider::Passkey,
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/profile/accounts",
+    tag = "Auth",
+    responses(
+        (status = 200, description = "Sign-in methods linked to the authenticated user", body = GetAccountsResponse),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("token" = [])
+    )
+)]
+/// List linked social accounts
+pub async fn get_accounts(
+    State(state): State<AppState>,
+    AuthBearer(token): AuthBearer,
+) -> Result<impl IntoResponse, AppError> {
+    let user_id = get_current_user_id(&token, &state).await?;
+
+    let user = match users::Entity::find_by_id(user_id).one(&state.db).await? {
+        Some(user) => user,
+        None => return Err(AppError::unauthorized()),
+    };
+
+    let accounts = accounts::Entity::find()
+        .filter(accounts::Column::UserId.eq(user.id))
+        .all(&state.db)
+        .await?;
+
+    let accounts = accounts
+        .into_iter()
+        .map(|account| Account {
+            id: account.id,
+            provider: to_provider(account.provider),
+            provider_account_id: account.provider_account_id,
+        })
+        .collect();
+
+    Ok((
+        StatusCode::OK,
+        Json(GetAccountsResponse {
+            accounts,
+            has_password: user.password_hash.is_some(),
+        }),
+    ))
+}
@@ -0,0 +1,44 @@
+use crate::{auth::get_current_user_id, error::AppError, AppState};
+use api_types::auth::{GetPasskeysResponse, Passkey};
+use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
+use axum_auth::AuthBearer;
+use entities::passkeys;
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
+
+#[utoipa::path(
+    get,
+    path = "/profile/passkeys",
+    tag = "Auth",
+    responses(
+        (status = 200, description = "Passkeys registered by the authenticated user", body = GetPasskeysResponse),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("token" = [])
+    )
+)]
+/// List registered passkeys
+pub async fn get_passkeys(
+    State(state): State<AppState>,
+    AuthBearer(token): AuthBearer,
+) -> Result<impl IntoResponse, AppError> {
+    let user_id = get_current_user_id(&token, &state).await?;
+
+    let passkeys = passkeys::Entity::find()
+        .filter(passkeys::Column::UserId.eq(user_id))
+        .all(&state.db)
+        .await?;
+
+    let passkeys = passkeys
+        .into_iter()
+        .map(|passkey| Passkey {
+            id: passkey.id,
+            name: passkey.name,
+            created_at: passkey.created_at.to_string(),
+            last_used_at: passkey.last_used_at.map(|at| at.to_rfc3339()),
+        })
+        .collect();
+
+    Ok((StatusCode::OK, Json(GetPasskeysResponse { passkeys })))
+}
@@ -1,19 +1,9 @@
-use crate::{auth::get_current_user_id, error::ErrorResponse, AppState};
+use crate::{auth::get_current_user_id, error::AppError, AppState};
+use api_types::auth::ProfileResponse;
 use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
 use axum_auth::AuthBearer;
 use entities::users;
 use sea_orm::EntityTrait;
-use serde::Serialize;
-use utoipa::ToSchema;
-use uuid::Uuid;
-
-#[derive(Serialize, ToSchema)]
-pub struct ProfileResponse {
-    id: Uuid,
-    name: Option<String>,
-    email: String,
-    avatar_url: Option<String>,
-}
 
 #[utoipa::path(
     get,
@@ -22,6 +12,7 @@ pub struct ProfileResponse {
     responses(
         (status = 200, description = "Authenticated user's profile", body = ProfileResponse),
         (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal server error")
     ),
     security(
         ("token" = [])
@@ -31,20 +22,12 @@ pub struct ProfileResponse {
 pub async fn get_profile(
     State(state): State<AppState>,
     AuthBearer(token): AuthBearer,
-) -> impl IntoResponse {
-    let user_id = match get_current_user_id(&token, &state) {
-        Ok(user_id) => user_id,
-        Err(err_response) => return Err(err_response),
-    };
+) -> Result<impl IntoResponse, AppError> {
+    let user_id = get_current_user_id(&token, &state).await?;
 
-    let user = match users::Entity::find_by_id(user_id).one(&state.db).await {
-        Ok(Some(user)) => user,
-        Ok(None) => {
-            return Err(ErrorResponse::unauthorized())
-        }
-        Err(_) => {
-            return Err(ErrorResponse::internal_error())
-        }
+    let user = match users::Entity::find_by_id(user_id).one(&state.db).await? {
+        Some(user) => user,
+        None => return Err(AppError::unauthorized()),
     };
 
     let response = ProfileResponse {
@@ -0,0 +1,141 @@
+use crate::{
+    audit::{AuditContext, AuditEvent},
+    auth::get_current_user_id,
+    error::AppError,
+    extract::ValidatedJson,
+    github::{self, OauthFlow},
+    i18n::t,
+    routes::auth::get_accounts::to_account_provider,
+    AppState,
+};
+use api_types::auth::{LinkAccountBody, LinkAuthorizeUrlResponse, Provider};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use axum_auth::AuthBearer;
+use entities::accounts;
+use sea_orm::{ActiveModelTrait, DbErr, Set, TransactionTrait};
+use serde_json::json;
+
+#[utoipa::path(
+    get,
+    path = "/profile/accounts/{provider}/authorize",
+    tag = "Auth",
+    params(
+        ("provider" = Provider, Path, description = "Social provider to link")
+    ),
+    responses(
+        (status = 200, description = "Provider authorize page for this user", body = LinkAuthorizeUrlResponse),
+        (status = 400, description = "Provider not supported or not enabled"),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("token" = [])
+    )
+)]
+/// Start linking a social account to the authenticated user
+pub async fn link_account_authorize(
+    State(state): State<AppState>,
+    Path(provider): Path<Provider>,
+    AuthBearer(token): AuthBearer,
+) -> Result<Json<LinkAuthorizeUrlResponse>, AppError> {
+    let user_id = get_current_user_id(&token, &state).await?;
+
+    let url = match provider {
+        Provider::Github => {
+            let client = state.github_client()?;
+            let oauth_state = github::issue_state(OauthFlow::Link, Some(user_id), &state).await?;
+            client.authorize_url(&oauth_state)
+        }
+        Provider::Google => {
+            return Err(AppError::bad_request(t("auth.provider_not_supported")));
+        }
+        Provider::Passkey => {
+            return Err(AppError::bad_request(t("auth.passkeys_not_linkable")));
+        }
+    };
+
+    Ok(Json(LinkAuthorizeUrlResponse { url }))
+}
+
+#[utoipa::path(
+    post,
+    path = "/profile/accounts/{provider}",
+    tag = "Auth",
+    params(
+        ("provider" = Provider, Path, description = "Social provider to link")
+    ),
+    request_body(content = LinkAccountBody, example = json!({ "code": "e72e16c7e42f292c6912", "state": "eyJhbGciOiJIUzI1NiJ9..." })),
+    responses(
+        (status = 204, description = "Account linked successfully"),
+        (status = 400, description = "Provider not supported or not enabled"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Invalid OAuth state"),
+        (status = 409, description = "Account already linked"),
+        (status = 500, description = "Internal server error"),
+        (status = 502, description = "GitHub request failed")
+    ),
+    security(
+        ("token" = [])
+    )
+)]
+/// Link a social account to the authenticated user
+pub async fn link_account(
+    State(state): State<AppState>,
+    audit: AuditContext,
+    Path(provider): Path<Provider>,
+    AuthBearer(token): AuthBearer,
+    ValidatedJson(body): ValidatedJson<LinkAccountBody>,
+) -> Result<impl IntoResponse, AppError> {
+    let user_id = get_current_user_id(&token, &state).await?;
+
+    let (provider_account_id, provider_login) = match provider {
+        Provider::Github => {
+            let client = state.github_client()?;
+            github::consume_state(OauthFlow::Link, &body.state, Some(user_id), &state).await?;
+            let github_user = client.fetch_profile(&body.code).await?;
+            (github_user.id, github_user.login)
+        }
+        Provider::Google => {
+            return Err(AppError::bad_request(t("auth.provider_not_supported")));
+        }
+        Provider::Passkey => {
+            return Err(AppError::bad_request(t("auth.passkeys_not_linkable")));
+        }
+    };
+
+    let provider = to_account_provider(provider);
+
+    let new_account = accounts::ActiveModel {
+        provider: Set(provider),
+        provider_account_id: Set(provider_account_id),
+        provider_login: Set(Some(provider_login)),
+        user_id: Set(user_id),
+        ..Default::default()
+    };
+
+    state
+        .db
+        .transaction(|txn| {
+            Box::pin(async move {
+                // Os índices únicos de accounts respondem 409 com a mensagem certa
+                let account = new_account.insert(txn).await?;
+
+                AuditEvent::new("account.linked")
+                    .actor(user_id)
+                    .target("account", account.id)
+                    .after(json!({ "provider": account.provider, "login": account.provider_login }))
+                    .record(txn, &audit)
+                    .await?;
+
+                Ok::<_, DbErr>(())
+            })
+        })
+        .await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -1,6 +1,23 @@
 pub mod authenticate_with_github;
+pub mod authenticate_with_passkey;
 pub mod authenticate_with_password;
+pub mod authenticate_with_two_factor;
+pub mod change_email;
+pub mod change_password;
 pub mod create_account;
+pub mod delete_account;
+pub mod delete_passkey;
+pub mod disable_two_factor;
+pub mod enable_two_factor;
+pub mod export_data;
+pub mod get_accounts;
+pub mod get_passkeys;
 pub mod get_profile;
+pub mod link_account;
+pub mod register_passkey;
 pub mod request_password_recover;
 pub mod reset_password;
+pub mod setup_two_factor;
+pub mod unlink_account;
+pub mod update_avatar;
+pub mod update_profile;
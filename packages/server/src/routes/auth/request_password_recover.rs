@@ -1,67 +1,49 @@
-use crate::{error::ErrorResponse, AppState};
+use crate::{
+    audit::{AuditContext, AuditEvent},
+    error::AppError,
+    extract::ValidatedJson,
+    i18n::t,
+    AppState,
+};
+use api_types::auth::{RequestPasswordRecoverBody, RequestPasswordRecoverResponse};
 use axum::{extract::State, response::IntoResponse, Json};
 use entities::{sea_orm_active_enums::TokenType, tokens, users};
 use reqwest::StatusCode;
-use sea_orm::{ActiveModelTrait, ColumnTrait, EntityTrait, QueryFilter, Set};
-use serde::{Deserialize, Serialize};
-use tracing::error;
-use utoipa::ToSchema;
-use uuid::Uuid;
-use validator::Validate;
-
-#[derive(Serialize, Deserialize, ToSchema, Validate)]
-pub struct RequestPasswordRecoverBody {
-    #[validate(email)]
-    pub email: String,
-}
-
-#[derive(Serialize, ToSchema)]
-pub struct RequestPasswordRecoverResponse {
-    pub code: Uuid,
-}
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DbErr, EntityTrait, QueryFilter, Set, TransactionTrait,
+};
 
 #[utoipa::path(
     post,
     path = "/password/recover",
     tag = "Auth",
-    request_body = RequestPasswordRecoverBody,
+    request_body(content = RequestPasswordRecoverBody, example = json!({ "email": "jane@acme.com" })),
     responses(
         (status = 200, description = "Password recovery requested successfully", body = RequestPasswordRecoverResponse),
         (status = 400, description = "Validation error"),
         (status = 403, description = "Invalid credentials"),
+        (status = 429, description = "Too many requests, retry after the `Retry-After` seconds"),
         (status = 500, description = "Internal server error")
-    )
+    ),
+    security(())
 )]
 /// Request password recovery for a user by email.
 pub async fn request_password_recover(
     State(state): State<AppState>,
-    Json(body): Json<RequestPasswordRecoverBody>,
-) -> impl IntoResponse {
+    audit: AuditContext,
+    ValidatedJson(body): ValidatedJson<RequestPasswordRecoverBody>,
+) -> Result<impl IntoResponse, AppError> {
     // NOTE: This implementation is for testing purposes only.
     // In a real application, the recovery code should not be returned directly in the response.
     // Instead, an email containing a link with the recovery code should be sent to the user.
     let user_from_email = match users::Entity::find()
         .filter(users::Column::Email.eq(body.email.clone()))
         .one(&state.db)
-        .await
+        .await?
     {
-        Ok(Some(user)) => user,
-        Ok(None) => {
-            return Err((
-                StatusCode::FORBIDDEN,
-                Json(ErrorResponse {
-                    error: String::from("Invalid credentials."),
-                }),
-            ));
-        }
-        Err(e) => {
-            error!("Db query error: {}", e);
-            return Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ErrorResponse {
-                    error: String::from("Internal server error"),
-                }),
-            ));
+        Some(user) => user,
+        None => {
+            return Err(AppError::forbidden(t("auth.invalid_credentials")));
         }
     };
 
@@ -71,18 +53,22 @@ pub async fn request_password_recover(
         ..Default::default()
     };
 
-    let token = match token.insert(&state.db).await {
-        Ok(token) => token,
-        Err(e) => {
-            error!("Db query error: {}", e);
-            return Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ErrorResponse {
-                    error: String::from("Internal server error"),
-                }),
-            ));
-        }
-    };
+    let token = state
+        .db
+        .transaction(|txn| {
+            Box::pin(async move {
+                let token = token.insert(txn).await?;
+
+                AuditEvent::new("password.recovery_requested")
+                    .actor(token.user_id)
+                    .target("user", token.user_id)
+                    .record(txn, &audit)
+                    .await?;
+
+                Ok::<_, DbErr>(token)
+            })
+        })
+        .await?;
 
     Ok((
         StatusCode::OK,
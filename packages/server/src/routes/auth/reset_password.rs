@@ -1,123 +1,105 @@
+use api_types::auth::ResetPasswordRequest;
 use argon2::{
     password_hash::{rand_core::OsRng, SaltString},
     Argon2, PasswordHasher,
 };
-use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
-use entities::{tokens, users};
+use axum::{extract::State, http::StatusCode, response::IntoResponse};
+use entities::{sea_orm_active_enums::TokenType, tokens, users};
 use sea_orm::{
     ActiveModelTrait, ColumnTrait, DbErr, EntityTrait, QueryFilter, Set, TransactionTrait,
 };
-use serde::{Deserialize, Serialize};
-use tracing::error;
-use utoipa::ToSchema;
-use uuid::Uuid;
-use validator::Validate;
 
-use crate::{error::ErrorResponse, AppState};
-
-#[derive(Serialize, Deserialize, ToSchema, Validate)]
-pub struct ResetPasswordRequest {
-    pub code: Uuid,
-    #[validate(length(min = 6))]
-    pub password: String,
-}
+use crate::{
+    audit::{AuditContext, AuditEvent},
+    error::AppError,
+    extract::ValidatedJson,
+    i18n::t,
+    AppState,
+};
 
 #[utoipa::path(
     post,
     path = "/password/reset",
     tag = "Auth",
-    request_body = ResetPasswordRequest,
+    request_body(content = ResetPasswordRequest, example = json!({ "code": "3f2b8c1e-6d4a-4f7e-9b1a-2c5d8e7f6a90", "password": "correct-horse-battery-staple" })),
     responses(
         (status = 204, description = "Password reset successfully"),
+        (status = 400, description = "Password does not meet the password policy"),
         (status = 403, description = "Invalid or expired recovery code"),
         (status = 500, description = "Internal server error")
-    )
+    ),
+    security(())
 )]
 /// Reset user password using a recovery code.
 pub async fn reset_password(
     State(state): State<AppState>,
-    Json(body): Json<ResetPasswordRequest>,
-) -> impl IntoResponse {
-    if let Err(e) = body.validate() {
-        return Err((
-            StatusCode::BAD_REQUEST,
-            Json(ErrorResponse {
-                error: format!("Validation error: {}", e),
-            }),
-        ));
-    }
-
+    audit: AuditContext,
+    ValidatedJson(body): ValidatedJson<ResetPasswordRequest>,
+) -> Result<impl IntoResponse, AppError> {
     let token = match tokens::Entity::find()
         .filter(tokens::Column::Id.eq(body.code))
+        .filter(tokens::Column::Type.eq(TokenType::PasswordRecover))
         .one(&state.db)
-        .await
+        .await?
     {
-        Ok(Some(token)) => token,
-        Ok(None) => {
-            return Err((
-                StatusCode::FORBIDDEN,
-                Json(ErrorResponse {
-                    error: "Invalid or expired recovery code.".into(),
-                }),
-            ));
+        Some(token) => token,
+        None => {
+            return Err(AppError::forbidden(t("auth.invalid_recovery_code")));
         }
-        Err(e) => {
-            error!("Error fetching token: {}", e);
-            return Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ErrorResponse {
-                    error: "Internal server error".into(),
-                }),
-            ));
+    };
+
+    let user = match users::Entity::find_by_id(token.user_id)
+        .one(&state.db)
+        .await?
+    {
+        Some(user) => user,
+        None => {
+            return Err(AppError::forbidden(t("auth.invalid_recovery_code")));
         }
     };
 
+    state
+        .config
+        .password_policy
+        .enforce(
+            "password",
+            &body.password,
+            &user.email,
+            user.name.as_deref(),
+        )
+        .await?;
+
     let salt = SaltString::generate(&mut OsRng);
     let argon2 = Argon2::default();
 
-    let password_hash = match argon2.hash_password(body.password.as_bytes(), &salt) {
-        Ok(hash) => hash.to_string(),
-        Err(e) => {
-            error!("Error hashing password: {}", e);
-            return Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ErrorResponse {
-                    error: "Internal server error".into(),
-                }),
-            ));
-        }
-    };
+    let password_hash = argon2
+        .hash_password(body.password.as_bytes(), &salt)
+        .map_err(|e| AppError::internal(format!("Error hashing password: {}", e)))?
+        .to_string();
 
-    let tx_result = state
+    state
         .db
         .transaction(|txn| {
             Box::pin(async move {
-                let mut user: users::ActiveModel = users::Entity::find_by_id(token.user_id)
-                    .one(txn)
-                    .await?
-                    .ok_or(DbErr::RecordNotFound("User not found".into()))?
-                    .into();
+                let session_version = user.session_version + 1;
+                let mut user: users::ActiveModel = user.into();
 
                 user.password_hash = Set(Some(password_hash));
+                user.session_version = Set(session_version);
                 user.update(txn).await?;
 
                 tokens::Entity::delete_by_id(token.id).exec(txn).await?;
 
+                AuditEvent::new("password.reset")
+                    .actor(token.user_id)
+                    .target("user", token.user_id)
+                    .record(txn, &audit)
+                    .await?;
+
                 Ok::<(), DbErr>(())
             })
         })
-        .await;
+        .await?;
 
-    match tx_result {
-        Ok(_) => Ok(StatusCode::NO_CONTENT),
-        Err(e) => {
-            error!("Reset password transaction failed: {}", e);
-            Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ErrorResponse {
-                    error: "Internal server error".into(),
-                }),
-            ))
-        }
-    }
+    Ok(StatusCode::NO_CONTENT)
 }
@@ -0,0 +1,76 @@
+use crate::{
+    audit::{AuditContext, AuditEvent},
+    auth::get_current_user_id,
+    error::AppError,
+    i18n::t,
+    two_factor, AppState,
+};
+use api_types::auth::SetupTwoFactorResponse;
+use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
+use axum_auth::AuthBearer;
+use entities::users;
+use sea_orm::{ActiveModelTrait, DbErr, EntityTrait, Set, TransactionTrait};
+
+#[utoipa::path(
+    post,
+    path = "/profile/two-factor",
+    tag = "Auth",
+    responses(
+        (status = 200, description = "TOTP secret generated, confirm it with a code to enable 2FA", body = SetupTwoFactorResponse),
+        (status = 401, description = "Unauthorized"),
+        (status = 409, description = "Two-factor authentication already enabled"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("token" = [])
+    )
+)]
+/// Start two-factor authentication enrollment
+pub async fn setup_two_factor(
+    State(state): State<AppState>,
+    audit: AuditContext,
+    AuthBearer(token): AuthBearer,
+) -> Result<impl IntoResponse, AppError> {
+    let user_id = get_current_user_id(&token, &state).await?;
+
+    let user = match users::Entity::find_by_id(user_id).one(&state.db).await? {
+        Some(user) => user,
+        None => return Err(AppError::unauthorized()),
+    };
+
+    if user.totp_enabled {
+        return Err(AppError::conflict(t("two_factor.already_enabled")));
+    }
+
+    let secret = two_factor::generate_secret();
+    let otpauth_uri = two_factor::otpauth_uri(&secret, &user.email)?;
+
+    let mut user: users::ActiveModel = user.into();
+    user.totp_secret = Set(Some(secret.clone()));
+    user.totp_last_step = Set(None);
+
+    state
+        .db
+        .transaction(|txn| {
+            Box::pin(async move {
+                user.update(txn).await?;
+
+                AuditEvent::new("two_factor.setup_started")
+                    .actor(user_id)
+                    .target("user", user_id)
+                    .record(txn, &audit)
+                    .await?;
+
+                Ok::<_, DbErr>(())
+            })
+        })
+        .await?;
+
+    Ok((
+        StatusCode::OK,
+        Json(SetupTwoFactorResponse {
+            secret,
+            otpauth_uri,
+        }),
+    ))
+}
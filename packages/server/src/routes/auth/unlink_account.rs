@@ -0,0 +1,101 @@
+use crate::{
+    audit::{AuditContext, AuditEvent},
+    auth::get_current_user_id,
+    error::AppError,
+    i18n::t,
+    routes::auth::get_accounts::to_account_provider,
+    AppState,
+};
+use api_types::auth::Provider;
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+use axum_auth::AuthBearer;
+use entities::{accounts, passkeys, sea_orm_active_enums::AccountProvider, users};
+use sea_orm::{ColumnTrait, DbErr, EntityTrait, ModelTrait, QueryFilter, TransactionTrait};
+use serde_json::json;
+
+#[utoipa::path(
+    delete,
+    path = "/profile/accounts/{provider}",
+    tag = "Auth",
+    params(
+        ("provider" = Provider, Path, description = "Social provider to unlink")
+    ),
+    responses(
+        (status = 204, description = "Account unlinked successfully"),
+        (status = 400, description = "Account is the only sign-in method"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "No account linked for this provider"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("token" = [])
+    )
+)]
+/// Unlink a social account from the authenticated user
+pub async fn unlink_account(
+    State(state): State<AppState>,
+    audit: AuditContext,
+    Path(provider): Path<Provider>,
+    AuthBearer(token): AuthBearer,
+) -> Result<impl IntoResponse, AppError> {
+    let user_id = get_current_user_id(&token, &state).await?;
+
+    let user = match users::Entity::find_by_id(user_id).one(&state.db).await? {
+        Some(user) => user,
+        None => return Err(AppError::unauthorized()),
+    };
+
+    let accounts = accounts::Entity::find()
+        .filter(accounts::Column::UserId.eq(user.id))
+        .all(&state.db)
+        .await?;
+
+    let provider = to_account_provider(provider);
+    let sign_in_methods = accounts.len() + usize::from(user.password_hash.is_some());
+
+    let account = match accounts.into_iter().find(|a| a.provider == provider) {
+        Some(account) => account,
+        None => {
+            return Err(AppError::not_found(t("auth.no_linked_account")));
+        }
+    };
+
+    if sign_in_methods <= 1 {
+        return Err(AppError::bad_request(t("auth.last_sign_in_method_unlink")));
+    }
+
+    state
+        .db
+        .transaction(|txn| {
+            Box::pin(async move {
+                if account.provider == AccountProvider::Passkey {
+                    passkeys::Entity::delete_many()
+                        .filter(passkeys::Column::UserId.eq(account.user_id))
+                        .exec(txn)
+                        .await?;
+                }
+
+                let snapshot =
+                    json!({ "provider": account.provider, "login": account.provider_login });
+                let account_id = account.id;
+
+                account.delete(txn).await?;
+
+                AuditEvent::new("account.unlinked")
+                    .actor(user_id)
+                    .target("account", account_id)
+                    .before(snapshot)
+                    .record(txn, &audit)
+                    .await?;
+
+                Ok::<(), DbErr>(())
+            })
+        })
+        .await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,78 @@
+use crate::{
+    audit::{AuditContext, AuditEvent},
+    auth::get_current_user_id,
+    avatar::{upload_avatar, AvatarOwner, AvatarUpload},
+    error::AppError,
+    AppState,
+};
+use api_types::avatar::AvatarResponse;
+use axum::{
+    extract::{Multipart, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use axum_auth::AuthBearer;
+use entities::users;
+use sea_orm::{ActiveModelTrait, DbErr, EntityTrait, Set, TransactionTrait};
+use serde_json::json;
+
+#[utoipa::path(
+    put,
+    path = "/profile/avatar",
+    tag = "Auth",
+    request_body(content = AvatarUpload, content_type = "multipart/form-data"),
+    responses(
+        (status = 200, description = "Avatar uploaded", body = AvatarResponse),
+        (status = 400, description = "Missing or invalid image"),
+        (status = 401, description = "Unauthorized"),
+        (status = 413, description = "Image too large"),
+        (status = 415, description = "Unsupported image type"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("token" = [])
+    )
+)]
+/// Upload the authenticated user's avatar
+pub async fn update_avatar(
+    State(state): State<AppState>,
+    audit: AuditContext,
+    AuthBearer(token): AuthBearer,
+    multipart: Multipart,
+) -> Result<impl IntoResponse, AppError> {
+    let user_id = get_current_user_id(&token, &state).await?;
+
+    let user = match users::Entity::find_by_id(user_id).one(&state.db).await? {
+        Some(user) => user,
+        None => return Err(AppError::unauthorized()),
+    };
+
+    let avatar_url = upload_avatar(&state, AvatarOwner::User(user.id), multipart).await?;
+
+    let before = json!({ "avatarUrl": user.avatar_url });
+    let after = json!({ "avatarUrl": avatar_url });
+    let mut user: users::ActiveModel = user.into();
+    user.avatar_url = Set(Some(avatar_url.clone()));
+
+    state
+        .db
+        .transaction(|txn| {
+            Box::pin(async move {
+                user.update(txn).await?;
+
+                AuditEvent::new("user.avatar_updated")
+                    .actor(user_id)
+                    .target("user", user_id)
+                    .before(before)
+                    .after(after)
+                    .record(txn, &audit)
+                    .await?;
+
+                Ok::<_, DbErr>(())
+            })
+        })
+        .await?;
+
+    Ok((StatusCode::OK, Json(AvatarResponse { avatar_url })))
+}
@@ -0,0 +1,83 @@
+use crate::{
+    audit::{AuditContext, AuditEvent},
+    auth::get_current_user_id,
+    error::AppError,
+    extract::ValidatedJson,
+    i18n::t,
+    AppState,
+};
+use api_types::auth::UpdateProfileBody;
+use axum::{extract::State, http::StatusCode, response::IntoResponse};
+use axum_auth::AuthBearer;
+use entities::{sea_orm_active_enums::Role, users};
+use rbac::{get_user_permission, Action, Resource};
+use sea_orm::{ActiveModelTrait, DbErr, EntityTrait, Set, TransactionTrait};
+use serde_json::json;
+
+#[utoipa::path(
+    put,
+    path = "/profile",
+    tag = "Auth",
+    request_body(content = UpdateProfileBody, example = json!({ "name": "Jane Doe", "avatarUrl": "https://avatars.githubusercontent.com/u/583231" })),
+    responses(
+        (status = 204, description = "Profile updated successfully"),
+        (status = 400, description = "Validation error"),
+        (status = 401, description = "Unauthorized"),
+        (status = 500, description = "Internal server error")
+    ),
+    security(
+        ("token" = [])
+    )
+)]
+/// Update the authenticated user's profile
+pub async fn update_profile(
+    State(state): State<AppState>,
+    audit: AuditContext,
+    AuthBearer(token): AuthBearer,
+    ValidatedJson(body): ValidatedJson<UpdateProfileBody>,
+) -> Result<impl IntoResponse, AppError> {
+    let user_id = get_current_user_id(&token, &state).await?;
+
+    let user = match users::Entity::find_by_id(user_id).one(&state.db).await? {
+        Some(user) => user,
+        None => return Err(AppError::unauthorized()),
+    };
+
+    // Fora de uma organização o usuário tem as permissões básicas de membro
+    let ability = get_user_permission(user_id, Role::Member);
+
+    if ability.cannot(&Action::Update, &Resource::User(&user)) {
+        return Err(AppError::forbidden(t("user.update_forbidden")));
+    }
+
+    let before = json!({ "name": user.name, "avatarUrl": user.avatar_url });
+    let mut user: users::ActiveModel = user.into();
+
+    if let Some(name) = body.name {
+        user.name = Set(Some(name));
+    }
+    if let Some(avatar_url) = body.avatar_url {
+        user.avatar_url = Set(Some(avatar_url));
+    }
+
+    state
+        .db
+        .transaction(|txn| {
+            Box::pin(async move {
+                let user = user.update(txn).await?;
+
+                AuditEvent::new("user.updated")
+                    .actor(user_id)
+                    .target("user", user_id)
+                    .before(before)
+                    .after(json!({ "name": user.name, "avatarUrl": user.avatar_url }))
+                    .record(txn, &audit)
+                    .await?;
+
+                Ok::<_, DbErr>(())
+            })
+        })
+        .await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
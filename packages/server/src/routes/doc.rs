@@ -1,21 +1,20 @@
-use crate::routes::auth::reset_password::ResetPasswordRequest;
-use crate::routes::auth::{
-    authenticate_with_github::{AuthenticateWithGithubBody, AuthenticateWithGithubResponse},
-    authenticate_with_password::{AuthenticateWithPasswordBody, AuthenticateWithPasswordResponse},
-    create_account::{CreateAccountBody, CreateAccountResponse},
-    get_profile::ProfileResponse,
-    request_password_recover::{RequestPasswordRecoverBody, RequestPasswordRecoverResponse},
-};
-use crate::routes::members::get_members::GetMembersResponse;
-use axum::{
-    body::Body,
-    http::{header, Response, StatusCode},
-    response::{Html, IntoResponse},
+use crate::error::{
+    AppError, ErrorCode, ErrorDetail, ErrorResponse, FieldErrors, PROBLEM_CONTENT_TYPE,
 };
+use crate::i18n::t;
+use crate::routes::api_router;
+use crate::versioning::ApiVersion;
+use axum::{http::header, response::Html, routing::get, Router};
 use scalar_doc::Documentation;
-use std::fs;
+use std::{collections::HashMap, sync::LazyLock, time::Duration};
 use utoipa::{
-    openapi::security::{Http, HttpAuthScheme, SecurityScheme},
+    openapi::{
+        content::ContentBuilder,
+        path::Operation,
+        security::{Http, HttpAuthScheme, SecurityRequirement, SecurityScheme},
+        server::Server,
+        Ref, RefOr, ResponseBuilder,
+    },
     Modify, OpenApi,
 };
 
@@ -31,55 +30,233 @@ impl Modify for SecurityAddon {
     }
 }
 
+/// Gives every error response of the routes the problem details body, with an
+/// example for its status. Also adds the errors any operation of a kind can
+/// fail with: 400 and 415 from `ValidatedJson` for JSON bodies and 401 for
+/// authenticated ones.
+struct ProblemResponses;
+
+impl Modify for ProblemResponses {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        // Sem `security` na operação vale o requisito global, o token
+        let secured_by_default = openapi.security.as_ref().is_some_and(|requirements| {
+            requirements
+                .iter()
+                .any(|requirement| *requirement != SecurityRequirement::default())
+        });
+
+        for item in openapi.paths.paths.values_mut() {
+            let operations = [
+                &mut item.get,
+                &mut item.put,
+                &mut item.post,
+                &mut item.delete,
+                &mut item.patch,
+            ];
+
+            for operation in operations.into_iter().flatten() {
+                add_problem_responses(operation, secured_by_default);
+            }
+        }
+    }
+}
+
+fn add_problem_responses(operation: &mut Operation, secured_by_default: bool) {
+    let takes_json = operation
+        .request_body
+        .as_ref()
+        .is_some_and(|body| body.content.contains_key("application/json"));
+    let authenticated = match &operation.security {
+        Some(requirements) => requirements
+            .iter()
+            .any(|requirement| *requirement != SecurityRequirement::default()),
+        None => secured_by_default,
+    };
+
+    let responses = &mut operation.responses.responses;
+    let mut implied = Vec::new();
+
+    if takes_json {
+        implied.push(("400", "Invalid request body"));
+        implied.push(("415", "Request body is not JSON"));
+    }
+    if authenticated {
+        implied.push(("401", "Unauthorized"));
+    }
+
+    for (status, description) in implied {
+        responses
+            .entry(status.to_owned())
+            .or_insert_with(|| ResponseBuilder::new().description(description).into());
+    }
+
+    for (status, response) in responses.iter_mut() {
+        let RefOr::T(response) = response else {
+            continue;
+        };
+        let Some(example) = problem_example(status) else {
+            continue;
+        };
+
+        response
+            .content
+            .entry(PROBLEM_CONTENT_TYPE.to_owned())
+            .or_insert_with(|| {
+                ContentBuilder::new()
+                    .schema(Some(Ref::from_schema_name("ErrorResponse")))
+                    .example(serde_json::to_value(example.body()).ok())
+                    .build()
+            });
+    }
+}
+
+/// A typical error for `status`, or `None` if it isn't an error status.
+fn problem_example(status: &str) -> Option<AppError> {
+    let field_error = |field: &str, rule: &str, key: &str| {
+        FieldErrors::from([(field.to_owned(), vec![ErrorDetail::new(rule, t(key))])])
+    };
+
+    let error = match status {
+        "400" => AppError::validation(
+            t("problem.validation_failed.detail"),
+            field_error("email", "email", "validation.email"),
+        ),
+        "401" => AppError::unauthorized(),
+        "403" => AppError::forbidden(t("organization.not_member")),
+        "404" => AppError::not_found(t("project.not_found")),
+        "409" => AppError::Conflict {
+            message: t("constraint.users_email_key"),
+            details: Vec::new(),
+            errors: field_error("email", "unique", "constraint.unique.field"),
+        },
+        "413" => AppError::PayloadTooLarge(t("request.body_too_large")),
+        "415" => AppError::UnsupportedMediaType(t("request.expected_json")),
+        "422" => AppError::UnprocessableEntity {
+            message: t("constraint.foreign_key"),
+            errors: FieldErrors::new(),
+        },
+        "429" => AppError::TooManyRequests {
+            retry_after: Duration::from_secs(60),
+        },
+        "502" => AppError::Upstream(String::new()),
+        status if status.starts_with('5') => AppError::internal(String::new()),
+        _ => return None,
+    };
+
+    Some(error)
+}
+
+/// What the routes don't describe themselves; paths and their schemas are
+/// added by [`api_router`].
 #[derive(OpenApi)]
 #[openapi(
-    paths(
-        crate::routes::auth::create_account::create_account,
-        crate::routes::auth::authenticate_with_password::authenticate_with_password,
-        crate::routes::auth::authenticate_with_github::authenticate_with_github,
-        crate::routes::auth::get_profile::get_profile,
-        crate::routes::auth::request_password_recover::request_password_recover,
-        crate::routes::auth::reset_password::reset_password,
-        crate::routes::members::get_members::get_members
+    components(schemas(ErrorCode, ErrorResponse, ErrorDetail)),
+    // Rotas públicas declaram `security(())`
+    security(("token" = [])),
+    info(
+        title = "Rust SaaS RBAC API",
+        version = "1.0.0",
+        description = "Errors are `application/problem+json` (RFC 7807) bodies whose `code` is one of `ErrorCode`. `title`, `detail` and e-mails are localized from `Accept-Language` (`en` or `pt-BR`, English by default)."
     ),
-    components(schemas(
-        CreateAccountBody,
-        CreateAccountResponse,
-        AuthenticateWithPasswordBody,
-        AuthenticateWithPasswordResponse,
-        AuthenticateWithGithubBody,
-        AuthenticateWithGithubResponse,
-        ProfileResponse,
-        RequestPasswordRecoverBody,
-        RequestPasswordRecoverResponse,
-        ResetPasswordRequest,
-        GetMembersResponse,
-    )),
-    info(title = "Rust SaaS RBAC API", version = "1.0.0"),
     modifiers(&SecurityAddon)
 )]
 pub struct ApiDoc;
 
-/// Serves the Scalar API documentation UI.
-pub async fn doc() -> impl IntoResponse {
-    Html(
-        Documentation::new("API Docs", "/openapi.json")
-            .build()
-            .unwrap(),
-    )
+/// `/doc`, the Scalar API documentation UI, and `/openapi.json`, the
+/// specification it shows, for `version`.
+pub fn doc_router<S: Clone + Send + Sync + 'static>(version: ApiVersion) -> Router<S> {
+    let spec_url = format!("{}/openapi.json", version.prefix());
+    let page = Html(Documentation::new("API Docs", &spec_url).build().unwrap());
+
+    Router::new()
+        .route("/doc", get(move || std::future::ready(page.clone())))
+        .route(
+            "/openapi.json",
+            get(move || async move {
+                (
+                    [(header::CONTENT_TYPE, "application/json")],
+                    openapi_json(version),
+                )
+            }),
+        )
+}
+
+/// The complete OpenAPI document of the routes in [`api_router`] for
+/// `version`. Paths are relative to the version prefix, given as the server.
+pub fn openapi(version: ApiVersion) -> utoipa::openapi::OpenApi {
+    let mut openapi = api_router(version, None).into_openapi();
+    openapi.servers = Some(vec![Server::new(version.prefix())]);
+    // Depois de juntar as rotas, que não existem ainda nos `modifiers`
+    ProblemResponses.modify(&mut openapi);
+    openapi
+}
+
+static OPENAPI_SPECS: LazyLock<HashMap<ApiVersion, String>> = LazyLock::new(|| {
+    ApiVersion::ALL
+        .into_iter()
+        .map(|version| {
+            let json = openapi(version)
+                .to_pretty_json()
+                .expect("Falha ao gerar a especificação OpenAPI");
+            (version, json)
+        })
+        .collect()
+});
+
+/// The OpenAPI specification of `version` as JSON, generated from the
+/// annotations the first time it's needed.
+pub fn openapi_json(version: ApiVersion) -> &'static str {
+    &OPENAPI_SPECS[&version]
 }
 
-/// Serves the raw OpenAPI specification file.
-pub async fn openapi_spec_handler() -> Response<Body> {
-    match fs::read_to_string("openapi.json") {
-        Ok(spec) => Response::builder()
-            .status(StatusCode::OK)
-            .header(header::CONTENT_TYPE, "application/json")
-            .body(Body::from(spec))
-            .unwrap(),
-        Err(_) => Response::builder()
-            .status(StatusCode::NOT_FOUND)
-            .body(Body::empty())
-            .unwrap(),
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+
+    fn committed_spec(version: ApiVersion) -> &'static str {
+        match version {
+            ApiVersion::V1 => include_str!("../../openapi/v1.json"),
+        }
+    }
+
+    #[test]
+    fn test_committed_specs_are_up_to_date() {
+        for version in ApiVersion::ALL {
+            let committed: Value = serde_json::from_str(committed_spec(version)).unwrap();
+            let generated: Value = serde_json::from_str(openapi_json(version)).unwrap();
+
+            assert!(
+                committed == generated,
+                "openapi/{}.json is stale, regenerate it with `cargo run --bin gen-openapi`",
+                version.as_str()
+            );
+        }
+    }
+
+    #[test]
+    fn test_every_error_response_has_a_problem_body() {
+        let spec: Value = serde_json::from_str(openapi_json(ApiVersion::V1)).unwrap();
+
+        for (path, item) in spec["paths"].as_object().unwrap() {
+            for (method, operation) in item.as_object().unwrap() {
+                for (status, response) in operation["responses"].as_object().unwrap() {
+                    if status.as_str() < "400" {
+                        continue;
+                    }
+                    let content = &response["content"][PROBLEM_CONTENT_TYPE];
+
+                    assert_eq!(
+                        content["schema"]["$ref"], "#/components/schemas/ErrorResponse",
+                        "{method} {path} {status}"
+                    );
+                    assert_eq!(content["example"]["status"].to_string(), *status);
+                }
+            }
+        }
+
+        let login = &spec["paths"]["/sessions/password"]["post"];
+        assert_eq!(login["security"], serde_json::json!([{}]));
+        assert!(login["responses"]["415"].is_object());
     }
 }
@@ -0,0 +1,109 @@
+//! Probes for the orchestrator, outside the versioned API: `/health/live`
+//! says the process is up, `/health/ready` that it can take traffic.
+
+use crate::{db::pending_migrations, AppState};
+use axum::{extract::State, http::StatusCode, routing::get, Json, Router};
+use serde::Serialize;
+use std::time::Duration;
+use tracing::warn;
+
+/// Readiness must answer before the orchestrator's own probe timeout, even
+/// when the pool has no free connection.
+const READY_TIMEOUT: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Serialize)]
+pub struct Health {
+    pub status: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub checks: Option<Checks>,
+}
+
+/// Outcome of each check: `ok`, or why not. Errors only go to the log, the
+/// probes are public.
+#[derive(Debug, Serialize)]
+pub struct Checks {
+    pub database: &'static str,
+    pub migrations: &'static str,
+}
+
+pub fn health_router() -> Router<AppState> {
+    Router::new()
+        .route("/health/live", get(live))
+        .route("/health/ready", get(ready))
+}
+
+async fn live() -> Json<Health> {
+    Json(Health {
+        status: "ok",
+        checks: None,
+    })
+}
+
+async fn ready(State(state): State<AppState>) -> (StatusCode, Json<Health>) {
+    let checks = tokio::time::timeout(READY_TIMEOUT, check(&state))
+        .await
+        .unwrap_or(Checks {
+            database: "timed out",
+            migrations: "unknown",
+        });
+
+    let ready = checks.database == "ok" && checks.migrations == "ok";
+
+    let (status, label) = if ready {
+        (StatusCode::OK, "ok")
+    } else {
+        (StatusCode::SERVICE_UNAVAILABLE, "unavailable")
+    };
+    (
+        status,
+        Json(Health {
+            status: label,
+            checks: Some(checks),
+        }),
+    )
+}
+
+async fn check(state: &AppState) -> Checks {
+    if let Err(e) = state.db.ping().await {
+        warn!("Not ready, database unreachable: {}", e);
+        return Checks {
+            database: "unreachable",
+            migrations: "unknown",
+        };
+    }
+
+    let migrations = match pending_migrations(&state.db).await {
+        Ok(pending) if pending.is_empty() => "ok",
+        Ok(pending) => {
+            warn!("Not ready, migrations pending: {}", pending.join(", "));
+            "pending"
+        }
+        Err(e) => {
+            warn!("Not ready, couldn't read the applied migrations: {}", e);
+            "unknown"
+        }
+    };
+
+    Checks {
+        database: "ok",
+        migrations,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_state;
+
+    #[tokio::test]
+    async fn test_ready_fails_without_database() {
+        let Json(health) = live().await;
+        assert_eq!(health.status, "ok");
+
+        let (status, Json(health)) = ready(State(test_state())).await;
+
+        assert_eq!(status, StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(health.status, "unavailable");
+        assert_eq!(health.checks.unwrap().migrations, "unknown");
+    }
+}
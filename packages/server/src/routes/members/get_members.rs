@@ -1,4 +1,11 @@
-use crate::{auth::get_user_membership, error::ErrorResponse, AppState};
+use crate::{
+    audit::{AuditContext, AuditEvent},
+    auth::get_user_membership,
+    error::AppError,
+    i18n::t,
+    AppState,
+};
+use api_types::members::{GetMembersResponse, Member, Role};
 use axum::{
     extract::{Path, State},
     http::StatusCode,
@@ -9,48 +16,22 @@ use axum_auth::AuthBearer;
 use entities::{members, users};
 use rbac::{get_user_permission, Action, Resource};
 use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
-use serde::{Deserialize, Serialize};
-use utoipa::ToSchema;
-use uuid::Uuid;
-
-#[derive(Serialize, Deserialize, ToSchema, Clone, Copy, Debug, PartialEq, Eq)]
-pub enum Role {
-    Admin,
-    Member,
-    Billing,
-}
 
-impl From<entities::sea_orm_active_enums::Role> for Role {
-    fn from(role: entities::sea_orm_active_enums::Role) -> Self {
-        match role {
-            entities::sea_orm_active_enums::Role::Admin => Role::Admin,
-            entities::sea_orm_active_enums::Role::Member => Role::Member,
-            entities::sea_orm_active_enums::Role::Billing => Role::Billing,
-        }
+fn to_role(role: entities::sea_orm_active_enums::Role) -> Role {
+    match role {
+        entities::sea_orm_active_enums::Role::Admin => Role::Admin,
+        entities::sea_orm_active_enums::Role::Member => Role::Member,
+        entities::sea_orm_active_enums::Role::Billing => Role::Billing,
     }
 }
 
-#[derive(Serialize, Deserialize, ToSchema)]
-pub struct Member {
-    id: Uuid,
-    #[serde(rename = "userId")]
-    user_id: Uuid,
-    role: Role,
-    name: Option<String>,
-    email: String,
-    #[serde(rename = "avatarUrl")]
-    avatar_url: Option<String>,
-}
-
-#[derive(Serialize, ToSchema)]
-pub struct GetMembersResponse {
-    members: Vec<Member>,
-}
-
 #[utoipa::path(
     get,
     path = "/organizations/{slug}/members",
     tag = "Members",
+    params(
+        ("slug" = String, Path, description = "Organization slug")
+    ),
     security(
         ("token" = [])
     ),
@@ -61,11 +42,13 @@ pub struct GetMembersResponse {
         (status = 500, description = "Internal server error")
     )
 )]
+/// List the organization's members
 pub async fn get_members(
     State(state): State<AppState>,
+    audit: AuditContext,
     Path(slug): Path<String>,
     AuthBearer(token): AuthBearer,
-) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
+) -> Result<impl IntoResponse, AppError> {
     let membership_response = get_user_membership(&state, &slug, &token).await?;
     let membership_data = membership_response.as_object().unwrap();
 
@@ -75,10 +58,16 @@ pub async fn get_members(
     let ability = get_user_permission(membership.user_id, membership.role);
 
     if ability.cannot(&Action::Read, &Resource::Subject("User")) {
-        return Err(ErrorResponse::new(
-            StatusCode::FORBIDDEN,
-            "You're not allowed to see organization members.",
-        ));
+        AuditEvent::permission_denied(
+            membership.user_id,
+            membership.organization_id,
+            &Action::Read,
+            "User",
+        )
+        .record(&state.db, &audit)
+        .await?;
+
+        return Err(AppError::forbidden(t("organization.members_forbidden")));
     }
 
     let organization_json = membership_data.get("organization").unwrap();
@@ -89,8 +78,7 @@ pub async fn get_members(
         .find_also_related(users::Entity)
         .filter(members::Column::OrganizationId.eq(organization.id))
         .all(&state.db)
-        .await
-        .map_err(|_| ErrorResponse::internal_error())?;
+        .await?;
 
     let members_with_roles = members
         .into_iter()
@@ -99,7 +87,7 @@ pub async fn get_members(
             Member {
                 id: member.id,
                 user_id: user.id,
-                role: member.role.into(),
+                role: to_role(member.role),
                 name: user.name,
                 email: user.email,
                 avatar_url: user.avatar_url,
@@ -114,4 +102,3 @@ pub async fn get_members(
         }),
     ))
 }
-
@@ -1,7 +1,288 @@
 pub mod auth;
 pub mod billing;
 pub mod doc;
+pub mod health;
 pub mod invites;
 pub mod members;
 pub mod orgs;
 pub mod projects;
+
+use crate::{
+    avatar::MAX_AVATAR_BYTES,
+    config::CorsConfig,
+    i18n::negotiate_locale,
+    rate_limit::{login_lockout, rate_limit},
+    request_id::{request_id, REQUEST_ID_HEADER},
+    versioning::{
+        deprecated, unversioned_deprecation, ApiVersion, DEPRECATION_HEADER, SUNSET_HEADER,
+    },
+    AppState,
+};
+use axum::{
+    extract::DefaultBodyLimit,
+    http::{header, HeaderValue, Method},
+    middleware::{from_fn, from_fn_with_state},
+    routing::get,
+    Router,
+};
+use doc::{doc_router, ApiDoc};
+use health::health_router;
+use tower_http::{cors::CorsLayer, services::ServeDir};
+use utoipa::OpenApi;
+use utoipa_axum::{
+    router::{OpenApiRouter, UtoipaMethodRouter, UtoipaMethodRouterExt},
+    routes,
+};
+
+/// Every documented endpoint of `version`, registered once so its path and
+/// schemas always reach the OpenAPI document. Paths are relative to the
+/// version prefix. Without a `state` the middleware that needs one (rate
+/// limiting, login lockout) is left out, which doesn't change the document.
+pub fn api_router(version: ApiVersion, state: Option<&AppState>) -> OpenApiRouter<AppState> {
+    let rate_limited = |route: UtoipaMethodRouter<AppState>| match state {
+        Some(state) => route.layer(from_fn_with_state(state.clone(), rate_limit)),
+        None => route,
+    };
+    let locked_out = |route: UtoipaMethodRouter<AppState>| match state {
+        Some(state) => route.layer(from_fn_with_state(state.clone(), login_lockout)),
+        None => route,
+    };
+    // Folga para os campos do multipart além do arquivo
+    let avatar_body_limit = || DefaultBodyLimit::max(MAX_AVATAR_BYTES + 64 * 1024);
+
+    match version {
+        ApiVersion::V1 => OpenApiRouter::with_openapi(ApiDoc::openapi())
+            .routes(rate_limited(routes!(auth::create_account::create_account)))
+            .routes(rate_limited(locked_out(routes!(
+                auth::authenticate_with_password::authenticate_with_password
+            ))))
+            .routes(routes!(
+                auth::authenticate_with_github::github_authorize,
+                auth::authenticate_with_github::authenticate_with_github
+            ))
+            .routes(rate_limited(routes!(
+                auth::authenticate_with_two_factor::authenticate_with_two_factor
+            )))
+            .routes(routes!(
+                auth::authenticate_with_passkey::get_passkey_authentication_options
+            ))
+            .routes(routes!(
+                auth::authenticate_with_passkey::authenticate_with_passkey
+            ))
+            .routes(routes!(
+                auth::get_profile::get_profile,
+                auth::update_profile::update_profile,
+                auth::delete_account::delete_account
+            ))
+            .routes(routes!(auth::update_avatar::update_avatar).layer(avatar_body_limit()))
+            .routes(routes!(
+                auth::setup_two_factor::setup_two_factor,
+                auth::disable_two_factor::disable_two_factor
+            ))
+            .routes(routes!(auth::enable_two_factor::enable_two_factor))
+            .routes(routes!(
+                auth::get_passkeys::get_passkeys,
+                auth::register_passkey::register_passkey
+            ))
+            .routes(routes!(
+                auth::register_passkey::get_passkey_registration_options
+            ))
+            .routes(routes!(auth::delete_passkey::delete_passkey))
+            .routes(rate_limited(routes!(
+                auth::export_data::request_data_export
+            )))
+            .routes(routes!(auth::export_data::download_data_export))
+            .routes(routes!(auth::change_password::change_password))
+            .routes(routes!(auth::change_email::request_email_change))
+            .routes(routes!(auth::change_email::confirm_email_change))
+            .routes(routes!(auth::get_accounts::get_accounts))
+            .routes(routes!(
+                auth::link_account::link_account,
+                auth::unlink_account::unlink_account
+            ))
+            .routes(routes!(auth::link_account::link_account_authorize))
+            .routes(rate_limited(routes!(
+                auth::request_password_recover::request_password_recover
+            )))
+            .routes(routes!(auth::reset_password::reset_password))
+            .routes(routes!(members::get_members::get_members))
+            .routes(routes!(
+                orgs::update_two_factor_policy::update_two_factor_policy
+            ))
+            .routes(routes!(orgs::get_audit_logs::get_audit_logs))
+            .routes(
+                routes!(orgs::update_organization_avatar::update_organization_avatar)
+                    .layer(avatar_body_limit()),
+            )
+            .routes(
+                routes!(projects::update_project_avatar::update_project_avatar)
+                    .layer(avatar_body_limit()),
+            ),
+    }
+}
+
+/// The whole application: every API version under its prefix with its
+/// documentation, the deprecated unversioned aliases of `/v1`, the health
+/// probes and the uploaded files under `/uploads`. Shared by `main` and the
+/// tests.
+pub fn app(state: AppState) -> Router {
+    let mut router = Router::new()
+        .route("/", get(|| async { "Hello, World!" }))
+        .merge(health_router())
+        // `/doc` e `/openapi.json` mostram sempre a versão mais recente
+        .merge(doc_router(ApiVersion::LATEST));
+
+    for version in ApiVersion::ALL {
+        let (api, _) = api_router(version, Some(&state)).split_for_parts();
+        router = router.nest(&version.prefix(), api.merge(doc_router(version)));
+    }
+
+    let (unversioned, _) = api_router(ApiVersion::V1, Some(&state)).split_for_parts();
+
+    let cors = state.config.cors.as_ref().map(cors_layer);
+    let router = router
+        .merge(unversioned.layer(from_fn_with_state(unversioned_deprecation(), deprecated)))
+        .nest_service("/uploads", ServeDir::new(&state.config.uploads.dir))
+        // Por último para envolver todas as outras camadas
+        .layer(from_fn(negotiate_locale))
+        .layer(from_fn(request_id))
+        .with_state(state);
+
+    // Fora de todas, para responder o preflight antes do resto
+    match cors {
+        Some(cors) => router.layer(cors),
+        None => router,
+    }
+}
+
+fn cors_layer(config: &CorsConfig) -> CorsLayer {
+    let origins: Vec<HeaderValue> = config
+        .allowed_origins
+        .iter()
+        .filter_map(|origin| HeaderValue::from_str(origin).ok())
+        .collect();
+
+    CorsLayer::new()
+        .allow_origin(origins)
+        .allow_methods([
+            Method::GET,
+            Method::POST,
+            Method::PUT,
+            Method::PATCH,
+            Method::DELETE,
+        ])
+        .allow_headers([
+            header::AUTHORIZATION,
+            header::CONTENT_TYPE,
+            header::ACCEPT_LANGUAGE,
+            REQUEST_ID_HEADER,
+        ])
+        .expose_headers([
+            header::RETRY_AFTER,
+            header::LINK,
+            REQUEST_ID_HEADER,
+            DEPRECATION_HEADER,
+            SUNSET_HEADER,
+        ])
+        .allow_credentials(config.allow_credentials)
+        .max_age(config.max_age)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{config::Config, test_state};
+    use std::{sync::Arc, time::Duration};
+
+    #[test]
+    fn test_app_registers_documented_routes() {
+        let openapi = api_router(ApiVersion::V1, Some(&test_state())).into_openapi();
+
+        assert!(openapi.paths.paths.contains_key("/sessions/password"));
+        assert!(openapi
+            .paths
+            .paths
+            .contains_key("/organizations/{slug}/audit-logs"));
+
+        // Rotas sobrepostas fariam o axum entrar em pânico aqui
+        let _ = app(test_state());
+    }
+
+    #[tokio::test]
+    async fn test_unversioned_paths_are_deprecated_aliases() {
+        let app = app(test_state());
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let client = reqwest::Client::new();
+        let get_profile = |path: &str| {
+            client
+                .get(format!("{base}{path}"))
+                .bearer_auth("invalid")
+                .send()
+        };
+
+        let current = get_profile("/v1/profile").await.unwrap();
+        assert_eq!(current.status(), 401);
+        assert!(!current.headers().contains_key("deprecation"));
+
+        let legacy = get_profile("/profile").await.unwrap();
+        assert_eq!(legacy.status(), 401);
+        assert_eq!(legacy.headers()["deprecation"], "@1792281600");
+        assert_eq!(legacy.headers()["sunset"], "Sun, 18 Apr 2027 00:00:00 GMT");
+        assert_eq!(
+            legacy.headers()["link"],
+            "</v1/profile>; rel=\"successor-version\""
+        );
+
+        let spec: serde_json::Value = reqwest::get(format!("{base}/v1/openapi.json"))
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(spec["servers"][0]["url"], "/v1");
+        assert!(spec["paths"]["/profile"].is_object());
+    }
+
+    #[tokio::test]
+    async fn test_cors_preflight_allows_configured_origins() {
+        let mut state = test_state();
+        state.config = Arc::new(Config {
+            cors: Some(CorsConfig {
+                allowed_origins: vec!["https://app.example.com".into()],
+                allow_credentials: true,
+                max_age: Duration::from_secs(600),
+            }),
+            ..(*state.config).clone()
+        });
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app(state)).await.unwrap() });
+
+        let client = reqwest::Client::new();
+        let preflight = |origin: &'static str| {
+            client
+                .request(Method::OPTIONS, format!("{base}/v1/profile"))
+                .header("origin", origin)
+                .header("access-control-request-method", "GET")
+                .header("access-control-request-headers", "authorization")
+                .send()
+        };
+
+        let allowed = preflight("https://app.example.com").await.unwrap();
+        let headers = allowed.headers();
+        assert_eq!(
+            headers["access-control-allow-origin"],
+            "https://app.example.com"
+        );
+        assert_eq!(headers["access-control-allow-credentials"], "true");
+        assert_eq!(headers["access-control-max-age"], "600");
+
+        let denied = preflight("https://evil.example").await.unwrap();
+        assert!(!denied.headers().contains_key("access-control-allow-origin"));
+    }
+}
@@ -0,0 +1,184 @@
+use crate::{
+    audit::{AuditContext, AuditEvent},
+    auth::get_user_membership,
+    error::AppError,
+    extract::ValidatedQuery,
+    i18n::t,
+    AppState,
+};
+use api_types::orgs::{AuditLog, AuditLogsQuery, GetAuditLogsResponse};
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use axum_auth::AuthBearer;
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::{DateTime, FixedOffset};
+use entities::{audit_logs, members};
+use rbac::{get_user_permission, Action, Resource};
+use sea_orm::{ColumnTrait, Condition, EntityTrait, QueryFilter, QueryOrder, QuerySelect};
+use uuid::Uuid;
+
+const DEFAULT_LIMIT: u64 = 50;
+
+/// Position of an entry in the newest-first listing, opaque to clients.
+fn encode_cursor(created_at: &DateTime<FixedOffset>, id: Uuid) -> String {
+    URL_SAFE_NO_PAD.encode(format!("{}.{}", created_at.timestamp_micros(), id))
+}
+
+fn decode_cursor(cursor: &str) -> Option<(DateTime<FixedOffset>, Uuid)> {
+    let decoded = String::from_utf8(URL_SAFE_NO_PAD.decode(cursor).ok()?).ok()?;
+    let (micros, id) = decoded.split_once('.')?;
+
+    let created_at = DateTime::from_timestamp_micros(micros.parse().ok()?)?;
+
+    Some((created_at.fixed_offset(), id.parse().ok()?))
+}
+
+#[utoipa::path(
+    get,
+    path = "/organizations/{slug}/audit-logs",
+    tag = "Organizations",
+    params(
+        ("slug" = String, Path, description = "Organization slug"),
+        AuditLogsQuery
+    ),
+    security(
+        ("token" = [])
+    ),
+    responses(
+        (status = 200, description = "Audit log entries, newest first", body = GetAuditLogsResponse),
+        (status = 400, description = "Invalid filter or cursor"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+/// List the organization's audit log
+pub async fn get_audit_logs(
+    State(state): State<AppState>,
+    audit: AuditContext,
+    Path(slug): Path<String>,
+    AuthBearer(token): AuthBearer,
+    ValidatedQuery(query): ValidatedQuery<AuditLogsQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    let membership_response = get_user_membership(&state, &slug, &token).await?;
+    let membership_data = membership_response.as_object().unwrap();
+
+    let membership_json = membership_data.get("membership").unwrap();
+    let membership: members::Model = serde_json::from_value(membership_json.clone()).unwrap();
+
+    let ability = get_user_permission(membership.user_id, membership.role);
+
+    if ability.cannot(&Action::Read, &Resource::Subject("AuditLog")) {
+        AuditEvent::permission_denied(
+            membership.user_id,
+            membership.organization_id,
+            &Action::Read,
+            "AuditLog",
+        )
+        .record(&state.db, &audit)
+        .await?;
+
+        return Err(AppError::forbidden(t("organization.audit_logs_forbidden")));
+    }
+
+    let mut select = audit_logs::Entity::find()
+        .filter(audit_logs::Column::OrganizationId.eq(membership.organization_id));
+
+    if let Some(action) = query.action {
+        select = select.filter(audit_logs::Column::Action.eq(action));
+    }
+    if let Some(actor_id) = query.actor_id {
+        select = select.filter(audit_logs::Column::ActorId.eq(actor_id));
+    }
+    if let Some(target_type) = query.target_type {
+        select = select.filter(audit_logs::Column::TargetType.eq(target_type));
+    }
+    if let Some(target_id) = query.target_id {
+        select = select.filter(audit_logs::Column::TargetId.eq(target_id));
+    }
+    if let Some(from) = query.from {
+        select = select.filter(audit_logs::Column::CreatedAt.gte(from));
+    }
+    if let Some(to) = query.to {
+        select = select.filter(audit_logs::Column::CreatedAt.lt(to));
+    }
+
+    if let Some(cursor) = query.cursor {
+        let (created_at, id) = decode_cursor(&cursor)
+            .ok_or_else(|| AppError::bad_request(t("audit_log.invalid_cursor")))?;
+
+        // Continua logo depois da última entrada da página anterior
+        select = select.filter(
+            Condition::any()
+                .add(audit_logs::Column::CreatedAt.lt(created_at))
+                .add(
+                    Condition::all()
+                        .add(audit_logs::Column::CreatedAt.eq(created_at))
+                        .add(audit_logs::Column::Id.lt(id)),
+                ),
+        );
+    }
+
+    let limit = query.limit.unwrap_or(DEFAULT_LIMIT);
+
+    // Uma entrada a mais indica se existe outra página
+    let mut entries = select
+        .order_by_desc(audit_logs::Column::CreatedAt)
+        .order_by_desc(audit_logs::Column::Id)
+        .limit(limit + 1)
+        .all(&state.db)
+        .await?;
+
+    let has_more = entries.len() as u64 > limit;
+    entries.truncate(limit as usize);
+
+    let next_cursor = entries
+        .last()
+        .filter(|_| has_more)
+        .map(|last| encode_cursor(&last.created_at, last.id));
+
+    let audit_logs = entries
+        .into_iter()
+        .map(|entry| AuditLog {
+            id: entry.id,
+            actor_id: entry.actor_id,
+            action: entry.action,
+            target_type: entry.target_type,
+            target_id: entry.target_id,
+            before: entry.before,
+            after: entry.after,
+            ip: entry.ip,
+            user_agent: entry.user_agent,
+            created_at: entry.created_at.to_rfc3339(),
+        })
+        .collect();
+
+    Ok((
+        StatusCode::OK,
+        Json(GetAuditLogsResponse {
+            audit_logs,
+            next_cursor,
+        }),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cursor_round_trips() {
+        let created_at = DateTime::parse_from_rfc3339("2026-10-18T12:34:56.789012-03:00").unwrap();
+        let id = Uuid::new_v4();
+
+        let cursor = encode_cursor(&created_at, id);
+
+        assert_eq!(decode_cursor(&cursor), Some((created_at, id)));
+        assert_eq!(decode_cursor("not a cursor"), None);
+        assert_eq!(decode_cursor(&URL_SAFE_NO_PAD.encode("12.nope")), None);
+    }
+}
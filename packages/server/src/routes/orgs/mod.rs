@@ -0,0 +1,3 @@
+pub mod get_audit_logs;
+pub mod update_organization_avatar;
+pub mod update_two_factor_policy;
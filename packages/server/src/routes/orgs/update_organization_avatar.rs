@@ -0,0 +1,112 @@
+use crate::{
+    audit::{AuditContext, AuditEvent},
+    auth::get_user_membership,
+    avatar::{upload_avatar, AvatarOwner, AvatarUpload},
+    error::AppError,
+    i18n::t,
+    AppState,
+};
+use api_types::avatar::AvatarResponse;
+use axum::{
+    extract::{Multipart, Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use axum_auth::AuthBearer;
+use entities::{members, organizations};
+use rbac::{get_user_permission, Action, Resource};
+use sea_orm::{ActiveModelTrait, DbErr, Set, TransactionTrait};
+use serde_json::json;
+
+#[utoipa::path(
+    put,
+    path = "/organizations/{slug}/avatar",
+    tag = "Organizations",
+    params(
+        ("slug" = String, Path, description = "Organization slug")
+    ),
+    request_body(content = AvatarUpload, content_type = "multipart/form-data"),
+    security(
+        ("token" = [])
+    ),
+    responses(
+        (status = 200, description = "Avatar uploaded", body = AvatarResponse),
+        (status = 400, description = "Missing or invalid image"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden"),
+        (status = 413, description = "Image too large"),
+        (status = 415, description = "Unsupported image type"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+/// Upload the organization avatar
+pub async fn update_organization_avatar(
+    State(state): State<AppState>,
+    audit: AuditContext,
+    Path(slug): Path<String>,
+    AuthBearer(token): AuthBearer,
+    multipart: Multipart,
+) -> Result<impl IntoResponse, AppError> {
+    let membership_response = get_user_membership(&state, &slug, &token).await?;
+    let membership_data = membership_response.as_object().unwrap();
+
+    let membership_json = membership_data.get("membership").unwrap();
+    let membership: members::Model = serde_json::from_value(membership_json.clone()).unwrap();
+
+    let organization_json = membership_data.get("organization").unwrap();
+    let organization: organizations::Model =
+        serde_json::from_value(organization_json.clone()).unwrap();
+
+    let ability = get_user_permission(membership.user_id, membership.role);
+
+    if ability.cannot(&Action::Update, &Resource::Organization(&organization)) {
+        AuditEvent::permission_denied(
+            membership.user_id,
+            organization.id,
+            &Action::Update,
+            "Organization",
+        )
+        .record(&state.db, &audit)
+        .await?;
+
+        return Err(AppError::forbidden(t("organization.update_forbidden")));
+    }
+
+    let avatar_url = upload_avatar(
+        &state,
+        AvatarOwner::Organization(organization.id),
+        multipart,
+    )
+    .await?;
+
+    let organization_id = organization.id;
+    let actor_id = membership.user_id;
+    let before = json!({ "avatarUrl": organization.avatar_url });
+    let after = json!({ "avatarUrl": avatar_url });
+
+    let mut organization: organizations::ActiveModel = organization.into();
+    organization.avatar_url = Set(Some(avatar_url.clone()));
+
+    state
+        .db
+        .transaction(|txn| {
+            Box::pin(async move {
+                organization.update(txn).await?;
+
+                AuditEvent::new("organization.avatar_updated")
+                    .actor(actor_id)
+                    .organization(organization_id)
+                    .target("organization", organization_id)
+                    .before(before)
+                    .after(after)
+                    .record(txn, &audit)
+                    .await?;
+
+                Ok::<_, DbErr>(())
+            })
+        })
+        .await?;
+
+    Ok((StatusCode::OK, Json(AvatarResponse { avatar_url })))
+}
@@ -0,0 +1,100 @@
+use crate::{
+    audit::{AuditContext, AuditEvent},
+    auth::get_user_membership,
+    error::AppError,
+    extract::ValidatedJson,
+    i18n::t,
+    AppState,
+};
+use api_types::orgs::UpdateTwoFactorPolicyBody;
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+};
+use axum_auth::AuthBearer;
+use entities::{members, organizations};
+use rbac::{get_user_permission, Action, Resource};
+use sea_orm::{ActiveModelTrait, DbErr, Set, TransactionTrait};
+use serde_json::json;
+
+#[utoipa::path(
+    put,
+    path = "/organizations/{slug}/two-factor",
+    tag = "Organizations",
+    params(
+        ("slug" = String, Path, description = "Organization slug")
+    ),
+    request_body(content = UpdateTwoFactorPolicyBody, example = json!({ "required": true })),
+    security(
+        ("token" = [])
+    ),
+    responses(
+        (status = 204, description = "Two-factor policy updated"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+/// Require two-factor authentication for all organization members
+pub async fn update_two_factor_policy(
+    State(state): State<AppState>,
+    audit: AuditContext,
+    Path(slug): Path<String>,
+    AuthBearer(token): AuthBearer,
+    ValidatedJson(body): ValidatedJson<UpdateTwoFactorPolicyBody>,
+) -> Result<impl IntoResponse, AppError> {
+    let membership_response = get_user_membership(&state, &slug, &token).await?;
+    let membership_data = membership_response.as_object().unwrap();
+
+    let membership_json = membership_data.get("membership").unwrap();
+    let membership: members::Model = serde_json::from_value(membership_json.clone()).unwrap();
+
+    let organization_json = membership_data.get("organization").unwrap();
+    let organization: organizations::Model =
+        serde_json::from_value(organization_json.clone()).unwrap();
+
+    let ability = get_user_permission(membership.user_id, membership.role);
+
+    if ability.cannot(&Action::Update, &Resource::Organization(&organization)) {
+        AuditEvent::permission_denied(
+            membership.user_id,
+            organization.id,
+            &Action::Update,
+            "Organization",
+        )
+        .record(&state.db, &audit)
+        .await?;
+
+        return Err(AppError::forbidden(t("organization.update_forbidden")));
+    }
+
+    let organization_id = organization.id;
+    let actor_id = membership.user_id;
+    let before = json!({ "requireTwoFactor": organization.require_two_factor });
+
+    let mut organization: organizations::ActiveModel = organization.into();
+    organization.require_two_factor = Set(body.required);
+
+    state
+        .db
+        .transaction(|txn| {
+            Box::pin(async move {
+                organization.update(txn).await?;
+
+                AuditEvent::new("organization.two_factor_policy_updated")
+                    .actor(actor_id)
+                    .organization(organization_id)
+                    .target("organization", organization_id)
+                    .before(before)
+                    .after(json!({ "requireTwoFactor": body.required }))
+                    .record(txn, &audit)
+                    .await?;
+
+                Ok::<_, DbErr>(())
+            })
+        })
+        .await?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
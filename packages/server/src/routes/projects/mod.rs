@@ -0,0 +1 @@
+pub mod update_project_avatar;
@@ -0,0 +1,124 @@
+use crate::{
+    audit::{AuditContext, AuditEvent},
+    auth::get_user_membership,
+    avatar::{upload_avatar, AvatarOwner, AvatarUpload},
+    error::AppError,
+    i18n::t,
+    AppState,
+};
+use api_types::avatar::AvatarResponse;
+use axum::{
+    extract::{Multipart, Path, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Json,
+};
+use axum_auth::AuthBearer;
+use entities::{members, organizations, projects};
+use rbac::{get_user_permission, Action, Resource};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DbErr, EntityTrait, QueryFilter, Set, TransactionTrait,
+};
+use serde_json::json;
+
+#[utoipa::path(
+    put,
+    path = "/organizations/{slug}/projects/{project_slug}/avatar",
+    tag = "Projects",
+    params(
+        ("slug" = String, Path, description = "Organization slug"),
+        ("project_slug" = String, Path, description = "Project slug")
+    ),
+    request_body(content = AvatarUpload, content_type = "multipart/form-data"),
+    security(
+        ("token" = [])
+    ),
+    responses(
+        (status = 200, description = "Avatar uploaded", body = AvatarResponse),
+        (status = 400, description = "Missing or invalid image"),
+        (status = 401, description = "Unauthorized"),
+        (status = 403, description = "Forbidden"),
+        (status = 404, description = "Project not found"),
+        (status = 413, description = "Image too large"),
+        (status = 415, description = "Unsupported image type"),
+        (status = 500, description = "Internal server error")
+    )
+)]
+/// Upload a project avatar
+pub async fn update_project_avatar(
+    State(state): State<AppState>,
+    audit: AuditContext,
+    Path((slug, project_slug)): Path<(String, String)>,
+    AuthBearer(token): AuthBearer,
+    multipart: Multipart,
+) -> Result<impl IntoResponse, AppError> {
+    let membership_response = get_user_membership(&state, &slug, &token).await?;
+    let membership_data = membership_response.as_object().unwrap();
+
+    let membership_json = membership_data.get("membership").unwrap();
+    let membership: members::Model = serde_json::from_value(membership_json.clone()).unwrap();
+
+    let organization_json = membership_data.get("organization").unwrap();
+    let organization: organizations::Model =
+        serde_json::from_value(organization_json.clone()).unwrap();
+
+    let project = match projects::Entity::find()
+        .filter(projects::Column::Slug.eq(project_slug))
+        .filter(projects::Column::OrganizationId.eq(organization.id))
+        .one(&state.db)
+        .await?
+    {
+        Some(project) => project,
+        None => {
+            return Err(AppError::not_found(t("project.not_found")));
+        }
+    };
+
+    let ability = get_user_permission(membership.user_id, membership.role);
+
+    if ability.cannot(&Action::Update, &Resource::Project(&project)) {
+        AuditEvent::permission_denied(
+            membership.user_id,
+            organization.id,
+            &Action::Update,
+            "Project",
+        )
+        .record(&state.db, &audit)
+        .await?;
+
+        return Err(AppError::forbidden(t("project.update_forbidden")));
+    }
+
+    let avatar_url = upload_avatar(&state, AvatarOwner::Project(project.id), multipart).await?;
+
+    let project_id = project.id;
+    let actor_id = membership.user_id;
+    let organization_id = organization.id;
+    let before = json!({ "avatarUrl": project.avatar_url });
+    let after = json!({ "avatarUrl": avatar_url });
+
+    let mut project: projects::ActiveModel = project.into();
+    project.avatar_url = Set(Some(avatar_url.clone()));
+
+    state
+        .db
+        .transaction(|txn| {
+            Box::pin(async move {
+                project.update(txn).await?;
+
+                AuditEvent::new("project.avatar_updated")
+                    .actor(actor_id)
+                    .organization(organization_id)
+                    .target("project", project_id)
+                    .before(before)
+                    .after(after)
+                    .record(txn, &audit)
+                    .await?;
+
+                Ok::<_, DbErr>(())
+            })
+        })
+        .await?;
+
+    Ok((StatusCode::OK, Json(AvatarResponse { avatar_url })))
+}
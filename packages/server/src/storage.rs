@@ -0,0 +1,151 @@
+//! File storage for user uploads. [`LocalStorage`] writes to disk and is
+//! served by the API itself; other backends (S3, GCS) can implement
+//! [`Storage`].
+
+use std::{
+    fmt,
+    future::Future,
+    path::{Component, Path, PathBuf},
+    pin::Pin,
+};
+
+pub type StorageFuture<'a, T> = Pin<Box<dyn Future<Output = Result<T, StorageError>> + Send + 'a>>;
+
+#[derive(Debug)]
+pub struct StorageError(pub String);
+
+impl fmt::Display for StorageError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+pub trait Storage: Send + Sync {
+    /// Stores `bytes` under `key`, replacing any previous object, and returns
+    /// its public URL.
+    fn put<'a>(
+        &'a self,
+        key: &'a str,
+        bytes: Vec<u8>,
+        content_type: &'a str,
+    ) -> StorageFuture<'a, String>;
+
+    /// Reads the object under `key`.
+    fn get<'a>(&'a self, key: &'a str) -> StorageFuture<'a, Vec<u8>>;
+
+    /// Removes the object under `key`. Missing objects are not an error.
+    fn delete<'a>(&'a self, key: &'a str) -> StorageFuture<'a, ()>;
+}
+
+#[derive(Debug, Clone)]
+pub struct LocalStorage {
+    root: PathBuf,
+    public_url: String,
+}
+
+impl LocalStorage {
+    /// `public_url` is where `root` is served, e.g. `http://localhost:3000/uploads`.
+    pub fn new(root: impl Into<PathBuf>, public_url: impl Into<String>) -> Self {
+        Self {
+            root: root.into(),
+            public_url: public_url.into().trim_end_matches('/').to_string(),
+        }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    fn path(&self, key: &str) -> Result<PathBuf, StorageError> {
+        let key = Path::new(key);
+
+        if !key
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)))
+        {
+            return Err(StorageError(format!("Invalid storage key {:?}", key)));
+        }
+
+        Ok(self.root.join(key))
+    }
+}
+
+impl Storage for LocalStorage {
+    fn put<'a>(
+        &'a self,
+        key: &'a str,
+        bytes: Vec<u8>,
+        _content_type: &'a str,
+    ) -> StorageFuture<'a, String> {
+        Box::pin(async move {
+            let path = self.path(key)?;
+
+            if let Some(parent) = path.parent() {
+                tokio::fs::create_dir_all(parent)
+                    .await
+                    .map_err(|e| StorageError(e.to_string()))?;
+            }
+
+            tokio::fs::write(&path, bytes)
+                .await
+                .map_err(|e| StorageError(e.to_string()))?;
+
+            Ok(format!("{}/{}", self.public_url, key))
+        })
+    }
+
+    fn get<'a>(&'a self, key: &'a str) -> StorageFuture<'a, Vec<u8>> {
+        Box::pin(async move {
+            tokio::fs::read(self.path(key)?)
+                .await
+                .map_err(|e| StorageError(e.to_string()))
+        })
+    }
+
+    fn delete<'a>(&'a self, key: &'a str) -> StorageFuture<'a, ()> {
+        Box::pin(async move {
+            match tokio::fs::remove_file(self.path(key)?).await {
+                Ok(()) => Ok(()),
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+                Err(e) => Err(StorageError(e.to_string())),
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_local_storage_round_trip() {
+        let root = std::env::temp_dir().join(format!("storage-{}", uuid::Uuid::new_v4()));
+        let storage = LocalStorage::new(&root, "http://localhost:3000/uploads/");
+
+        let url = storage
+            .put("avatars/users/1/256.png", vec![1, 2, 3], "image/png")
+            .await
+            .unwrap();
+
+        assert_eq!(url, "http://localhost:3000/uploads/avatars/users/1/256.png");
+        assert_eq!(
+            std::fs::read(root.join("avatars/users/1/256.png")).unwrap(),
+            [1, 2, 3]
+        );
+        assert_eq!(
+            storage.get("avatars/users/1/256.png").await.unwrap(),
+            [1, 2, 3]
+        );
+
+        storage.delete("avatars/users/1/256.png").await.unwrap();
+        storage.delete("avatars/users/1/256.png").await.unwrap();
+        assert!(!root.join("avatars/users/1/256.png").exists());
+
+        assert!(storage
+            .put("../escape.png", vec![], "image/png")
+            .await
+            .is_err());
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
+}
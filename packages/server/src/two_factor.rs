@@ -0,0 +1,207 @@
+use crate::{challenge, error::AppError, i18n::t, AppState};
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use entities::users;
+use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
+use sea_orm::{sea_query::Expr, ColumnTrait, Condition, ConnectionTrait, EntityTrait, QueryFilter};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    str::FromStr,
+    time::{SystemTime, UNIX_EPOCH},
+};
+use time::{Duration, OffsetDateTime};
+use totp_rs::{Algorithm, Secret, TOTP};
+use uuid::Uuid;
+
+const ISSUER: &str = "Rust SaaS RBAC";
+const MFA_AUDIENCE: &str = "mfa";
+const RECOVERY_CODES_COUNT: usize = 10;
+const STEP_SECS: u64 = 30;
+/// Time steps either side of the current one a code is still accepted from.
+const SKEW_STEPS: u64 = 1;
+const MFA_TOKEN_TTL: Duration = Duration::minutes(5);
+/// Codes that can be tried with one MFA token before signing in again.
+pub const MFA_TOKEN_ATTEMPTS: i32 = 3;
+
+/// Claims of the short-lived token returned by the first step of a login when
+/// the user has 2FA enabled. The `aud` claim makes regular token validation
+/// reject it, so it can only be exchanged at `/sessions/two-factor`, and `jti`
+/// is the [`challenge`] that limits its attempts and single use.
+#[derive(Debug, Serialize, Deserialize)]
+struct MfaClaims {
+    sub: String,
+    jti: String,
+    exp: i64,
+    aud: String,
+}
+
+/// A decoded MFA token.
+#[derive(Debug, Clone, Copy)]
+pub struct MfaToken {
+    pub user_id: Uuid,
+    /// Id of the challenge recorded when the token was issued.
+    pub challenge_id: Uuid,
+}
+
+/// Generates a new base32-encoded TOTP secret.
+pub fn generate_secret() -> String {
+    match Secret::generate_secret().to_encoded() {
+        Secret::Encoded(secret) => secret,
+        Secret::Raw(_) => unreachable!(),
+    }
+}
+
+fn totp(secret: &str, email: &str) -> Result<TOTP, AppError> {
+    let bytes = Secret::Encoded(secret.to_string())
+        .to_bytes()
+        .map_err(|e| AppError::internal(format!("Invalid TOTP secret: {:?}", e)))?;
+
+    // Sem skew aqui: `code_step` percorre os passos vizinhos para saber qual casou
+    TOTP::new(
+        Algorithm::SHA1,
+        6,
+        0,
+        STEP_SECS,
+        bytes,
+        Some(ISSUER.to_string()),
+        email.to_string(),
+    )
+    .map_err(|e| AppError::internal(format!("Invalid TOTP parameters: {}", e)))
+}
+
+/// Builds the `otpauth://` URI scanned by authenticator apps.
+pub fn otpauth_uri(secret: &str, email: &str) -> Result<String, AppError> {
+    Ok(totp(secret, email)?.get_url())
+}
+
+/// Finds the time step a TOTP code was generated for, among the current one
+/// and [`SKEW_STEPS`] either side.
+fn code_step(secret: &str, email: &str, code: &str) -> Result<Option<u64>, AppError> {
+    let totp = totp(secret, email)?;
+    let current = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_err(|e| AppError::internal(format!("System time error: {}", e)))?
+        .as_secs()
+        / STEP_SECS;
+
+    Ok((current.saturating_sub(SKEW_STEPS)..=current + SKEW_STEPS)
+        .find(|step| totp.check(code, step * STEP_SECS)))
+}
+
+/// Checks a TOTP code of `user` and records its time step as their last one.
+/// A code at or before that step is refused, so each code works only once
+/// even within its skew window, including for concurrent requests.
+pub async fn accept_code<C: ConnectionTrait>(
+    db: &C,
+    user: &users::Model,
+    secret: &str,
+    code: &str,
+) -> Result<bool, AppError> {
+    let Some(step) = code_step(secret, &user.email, code)? else {
+        return Ok(false);
+    };
+    let step = step as i64;
+
+    let accepted = users::Entity::update_many()
+        .col_expr(users::Column::TotpLastStep, Expr::value(step))
+        .filter(users::Column::Id.eq(user.id))
+        .filter(
+            Condition::any()
+                .add(users::Column::TotpLastStep.is_null())
+                .add(users::Column::TotpLastStep.lt(step)),
+        )
+        .exec(db)
+        .await?;
+
+    Ok(accepted.rows_affected == 1)
+}
+
+/// Generates one-time recovery codes formatted as `xxxxx-xxxxx`.
+pub fn generate_recovery_codes() -> Vec<String> {
+    (0..RECOVERY_CODES_COUNT)
+        .map(|_| {
+            let mut bytes = [0u8; 5];
+            OsRng.fill_bytes(&mut bytes);
+            let hex: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+            format!("{}-{}", &hex[..5], &hex[5..])
+        })
+        .collect()
+}
+
+/// Recovery codes are random, so a fast hash is enough to store them.
+pub fn hash_recovery_code(code: &str) -> String {
+    let normalized = code.trim().to_lowercase();
+    format!("{:x}", Sha256::digest(normalized.as_bytes()))
+}
+
+pub async fn encode_mfa_token(user_id: Uuid, state: &AppState) -> Result<String, AppError> {
+    let challenge_id = challenge::issue(&state.db, MFA_TOKEN_TTL.unsigned_abs()).await?;
+
+    let claims = MfaClaims {
+        sub: user_id.to_string(),
+        jti: challenge_id.to_string(),
+        exp: (OffsetDateTime::now_utc() + MFA_TOKEN_TTL).unix_timestamp(),
+        aud: MFA_AUDIENCE.to_string(),
+    };
+
+    encode(
+        &Header::default(),
+        &claims,
+        &EncodingKey::from_secret(state.config.jwt_secret.as_ref()),
+    )
+    .map_err(|e| AppError::internal(format!("Failed to generate MFA token: {}", e)))
+}
+
+pub fn decode_mfa_token(token: &str, state: &AppState) -> Result<MfaToken, AppError> {
+    let mut validation = Validation::default();
+    validation.set_audience(&[MFA_AUDIENCE]);
+
+    let token_data = decode::<MfaClaims>(
+        token,
+        &DecodingKey::from_secret(state.config.jwt_secret.as_ref()),
+        &validation,
+    )
+    .map_err(|_| AppError::Unauthorized(t("two_factor.invalid_mfa_token")))?;
+
+    let user_id = Uuid::from_str(&token_data.claims.sub)
+        .map_err(|_| AppError::Unauthorized(t("auth.invalid_token_subject")))?;
+    let challenge_id = Uuid::from_str(&token_data.claims.jti)
+        .map_err(|_| AppError::Unauthorized(t("two_factor.invalid_mfa_token")))?;
+
+    Ok(MfaToken {
+        user_id,
+        challenge_id,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_code_step_allows_one_step_of_skew() {
+        let secret = generate_secret();
+        let totp = totp(&secret, "john@acme.com").unwrap();
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        let step = |secs: u64| code_step(&secret, "john@acme.com", &totp.generate(secs)).unwrap();
+
+        assert_eq!(step(now), Some(now / STEP_SECS));
+        assert_eq!(step(now - STEP_SECS), Some(now / STEP_SECS - 1));
+        assert_eq!(step(now - 3 * STEP_SECS), None);
+    }
+
+    #[test]
+    fn test_recovery_codes_format_and_normalized_hash() {
+        let codes = generate_recovery_codes();
+
+        assert_eq!(codes.len(), RECOVERY_CODES_COUNT);
+        assert!(codes.iter().all(|c| c.len() == 11));
+        assert_eq!(
+            hash_recovery_code(&codes[0]),
+            hash_recovery_code(&format!(" {} ", codes[0].to_uppercase()))
+        );
+    }
+}
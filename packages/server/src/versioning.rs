@@ -0,0 +1,120 @@
+//! API versions and the headers announcing retired endpoints.
+//!
+//! Every version is mounted under its own prefix (`/v1/...`). A breaking
+//! change goes into a new version while the old one keeps working until its
+//! sunset, answering with `Deprecation` (RFC 9745), `Sunset` (RFC 8594) and a
+//! `successor-version` link so clients can notice before it's gone.
+
+use axum::{
+    extract::{Request, State},
+    http::{header, HeaderName, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+use chrono::{DateTime, TimeZone, Utc};
+
+pub const DEPRECATION_HEADER: HeaderName = HeaderName::from_static("deprecation");
+pub const SUNSET_HEADER: HeaderName = HeaderName::from_static("sunset");
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ApiVersion {
+    V1,
+}
+
+impl ApiVersion {
+    /// Every version still served, oldest first.
+    pub const ALL: [ApiVersion; 1] = [ApiVersion::V1];
+    pub const LATEST: ApiVersion = ApiVersion::V1;
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ApiVersion::V1 => "v1",
+        }
+    }
+
+    /// Path the version is mounted at, e.g. `/v1`.
+    pub fn prefix(self) -> String {
+        format!("/{}", self.as_str())
+    }
+}
+
+/// When an endpoint stopped being recommended and when it goes away.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Deprecation {
+    pub since: DateTime<Utc>,
+    pub sunset: DateTime<Utc>,
+    /// Version with the replacement, at the same path under its prefix.
+    pub successor: Option<ApiVersion>,
+}
+
+impl Deprecation {
+    fn headers(&self, path: &str) -> Vec<(HeaderName, String)> {
+        let mut headers = vec![
+            (DEPRECATION_HEADER, format!("@{}", self.since.timestamp())),
+            (
+                SUNSET_HEADER,
+                self.sunset.format("%a, %d %b %Y %H:%M:%S GMT").to_string(),
+            ),
+        ];
+
+        if let Some(successor) = self.successor {
+            headers.push((
+                header::LINK,
+                format!(
+                    "<{}{}>; rel=\"successor-version\"",
+                    successor.prefix(),
+                    path
+                ),
+            ));
+        }
+
+        headers
+    }
+}
+
+/// The unversioned paths, kept as aliases of `/v1` while clients migrate.
+pub fn unversioned_deprecation() -> Deprecation {
+    Deprecation {
+        since: Utc.with_ymd_and_hms(2026, 10, 18, 0, 0, 0).unwrap(),
+        sunset: Utc.with_ymd_and_hms(2027, 4, 18, 0, 0, 0).unwrap(),
+        successor: Some(ApiVersion::V1),
+    }
+}
+
+/// Middleware marking every response of the routes it wraps as deprecated.
+pub async fn deprecated(
+    State(deprecation): State<Deprecation>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let path = request.uri().path().to_owned();
+    let mut response = next.run(request).await;
+
+    for (name, value) in deprecation.headers(&path) {
+        if let Ok(value) = HeaderValue::from_str(&value) {
+            response.headers_mut().insert(name, value);
+        }
+    }
+
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_headers_announce_sunset_and_successor() {
+        assert_eq!(
+            unversioned_deprecation().headers("/profile"),
+            vec![
+                (DEPRECATION_HEADER, "@1792281600".to_owned()),
+                (SUNSET_HEADER, "Sun, 18 Apr 2027 00:00:00 GMT".to_owned()),
+                (
+                    header::LINK,
+                    "</v1/profile>; rel=\"successor-version\"".to_owned()
+                ),
+            ]
+        );
+    }
+}
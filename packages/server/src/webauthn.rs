@@ -0,0 +1,614 @@
+//! Minimal WebAuthn relying party: ES256 credentials with `none` attestation.
+
+use crate::{
+    challenge,
+    error::AppError,
+    i18n::{t, t_args},
+    AppState,
+};
+use api_types::webauthn::{AuthenticationCredential, RegistrationCredential};
+use argon2::password_hash::rand_core::{OsRng, RngCore};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use ciborium::Value;
+use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
+use p256::ecdsa::{signature::Verifier, Signature, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use time::{Duration, OffsetDateTime};
+use uuid::Uuid;
+
+const CHALLENGE_TTL: Duration = Duration::minutes(5);
+const COSE_ALG_ES256: i64 = -7;
+const FLAG_USER_PRESENT: u8 = 0x01;
+const FLAG_USER_VERIFIED: u8 = 0x04;
+const FLAG_ATTESTED_CREDENTIAL_DATA: u8 = 0x40;
+
+#[derive(Clone)]
+pub struct WebauthnConfig {
+    pub rp_id: String,
+    pub rp_name: String,
+    pub origin: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Ceremony {
+    Registration,
+    Authentication,
+}
+
+impl Ceremony {
+    fn audience(self) -> &'static str {
+        match self {
+            Ceremony::Registration => "webauthn.create",
+            Ceremony::Authentication => "webauthn.get",
+        }
+    }
+}
+
+/// Claims of the token that carries a ceremony challenge between the options
+/// and verification requests. `jti` is the [`challenge`] record the handler
+/// consumes, so each challenge is only good for one ceremony.
+#[derive(Debug, Serialize, Deserialize)]
+struct ChallengeClaims {
+    sub: Option<String>,
+    jti: String,
+    challenge: String,
+    exp: i64,
+    aud: String,
+}
+
+#[derive(Deserialize)]
+struct ClientData {
+    r#type: String,
+    challenge: String,
+    origin: String,
+}
+
+/// Credential extracted from a verified registration.
+#[derive(Debug)]
+pub struct VerifiedCredential {
+    pub credential_id: String,
+    pub public_key: String,
+    pub sign_count: u32,
+    /// Challenge to consume before storing the credential.
+    pub challenge_id: Uuid,
+}
+
+/// Outcome of a verified authentication assertion.
+#[derive(Debug)]
+pub struct VerifiedAssertion {
+    pub sign_count: u32,
+    /// Challenge to consume before signing the user in.
+    pub challenge_id: Uuid,
+}
+
+/// A decoded challenge token.
+struct OpenedChallenge {
+    challenge: String,
+    subject: Option<String>,
+    id: Uuid,
+}
+
+fn invalid_credential(reason: &str) -> AppError {
+    AppError::bad_request(t_args("passkey.invalid_credential", &[("reason", &reason)]))
+}
+
+fn decode_b64(value: &str, field: &str) -> Result<Vec<u8>, AppError> {
+    URL_SAFE_NO_PAD
+        .decode(value.trim_end_matches('='))
+        .map_err(|_| invalid_credential(&format!("{} is not base64url", field)))
+}
+
+/// Generates a random challenge, records it and returns it with the signed
+/// token carrying it.
+pub async fn new_challenge(
+    ceremony: Ceremony,
+    user_id: Option<Uuid>,
+    state: &AppState,
+) -> Result<(String, String), AppError> {
+    let challenge_id = challenge::issue(&state.db, CHALLENGE_TTL.unsigned_abs()).await?;
+
+    sign_challenge(ceremony, user_id, challenge_id, state)
+}
+
+fn sign_challenge(
+    ceremony: Ceremony,
+    user_id: Option<Uuid>,
+    challenge_id: Uuid,
+    state: &AppState,
+) -> Result<(String, String), AppError> {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    let challenge = URL_SAFE_NO_PAD.encode(bytes);
+
+    let claims = ChallengeClaims {
+        sub: user_id.map(|id| id.to_string()),
+        jti: challenge_id.to_string(),
+        challenge: challenge.clone(),
+        exp: (OffsetDateTime::now_utc() + CHALLENGE_TTL).unix_timestamp(),
+        aud: ceremony.audience().to_string(),
+    };
+
+    let token = encode(
+        &Header::default(),
+        &claims,
+        &EncodingKey::from_secret(state.config.jwt_secret.as_ref()),
+    )
+    .map_err(|e| AppError::internal(format!("Failed to generate challenge token: {}", e)))?;
+
+    Ok((challenge, token))
+}
+
+fn open_challenge(
+    ceremony: Ceremony,
+    token: &str,
+    state: &AppState,
+) -> Result<OpenedChallenge, AppError> {
+    let mut validation = Validation::default();
+    validation.set_audience(&[ceremony.audience()]);
+
+    let data = decode::<ChallengeClaims>(
+        token,
+        &DecodingKey::from_secret(state.config.jwt_secret.as_ref()),
+        &validation,
+    )
+    .map_err(|_| AppError::Unauthorized(t("passkey.invalid_challenge_token")))?;
+
+    let id = data
+        .claims
+        .jti
+        .parse()
+        .map_err(|_| AppError::Unauthorized(t("passkey.invalid_challenge_token")))?;
+
+    Ok(OpenedChallenge {
+        challenge: data.claims.challenge,
+        subject: data.claims.sub,
+        id,
+    })
+}
+
+/// Uses up the challenge of a verified ceremony, so a replayed credential is
+/// rejected even when the authenticator keeps its counter at zero.
+pub async fn consume_challenge(challenge_id: Uuid, state: &AppState) -> Result<(), AppError> {
+    if !challenge::consume(&state.db, challenge_id).await? {
+        return Err(AppError::Unauthorized(t("passkey.invalid_challenge_token")));
+    }
+
+    Ok(())
+}
+
+fn verify_client_data(
+    ceremony: Ceremony,
+    client_data_json: &[u8],
+    challenge: &str,
+    config: &WebauthnConfig,
+) -> Result<(), AppError> {
+    let client_data: ClientData = serde_json::from_slice(client_data_json)
+        .map_err(|_| invalid_credential("malformed clientDataJSON"))?;
+
+    if client_data.r#type != ceremony.audience() {
+        return Err(invalid_credential("unexpected ceremony type"));
+    }
+    if client_data.challenge.trim_end_matches('=') != challenge {
+        return Err(invalid_credential("challenge mismatch"));
+    }
+    if client_data.origin != config.origin {
+        return Err(invalid_credential("origin mismatch"));
+    }
+
+    Ok(())
+}
+
+/// Checks the RP ID hash and flags, returning the signature counter.
+fn verify_authenticator_data(
+    auth_data: &[u8],
+    required_flags: u8,
+    config: &WebauthnConfig,
+) -> Result<u32, AppError> {
+    if auth_data.len() < 37 {
+        return Err(invalid_credential("authenticator data too short"));
+    }
+    if auth_data[..32] != Sha256::digest(config.rp_id.as_bytes())[..] {
+        return Err(invalid_credential("relying party mismatch"));
+    }
+    if auth_data[32] & required_flags != required_flags {
+        return Err(invalid_credential("missing authenticator flags"));
+    }
+
+    Ok(u32::from_be_bytes([
+        auth_data[33],
+        auth_data[34],
+        auth_data[35],
+        auth_data[36],
+    ]))
+}
+
+fn cbor_map_get(map: &[(Value, Value)], key: i64) -> Option<&Value> {
+    map.iter()
+        .find(|(k, _)| k.as_integer().and_then(|k| i64::try_from(k).ok()) == Some(key))
+        .map(|(_, v)| v)
+}
+
+/// Converts an EC2 P-256 COSE key into SEC1 uncompressed bytes.
+fn cose_to_sec1(cose_key: &Value) -> Result<Vec<u8>, AppError> {
+    let map = cose_key
+        .as_map()
+        .ok_or_else(|| invalid_credential("public key is not a COSE map"))?;
+
+    let alg = cbor_map_get(map, 3)
+        .and_then(|v| v.as_integer())
+        .and_then(|v| i64::try_from(v).ok());
+    if alg != Some(COSE_ALG_ES256) {
+        return Err(invalid_credential("only ES256 passkeys are supported"));
+    }
+
+    let coordinate = |key| {
+        cbor_map_get(map, key)
+            .and_then(|v| v.as_bytes())
+            .filter(|b| b.len() == 32)
+            .ok_or_else(|| invalid_credential("malformed public key"))
+    };
+
+    let mut sec1 = vec![0x04];
+    sec1.extend_from_slice(coordinate(-2)?);
+    sec1.extend_from_slice(coordinate(-3)?);
+
+    VerifyingKey::from_sec1_bytes(&sec1).map_err(|_| invalid_credential("invalid public key"))?;
+
+    Ok(sec1)
+}
+
+/// Verifies a registration ceremony and extracts the new credential.
+pub fn verify_registration(
+    state: &AppState,
+    challenge_token: &str,
+    credential: &RegistrationCredential,
+) -> Result<(Uuid, VerifiedCredential), AppError> {
+    let opened = open_challenge(Ceremony::Registration, challenge_token, state)?;
+    let user_id = opened
+        .subject
+        .and_then(|sub| sub.parse().ok())
+        .ok_or_else(AppError::unauthorized)?;
+
+    let client_data_json = decode_b64(&credential.response.client_data_json, "clientDataJSON")?;
+    verify_client_data(
+        Ceremony::Registration,
+        &client_data_json,
+        &opened.challenge,
+        &state.config.webauthn,
+    )?;
+
+    let attestation_object =
+        decode_b64(&credential.response.attestation_object, "attestationObject")?;
+    let attestation: Value = ciborium::from_reader(attestation_object.as_slice())
+        .map_err(|_| invalid_credential("malformed attestationObject"))?;
+    let auth_data = attestation
+        .as_map()
+        .and_then(|map| {
+            map.iter()
+                .find(|(k, _)| k.as_text() == Some("authData"))
+                .and_then(|(_, v)| v.as_bytes())
+        })
+        .ok_or_else(|| invalid_credential("missing authData"))?;
+
+    let sign_count = verify_authenticator_data(
+        auth_data,
+        FLAG_USER_PRESENT | FLAG_ATTESTED_CREDENTIAL_DATA,
+        &state.config.webauthn,
+    )?;
+
+    // Attested credential data: aaguid (16) | id length (2) | id | COSE key
+    let attested = &auth_data[37..];
+    if attested.len() < 18 {
+        return Err(invalid_credential("missing attested credential data"));
+    }
+    let id_len = u16::from_be_bytes([attested[16], attested[17]]) as usize;
+    let credential_id = attested
+        .get(18..18 + id_len)
+        .ok_or_else(|| invalid_credential("truncated credential id"))?;
+    if URL_SAFE_NO_PAD.encode(credential_id) != credential.id.trim_end_matches('=') {
+        return Err(invalid_credential("credential id mismatch"));
+    }
+
+    let cose_key: Value = ciborium::from_reader(&attested[18 + id_len..])
+        .map_err(|_| invalid_credential("malformed public key"))?;
+    let public_key = cose_to_sec1(&cose_key)?;
+
+    Ok((
+        user_id,
+        VerifiedCredential {
+            credential_id: URL_SAFE_NO_PAD.encode(credential_id),
+            public_key: URL_SAFE_NO_PAD.encode(public_key),
+            sign_count,
+            challenge_id: opened.id,
+        },
+    ))
+}
+
+/// Verifies an authentication assertion against a stored public key. The
+/// passkey replaces both password and second factor, so the authenticator
+/// must have verified the user, not only seen them present.
+pub fn verify_authentication(
+    state: &AppState,
+    challenge_token: &str,
+    credential: &AuthenticationCredential,
+    public_key: &str,
+    stored_sign_count: u32,
+) -> Result<VerifiedAssertion, AppError> {
+    let opened = open_challenge(Ceremony::Authentication, challenge_token, state)?;
+
+    let client_data_json = decode_b64(&credential.response.client_data_json, "clientDataJSON")?;
+    verify_client_data(
+        Ceremony::Authentication,
+        &client_data_json,
+        &opened.challenge,
+        &state.config.webauthn,
+    )?;
+
+    let auth_data = decode_b64(&credential.response.authenticator_data, "authenticatorData")?;
+    let sign_count = verify_authenticator_data(
+        &auth_data,
+        FLAG_USER_PRESENT | FLAG_USER_VERIFIED,
+        &state.config.webauthn,
+    )?;
+
+    let key = VerifyingKey::from_sec1_bytes(&decode_b64(public_key, "publicKey")?)
+        .map_err(|e| AppError::internal(format!("Stored passkey public key is invalid: {}", e)))?;
+    let signature = Signature::from_der(&decode_b64(&credential.response.signature, "signature")?)
+        .map_err(|_| invalid_credential("malformed signature"))?;
+
+    let mut signed = auth_data.clone();
+    signed.extend_from_slice(&Sha256::digest(&client_data_json));
+
+    if key.verify(&signed, &signature).is_err() {
+        return Err(AppError::forbidden(t("passkey.invalid_signature")));
+    }
+
+    // Authenticators that don't implement counters always report zero
+    if (sign_count != 0 || stored_sign_count != 0) && sign_count <= stored_sign_count {
+        return Err(AppError::forbidden(t("passkey.counter_not_increased")));
+    }
+
+    Ok(VerifiedAssertion {
+        sign_count,
+        challenge_id: opened.id,
+    })
+}
+
+/// Authenticator for tests, also used by the integration tests through the
+/// `test-util` feature.
+#[cfg(any(test, feature = "test-util"))]
+pub mod testing {
+    use super::*;
+    use api_types::webauthn::{AssertionResponse, AttestationResponse};
+    use p256::ecdsa::{signature::Signer, SigningKey};
+    use serde_json::json;
+
+    /// Software authenticator producing ES256 credentials with `none` attestation.
+    pub struct SoftwareAuthenticator {
+        pub key: SigningKey,
+        pub credential_id: Vec<u8>,
+        pub sign_count: u32,
+        /// Off for authenticators that always report a zero counter, like
+        /// most synced passkeys.
+        pub counts_signatures: bool,
+        /// Whether assertions carry the user verified flag.
+        pub verifies_user: bool,
+    }
+
+    impl Default for SoftwareAuthenticator {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+
+    impl SoftwareAuthenticator {
+        pub fn new() -> Self {
+            let mut credential_id = vec![0u8; 16];
+            OsRng.fill_bytes(&mut credential_id);
+            Self {
+                key: SigningKey::random(&mut OsRng),
+                credential_id,
+                sign_count: 0,
+                counts_signatures: true,
+                verifies_user: true,
+            }
+        }
+
+        fn count_signature(&mut self) {
+            if self.counts_signatures {
+                self.sign_count += 1;
+            }
+        }
+
+        fn client_data(ceremony: Ceremony, challenge: &str, origin: &str) -> Vec<u8> {
+            serde_json::to_vec(&json!({
+                "type": ceremony.audience(),
+                "challenge": challenge,
+                "origin": origin,
+            }))
+            .unwrap()
+        }
+
+        fn auth_data(&self, rp_id: &str, flags: u8) -> Vec<u8> {
+            let mut data = Sha256::digest(rp_id.as_bytes()).to_vec();
+            data.push(flags);
+            data.extend_from_slice(&self.sign_count.to_be_bytes());
+            data
+        }
+
+        pub fn register(
+            &mut self,
+            rp_id: &str,
+            origin: &str,
+            challenge: &str,
+        ) -> RegistrationCredential {
+            self.count_signature();
+            let point = self.key.verifying_key().to_encoded_point(false);
+            let cose_key = Value::Map(vec![
+                (Value::from(1), Value::from(2)),
+                (Value::from(3), Value::from(COSE_ALG_ES256)),
+                (Value::from(-1), Value::from(1)),
+                (Value::from(-2), Value::Bytes(point.x().unwrap().to_vec())),
+                (Value::from(-3), Value::Bytes(point.y().unwrap().to_vec())),
+            ]);
+
+            let mut auth_data =
+                self.auth_data(rp_id, FLAG_USER_PRESENT | FLAG_ATTESTED_CREDENTIAL_DATA);
+            auth_data.extend_from_slice(&[0u8; 16]);
+            auth_data.extend_from_slice(&(self.credential_id.len() as u16).to_be_bytes());
+            auth_data.extend_from_slice(&self.credential_id);
+            ciborium::into_writer(&cose_key, &mut auth_data).unwrap();
+
+            let attestation = Value::Map(vec![
+                (Value::from("fmt"), Value::from("none")),
+                (Value::from("attStmt"), Value::Map(vec![])),
+                (Value::from("authData"), Value::Bytes(auth_data)),
+            ]);
+            let mut attestation_object = Vec::new();
+            ciborium::into_writer(&attestation, &mut attestation_object).unwrap();
+
+            RegistrationCredential {
+                id: URL_SAFE_NO_PAD.encode(&self.credential_id),
+                response: AttestationResponse {
+                    client_data_json: URL_SAFE_NO_PAD.encode(Self::client_data(
+                        Ceremony::Registration,
+                        challenge,
+                        origin,
+                    )),
+                    attestation_object: URL_SAFE_NO_PAD.encode(attestation_object),
+                },
+            }
+        }
+
+        pub fn authenticate(
+            &mut self,
+            rp_id: &str,
+            origin: &str,
+            challenge: &str,
+        ) -> AuthenticationCredential {
+            self.count_signature();
+            let flags = if self.verifies_user {
+                FLAG_USER_PRESENT | FLAG_USER_VERIFIED
+            } else {
+                FLAG_USER_PRESENT
+            };
+            let auth_data = self.auth_data(rp_id, flags);
+            let client_data = Self::client_data(Ceremony::Authentication, challenge, origin);
+
+            let mut signed = auth_data.clone();
+            signed.extend_from_slice(&Sha256::digest(&client_data));
+            let signature: Signature = self.key.sign(&signed);
+
+            AuthenticationCredential {
+                id: URL_SAFE_NO_PAD.encode(&self.credential_id),
+                response: AssertionResponse {
+                    client_data_json: URL_SAFE_NO_PAD.encode(client_data),
+                    authenticator_data: URL_SAFE_NO_PAD.encode(auth_data),
+                    signature: URL_SAFE_NO_PAD.encode(signature.to_der()),
+                },
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::testing::SoftwareAuthenticator;
+    use super::*;
+    use crate::test_state;
+    use axum::http::StatusCode;
+    use uuid::Uuid;
+
+    #[test]
+    fn test_registration_and_authentication_ceremonies() {
+        let state = test_state();
+        let user_id = Uuid::new_v4();
+        let mut authenticator = SoftwareAuthenticator::new();
+
+        let (challenge, token) = sign_challenge(
+            Ceremony::Registration,
+            Some(user_id),
+            Uuid::new_v4(),
+            &state,
+        )
+        .unwrap();
+        let credential = authenticator.register("localhost", "http://localhost:8080", &challenge);
+        let (registered_user, verified) = verify_registration(&state, &token, &credential).unwrap();
+
+        assert_eq!(registered_user, user_id);
+        assert_eq!(verified.credential_id, credential.id);
+
+        let (challenge, token) =
+            sign_challenge(Ceremony::Authentication, None, Uuid::new_v4(), &state).unwrap();
+        let assertion =
+            authenticator.authenticate("localhost", "http://localhost:8080", &challenge);
+        let assertion = verify_authentication(
+            &state,
+            &token,
+            &assertion,
+            &verified.public_key,
+            verified.sign_count,
+        )
+        .unwrap();
+
+        assert_eq!(assertion.sign_count, 2);
+    }
+
+    #[test]
+    fn test_authentication_rejects_wrong_origin_old_counter_and_unverified_user() {
+        let state = test_state();
+        let mut authenticator = SoftwareAuthenticator::new();
+
+        let (challenge, token) = sign_challenge(
+            Ceremony::Registration,
+            Some(Uuid::new_v4()),
+            Uuid::new_v4(),
+            &state,
+        )
+        .unwrap();
+        let credential = authenticator.register("localhost", "http://localhost:8080", &challenge);
+        let (_, verified) = verify_registration(&state, &token, &credential).unwrap();
+
+        let (challenge, token) =
+            sign_challenge(Ceremony::Authentication, None, Uuid::new_v4(), &state).unwrap();
+        let assertion = authenticator.authenticate("localhost", "https://evil.example", &challenge);
+        let error =
+            verify_authentication(&state, &token, &assertion, &verified.public_key, 1).unwrap_err();
+        assert_eq!(error.status(), StatusCode::BAD_REQUEST);
+
+        let assertion =
+            authenticator.authenticate("localhost", "http://localhost:8080", &challenge);
+        let error = verify_authentication(&state, &token, &assertion, &verified.public_key, 10)
+            .unwrap_err();
+        assert_eq!(error.status(), StatusCode::FORBIDDEN);
+
+        authenticator.verifies_user = false;
+        let assertion =
+            authenticator.authenticate("localhost", "http://localhost:8080", &challenge);
+        let error =
+            verify_authentication(&state, &token, &assertion, &verified.public_key, 1).unwrap_err();
+        assert_eq!(error.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[test]
+    fn test_registration_token_cannot_be_used_for_authentication() {
+        let state = test_state();
+        let mut authenticator = SoftwareAuthenticator::new();
+
+        let (challenge, token) = sign_challenge(
+            Ceremony::Registration,
+            Some(Uuid::new_v4()),
+            Uuid::new_v4(),
+            &state,
+        )
+        .unwrap();
+        let assertion =
+            authenticator.authenticate("localhost", "http://localhost:8080", &challenge);
+
+        let status = verify_authentication(&state, &token, &assertion, "", 0)
+            .unwrap_err()
+            .status();
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+    }
+}
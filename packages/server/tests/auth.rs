@@ -0,0 +1,763 @@
+mod common;
+
+use api_client::{ApiClient, ClientError, GithubSession, PasswordSession};
+use api_types::{
+    auth::{
+        AuthenticateWithGithubBody, AuthenticateWithPasskeyBody, AuthenticateWithPasswordBody,
+        AuthenticateWithTwoFactorBody, AuthenticateWithTwoFactorResponse, ChangePasswordBody,
+        ConfirmEmailChangeBody, CreateAccountBody, DeleteAccountBody, DisableTwoFactorBody,
+        EnableTwoFactorBody, LinkAccountBody, Provider, RequestEmailChangeBody,
+        RequestPasswordRecoverBody, ResetPasswordRequest, UpdateProfileBody,
+    },
+    error::ErrorCode,
+};
+use common::{problem, uuid_in, TestApp, ORIGIN, PASSWORD, RP_ID, SEEDED_EMAIL, SEEDED_PASSWORD};
+use reqwest::Url;
+use server::webauthn::testing::SoftwareAuthenticator;
+use std::{
+    io::Cursor,
+    time::{SystemTime, UNIX_EPOCH},
+};
+use totp_rs::TOTP;
+
+fn password_login(email: &str, password: &str) -> AuthenticateWithPasswordBody {
+    AuthenticateWithPasswordBody {
+        email: email.into(),
+        password: password.into(),
+    }
+}
+
+fn unix_time() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+/// Hands out the codes of an authenticator app for increasing time steps,
+/// since the server takes each step only once.
+struct AuthenticatorApp {
+    totp: TOTP,
+    last_step: Option<u64>,
+}
+
+impl AuthenticatorApp {
+    fn new(otpauth_uri: &str) -> Self {
+        Self {
+            totp: TOTP::from_url(otpauth_uri).unwrap(),
+            last_step: None,
+        }
+    }
+
+    /// A code for the step after the last one, starting one step behind the
+    /// current time so a test gets three codes inside the skew window.
+    fn next_code(&mut self) -> String {
+        let mut now = unix_time();
+        if self.last_step.is_none() && now % self.totp.step > self.totp.step - 3 {
+            // O passo anterior sairia da janela na virada, então espera ela passar
+            std::thread::sleep(std::time::Duration::from_secs(3));
+            now = unix_time();
+        }
+
+        let current = now / self.totp.step;
+        let step = self.last_step.map_or(current - 1, |last| last + 1);
+        assert!(step <= current + 1, "Ran out of codes for this time step");
+
+        self.last_step = Some(step);
+        self.totp.generate(step * self.totp.step)
+    }
+}
+
+/// The MFA token returned by signing in `jane@example.com` with her password.
+async fn mfa_token(app: &TestApp) -> Result<String, ClientError> {
+    match app
+        .client()
+        .authenticate_with_password(&password_login("jane@example.com", PASSWORD))
+        .await?
+    {
+        PasswordSession::TwoFactorRequired(challenge) => Ok(challenge.mfa_token),
+        PasswordSession::Authenticated(_) => panic!("2FA was skipped"),
+    }
+}
+
+async fn send_code(
+    app: &TestApp,
+    mfa_token: &str,
+    code: &str,
+) -> Result<AuthenticateWithTwoFactorResponse, ClientError> {
+    app.client()
+        .authenticate_with_two_factor(&AuthenticateWithTwoFactorBody {
+            mfa_token: mfa_token.into(),
+            code: code.into(),
+        })
+        .await
+}
+
+/// Signs in `jane@example.com` with her password and then `code`.
+async fn two_factor_login(
+    app: &TestApp,
+    code: &str,
+) -> Result<AuthenticateWithTwoFactorResponse, ClientError> {
+    send_code(app, &mfa_token(app).await?, code).await
+}
+
+/// The `state` GitHub would send back from an authorize `url`.
+fn state_in(url: &str) -> String {
+    Url::parse(url)
+        .unwrap()
+        .query_pairs()
+        .find(|(key, _)| key == "state")
+        .map(|(_, value)| value.into_owned())
+        .expect("authorize URL without a state")
+}
+
+/// Follows `/sessions/github` as the browser would and returns the GitHub
+/// authorize URL it redirects to.
+async fn github_authorize(app: &TestApp) -> String {
+    let redirect = reqwest::Client::builder()
+        .redirect(reqwest::redirect::Policy::none())
+        .build()
+        .unwrap()
+        .get(app.client().github_authorize_url())
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(redirect.status(), 307);
+
+    redirect.headers()["location"].to_str().unwrap().into()
+}
+
+/// A GitHub login as if the user came back from the authorize page.
+async fn github_login(app: &TestApp) -> AuthenticateWithGithubBody {
+    AuthenticateWithGithubBody {
+        code: "code".into(),
+        state: state_in(&github_authorize(app).await),
+    }
+}
+
+/// A GitHub link for the user of `client`, as if they came back from the
+/// authorize page.
+async fn github_link(client: &ApiClient) -> LinkAccountBody {
+    let authorize = client
+        .link_account_authorize_url(Provider::Github)
+        .await
+        .unwrap();
+
+    LinkAccountBody {
+        code: "code".into(),
+        state: state_in(&authorize.url),
+    }
+}
+
+#[tokio::test]
+async fn test_create_account() {
+    let app = TestApp::spawn().await;
+    let client = app.client();
+    let body = |email: &str, password: &str| CreateAccountBody {
+        name: "Jane Doe".into(),
+        email: email.into(),
+        password: password.into(),
+    };
+
+    let created = client
+        .create_account(&body("jane@example.com", PASSWORD))
+        .await
+        .unwrap();
+    let profile = app.log_in("jane@example.com", PASSWORD).await;
+    assert_eq!(profile.get_profile().await.unwrap().id, created.user_id);
+
+    let duplicate = problem(
+        client
+            .create_account(&body("jane@example.com", PASSWORD))
+            .await,
+    );
+    assert_eq!(duplicate.code, ErrorCode::Conflict);
+    assert!(duplicate.errors.contains_key("email"));
+
+    let weak = problem(
+        client
+            .create_account(&body("joe@example.com", "short"))
+            .await,
+    );
+    assert_eq!(weak.code, ErrorCode::ValidationFailed);
+    assert!(weak.errors.contains_key("password"));
+}
+
+#[tokio::test]
+async fn test_authenticate_with_password() {
+    let app = TestApp::spawn().await;
+    app.create_user("Jane Doe", "jane@example.com").await;
+
+    let wrong = app
+        .client()
+        .authenticate_with_password(&password_login("jane@example.com", "Wrong-Horse-42"))
+        .await;
+    assert_eq!(problem(wrong).code, ErrorCode::Forbidden);
+
+    let unknown = app
+        .client()
+        .authenticate_with_password(&password_login("nobody@example.com", PASSWORD))
+        .await;
+    assert_eq!(problem(unknown).code, ErrorCode::Forbidden);
+
+    let client = app.log_in("jane@example.com", PASSWORD).await;
+    assert_eq!(
+        client.get_profile().await.unwrap().email,
+        "jane@example.com"
+    );
+
+    let forged = app.client().with_token("not-a-jwt").get_profile().await;
+    assert_eq!(problem(forged).code, ErrorCode::Unauthorized);
+}
+
+#[tokio::test]
+async fn test_update_profile_and_avatar() {
+    let app = TestApp::spawn().await;
+    let client = app.sign_up("jane@example.com").await;
+
+    client
+        .update_profile(&UpdateProfileBody {
+            name: Some("Jane Roe".into()),
+            avatar_url: None,
+        })
+        .await
+        .unwrap();
+    assert_eq!(
+        client.get_profile().await.unwrap().name.as_deref(),
+        Some("Jane Roe")
+    );
+
+    let mut png = Vec::new();
+    image::RgbImage::new(300, 300)
+        .write_to(&mut Cursor::new(&mut png), image::ImageFormat::Png)
+        .unwrap();
+    let avatar = client.update_avatar(png, "avatar.png").await.unwrap();
+
+    assert_eq!(
+        client.get_profile().await.unwrap().avatar_url,
+        Some(avatar.avatar_url.clone())
+    );
+    let served = reqwest::get(&avatar.avatar_url).await.unwrap();
+    assert_eq!(served.status(), 200);
+
+    let not_an_image = client.update_avatar(b"hello".to_vec(), "avatar.png").await;
+    assert_eq!(problem(not_an_image).code, ErrorCode::UnsupportedMediaType);
+}
+
+#[tokio::test]
+async fn test_change_password_revokes_other_sessions() {
+    let app = TestApp::spawn().await;
+    let client = app.sign_up("jane@example.com").await;
+    let other_session = app.log_in("jane@example.com", PASSWORD).await;
+    let body = |current: &str| ChangePasswordBody {
+        current_password: Some(current.into()),
+        new_password: "Battery-Staple-43".into(),
+    };
+
+    let wrong = client.change_password(&body("Wrong-Horse-42")).await;
+    assert_eq!(problem(wrong).code, ErrorCode::Forbidden);
+
+    let changed = client.change_password(&body(PASSWORD)).await.unwrap();
+
+    let client = app.client().with_token(changed.token);
+    assert!(client.get_profile().await.is_ok());
+    assert_eq!(
+        problem(other_session.get_profile().await).code,
+        ErrorCode::Unauthorized
+    );
+    app.log_in("jane@example.com", "Battery-Staple-43").await;
+}
+
+#[tokio::test]
+async fn test_wrong_current_passwords_lock_the_account() {
+    let app = TestApp::spawn().await;
+    let client = app.sign_up("jane@example.com").await;
+    let change_password = |current: &str| ChangePasswordBody {
+        current_password: Some(current.into()),
+        new_password: "Battery-Staple-43".into(),
+    };
+
+    // Uma sessão roubada não dá tentativas à parte das do login
+    for _ in 0..5 {
+        let wrong = client
+            .change_password(&change_password("Wrong-Horse-42"))
+            .await;
+        assert_eq!(problem(wrong).code, ErrorCode::Forbidden);
+    }
+
+    let locked = client.change_password(&change_password(PASSWORD)).await;
+    assert_eq!(problem(locked).code, ErrorCode::TooManyRequests);
+    let change_email = client
+        .request_email_change(&RequestEmailChangeBody {
+            new_email: "jane@acme.org".into(),
+            current_password: Some(PASSWORD.into()),
+        })
+        .await;
+    assert_eq!(problem(change_email).code, ErrorCode::TooManyRequests);
+    let delete = client
+        .delete_account(&DeleteAccountBody {
+            password: Some(PASSWORD.into()),
+            transfers: Vec::new(),
+        })
+        .await;
+    assert_eq!(problem(delete).code, ErrorCode::TooManyRequests);
+    let login = app
+        .client()
+        .authenticate_with_password(&password_login("jane@example.com", PASSWORD))
+        .await;
+    assert_eq!(problem(login).code, ErrorCode::TooManyRequests);
+}
+
+#[tokio::test]
+async fn test_passwordless_users_need_a_recent_session() {
+    let app = TestApp::spawn().await;
+    app.set_github_user(42, "octocat", "octocat@example.com");
+    let session = match app
+        .client()
+        .authenticate_with_github(&github_login(&app).await)
+        .await
+        .unwrap()
+    {
+        GithubSession::Authenticated(session) => session,
+        GithubSession::TwoFactorRequired(_) => panic!("User without 2FA was challenged"),
+    };
+    let client = app.client().with_token(session.token);
+    let user_id = client.get_profile().await.unwrap().id;
+
+    // Um token antigo, talvez roubado, não basta para tomar a conta
+    let stale = app.session_started_ago(user_id, time::Duration::hours(1));
+    let set_password = ChangePasswordBody {
+        current_password: None,
+        new_password: "Battery-Staple-43".into(),
+    };
+    let change_email = RequestEmailChangeBody {
+        new_email: "attacker@example.com".into(),
+        current_password: None,
+    };
+
+    let password = stale.change_password(&set_password).await;
+    assert_eq!(problem(password).code, ErrorCode::Forbidden);
+    let email = stale.request_email_change(&change_email).await;
+    assert_eq!(problem(email).code, ErrorCode::Forbidden);
+    assert!(app.mailbox.sent_to("attacker@example.com").is_empty());
+
+    client.change_password(&set_password).await.unwrap();
+    app.log_in("octocat@example.com", "Battery-Staple-43").await;
+}
+
+#[tokio::test]
+async fn test_recover_and_reset_password() {
+    let app = TestApp::spawn().await;
+    app.create_user("Jane Doe", "jane@example.com").await;
+    let client = app.client();
+
+    let unknown = client
+        .request_password_recover(&RequestPasswordRecoverBody {
+            email: "nobody@example.com".into(),
+        })
+        .await;
+    assert_eq!(problem(unknown).code, ErrorCode::Forbidden);
+
+    let recover = client
+        .request_password_recover(&RequestPasswordRecoverBody {
+            email: "jane@example.com".into(),
+        })
+        .await
+        .unwrap();
+    let reset = ResetPasswordRequest {
+        code: recover.code,
+        password: "Battery-Staple-43".into(),
+    };
+
+    client.reset_password(&reset).await.unwrap();
+    app.log_in("jane@example.com", "Battery-Staple-43").await;
+
+    let reused = client.reset_password(&reset).await;
+    assert_eq!(problem(reused).code, ErrorCode::Forbidden);
+}
+
+#[tokio::test]
+async fn test_change_email() {
+    let app = TestApp::spawn().await;
+    app.create_user("John Doe", "john@example.com").await;
+    let client = app.sign_up("jane@example.com").await;
+    let request = |new_email: &str, password: &str| RequestEmailChangeBody {
+        new_email: new_email.into(),
+        current_password: Some(password.into()),
+    };
+
+    let taken = client
+        .request_email_change(&request("john@example.com", PASSWORD))
+        .await;
+    assert_eq!(problem(taken).code, ErrorCode::Conflict);
+
+    let wrong = client
+        .request_email_change(&request("jane@acme.org", "Wrong-Horse-42"))
+        .await;
+    assert_eq!(problem(wrong).code, ErrorCode::Forbidden);
+
+    client
+        .request_email_change(&request("jane@acme.org", PASSWORD))
+        .await
+        .unwrap();
+    let code = uuid_in(&app.mailbox.sent_to("jane@acme.org")[0].body);
+    assert_eq!(app.mailbox.sent_to("jane@example.com").len(), 1);
+
+    let confirmed = client
+        .confirm_email_change(&ConfirmEmailChangeBody { code })
+        .await
+        .unwrap();
+
+    let client = app.client().with_token(confirmed.token);
+    assert_eq!(client.get_profile().await.unwrap().email, "jane@acme.org");
+    app.log_in("jane@acme.org", PASSWORD).await;
+}
+
+#[tokio::test]
+async fn test_two_factor_lifecycle() {
+    let app = TestApp::spawn().await;
+    let client = app.sign_up("jane@example.com").await;
+
+    let setup = client.setup_two_factor().await.unwrap();
+    let mut authenticator = AuthenticatorApp::new(&setup.otpauth_uri);
+    let wrong = client
+        .enable_two_factor(&EnableTwoFactorBody {
+            code: "000000".into(),
+        })
+        .await;
+    assert_eq!(problem(wrong).status, 400);
+
+    let enabled = client
+        .enable_two_factor(&EnableTwoFactorBody {
+            code: authenticator.next_code(),
+        })
+        .await
+        .unwrap();
+    assert_eq!(enabled.recovery_codes.len(), 10);
+
+    let code = authenticator.next_code();
+    let session = two_factor_login(&app, &code).await.unwrap();
+    assert!(app
+        .client()
+        .with_token(session.token)
+        .get_profile()
+        .await
+        .is_ok());
+
+    let recovery_code = &enabled.recovery_codes[0];
+    assert!(two_factor_login(&app, recovery_code).await.is_ok());
+    let reused = two_factor_login(&app, recovery_code).await;
+    assert_eq!(problem(reused).code, ErrorCode::Forbidden);
+
+    // Ainda dentro da janela de tolerância, mas já usado
+    let reused = client
+        .disable_two_factor(&DisableTwoFactorBody { code })
+        .await;
+    assert_eq!(problem(reused).status, 400);
+
+    client
+        .disable_two_factor(&DisableTwoFactorBody {
+            code: authenticator.next_code(),
+        })
+        .await
+        .unwrap();
+    app.log_in("jane@example.com", PASSWORD).await;
+}
+
+#[tokio::test]
+async fn test_two_factor_codes_cannot_be_brute_forced() {
+    let app = TestApp::spawn().await;
+    let client = app.sign_up("jane@example.com").await;
+
+    let setup = client.setup_two_factor().await.unwrap();
+    let mut authenticator = AuthenticatorApp::new(&setup.otpauth_uri);
+    client
+        .enable_two_factor(&EnableTwoFactorBody {
+            code: authenticator.next_code(),
+        })
+        .await
+        .unwrap();
+    // Só o primeiro envio chega a conferir o código, os outros param antes
+    let code = authenticator.next_code();
+
+    // O token vale um login só
+    let used = mfa_token(&app).await.unwrap();
+    assert!(send_code(&app, &used, &code).await.is_ok());
+    let replayed = send_code(&app, &used, &code).await;
+    assert_eq!(problem(replayed).code, ErrorCode::Unauthorized);
+
+    // E só alguns códigos
+    let exhausted = mfa_token(&app).await.unwrap();
+    for _ in 0..3 {
+        let wrong = send_code(&app, &exhausted, "000000").await;
+        assert_eq!(problem(wrong).code, ErrorCode::Forbidden);
+    }
+    let after_limit = send_code(&app, &exhausted, &code).await;
+    assert_eq!(problem(after_limit).code, ErrorCode::Unauthorized);
+
+    // A senha certa não zera as falhas, então um token novo não ajuda
+    let fresh = mfa_token(&app).await.unwrap();
+    for _ in 0..2 {
+        let wrong = send_code(&app, &fresh, "000000").await;
+        assert_eq!(problem(wrong).code, ErrorCode::Forbidden);
+    }
+    let locked = send_code(&app, &fresh, &code).await;
+    assert_eq!(problem(locked).code, ErrorCode::TooManyRequests);
+    assert_eq!(
+        problem(mfa_token(&app).await).code,
+        ErrorCode::TooManyRequests
+    );
+}
+
+#[tokio::test]
+async fn test_passkeys() {
+    let app = TestApp::spawn().await;
+    let client = app.sign_up("jane@example.com").await;
+    let mut authenticator = SoftwareAuthenticator::new();
+
+    let passkey_id = app.register_passkey(&client, &mut authenticator).await;
+
+    let passkeys = client.get_passkeys().await.unwrap().passkeys;
+    assert_eq!(passkeys.len(), 1);
+    assert_eq!(passkeys[0].id, passkey_id);
+
+    let signed_in = app.log_in_with_passkey(&mut authenticator).await;
+    assert_eq!(
+        signed_in.get_profile().await.unwrap().email,
+        "jane@example.com"
+    );
+    let used = client.get_passkeys().await.unwrap().passkeys;
+    assert!(used[0].last_used_at.is_some());
+
+    client.delete_passkey(passkey_id).await.unwrap();
+    assert!(client.get_passkeys().await.unwrap().passkeys.is_empty());
+
+    let deleted_again = client.delete_passkey(passkey_id).await;
+    assert_eq!(problem(deleted_again).code, ErrorCode::NotFound);
+}
+
+#[tokio::test]
+async fn test_passkey_assertions_cannot_be_replayed() {
+    let app = TestApp::spawn().await;
+    let client = app.sign_up("jane@example.com").await;
+    // Passkeys sincronizados costumam manter o contador em zero
+    let mut authenticator = SoftwareAuthenticator::new();
+    authenticator.counts_signatures = false;
+    app.register_passkey(&client, &mut authenticator).await;
+
+    let options = app
+        .client()
+        .get_passkey_authentication_options()
+        .await
+        .unwrap();
+    let body = AuthenticateWithPasskeyBody {
+        credential: authenticator.authenticate(RP_ID, ORIGIN, &options.public_key.challenge),
+        challenge_token: options.challenge_token,
+    };
+
+    assert!(app.client().authenticate_with_passkey(&body).await.is_ok());
+    let replayed = app.client().authenticate_with_passkey(&body).await;
+    assert_eq!(problem(replayed).code, ErrorCode::Unauthorized);
+}
+
+#[tokio::test]
+async fn test_authenticate_with_github() {
+    let app = TestApp::spawn().await;
+
+    assert!(github_authorize(&app)
+        .await
+        .contains("/login/oauth/authorize?client_id=id"));
+
+    app.set_github_user(42, "octocat", "octocat@example.com");
+    let session = match app
+        .client()
+        .authenticate_with_github(&github_login(&app).await)
+        .await
+        .unwrap()
+    {
+        GithubSession::Authenticated(session) => session,
+        GithubSession::TwoFactorRequired(_) => panic!("User without 2FA was challenged"),
+    };
+
+    let client = app.client().with_token(session.token);
+    assert_eq!(
+        client.get_profile().await.unwrap().email,
+        "octocat@example.com"
+    );
+
+    let accounts = client.get_accounts().await.unwrap();
+    assert!(!accounts.has_password);
+    assert_eq!(accounts.accounts.len(), 1);
+    assert_eq!(accounts.accounts[0].provider_account_id, "42");
+
+    let last_method = client.unlink_account(Provider::Github).await;
+    assert_eq!(problem(last_method).status, 400);
+}
+
+#[tokio::test]
+async fn test_github_login_requires_the_second_factor() {
+    let app = TestApp::spawn().await;
+    let client = app.sign_up("jane@example.com").await;
+
+    let setup = client.setup_two_factor().await.unwrap();
+    let mut authenticator = AuthenticatorApp::new(&setup.otpauth_uri);
+    client
+        .enable_two_factor(&EnableTwoFactorBody {
+            code: authenticator.next_code(),
+        })
+        .await
+        .unwrap();
+
+    // Vincula pelo e-mail verificado ao usuário existente
+    app.set_github_user(7, "jane", "jane@example.com");
+    let github = app.client();
+
+    let code = authenticator.next_code();
+    let mfa_token = match github
+        .authenticate_with_github(&github_login(&app).await)
+        .await
+        .unwrap()
+    {
+        GithubSession::TwoFactorRequired(challenge) => challenge.mfa_token,
+        GithubSession::Authenticated(_) => panic!("2FA was skipped"),
+    };
+    let session = app
+        .client()
+        .authenticate_with_two_factor(&AuthenticateWithTwoFactorBody {
+            mfa_token,
+            code: code.clone(),
+        })
+        .await
+        .unwrap();
+    assert_eq!(
+        app.client()
+            .with_token(session.token)
+            .get_profile()
+            .await
+            .unwrap()
+            .email,
+        "jane@example.com"
+    );
+
+    let mfa_token = match github
+        .authenticate_with_github(&github_login(&app).await)
+        .await
+        .unwrap()
+    {
+        GithubSession::TwoFactorRequired(challenge) => challenge.mfa_token,
+        GithubSession::Authenticated(_) => panic!("2FA was skipped"),
+    };
+    // O mesmo código ainda está na janela de tolerância, mas já foi usado
+    let replayed = send_code(&app, &mfa_token, &code).await;
+    assert_eq!(problem(replayed).code, ErrorCode::Forbidden);
+}
+
+#[tokio::test]
+async fn test_link_and_unlink_account() {
+    let app = TestApp::spawn().await;
+    let client = app.sign_up("jane@example.com").await;
+    app.set_github_user(7, "jane", "jane@example.com");
+    let link = github_link(&client).await;
+
+    client.link_account(Provider::Github, &link).await.unwrap();
+
+    let accounts = client.get_accounts().await.unwrap();
+    assert!(accounts.has_password);
+    assert_eq!(accounts.accounts[0].provider_account_id, "7");
+
+    let other = app.sign_up("joe@example.com").await;
+    let taken = other
+        .link_account(Provider::Github, &github_link(&other).await)
+        .await;
+    assert_eq!(problem(taken).code, ErrorCode::Conflict);
+
+    client.unlink_account(Provider::Github).await.unwrap();
+    assert!(client.get_accounts().await.unwrap().accounts.is_empty());
+
+    let unlinked_again = client.unlink_account(Provider::Github).await;
+    assert_eq!(problem(unlinked_again).code, ErrorCode::NotFound);
+}
+
+#[tokio::test]
+async fn test_github_codes_need_a_valid_state() {
+    let app = TestApp::spawn().await;
+    app.set_github_user(7, "jane", "jane@example.com");
+    let github = app.client();
+
+    let forged = AuthenticateWithGithubBody {
+        code: "code".into(),
+        state: "forged".into(),
+    };
+    let login = github.authenticate_with_github(&forged).await;
+    assert_eq!(problem(login).code, ErrorCode::Forbidden);
+
+    let body = github_login(&app).await;
+    assert!(github.authenticate_with_github(&body).await.is_ok());
+    let replayed = github.authenticate_with_github(&body).await;
+    assert_eq!(problem(replayed).code, ErrorCode::Forbidden);
+
+    // Um state de login não serve para vincular, nem o de outro usuário
+    let client = app.sign_up("joe@example.com").await;
+    let login_state = LinkAccountBody {
+        code: "code".into(),
+        state: github_login(&app).await.state,
+    };
+    let linked = client.link_account(Provider::Github, &login_state).await;
+    assert_eq!(problem(linked).code, ErrorCode::Forbidden);
+
+    let victim = app.sign_up("ann@example.com").await;
+    let planted = victim
+        .link_account(Provider::Github, &github_link(&client).await)
+        .await;
+    assert_eq!(problem(planted).code, ErrorCode::Forbidden);
+    assert!(victim.get_accounts().await.unwrap().accounts.is_empty());
+}
+
+#[tokio::test]
+async fn test_data_export_downloads_once() {
+    let app = TestApp::spawn().await;
+    let client = app.sign_up("jane@example.com").await;
+
+    client.request_data_export().await.unwrap();
+    let email = app
+        .mailbox
+        .wait_for("jane@example.com", "Your data export is ready")
+        .await;
+    let code = uuid_in(&email.body).to_string();
+
+    // Um export por vez, até o link ser usado ou expirar
+    let again = client.request_data_export().await;
+    assert_eq!(problem(again).code, ErrorCode::Conflict);
+
+    let archive = client.download_data_export(&code).await.unwrap();
+    assert!(archive.starts_with(b"PK"));
+
+    let downloaded_again = client.download_data_export(&code).await;
+    assert_eq!(problem(downloaded_again).code, ErrorCode::NotFound);
+    assert!(client.request_data_export().await.is_ok());
+}
+
+#[tokio::test]
+async fn test_delete_account() {
+    let app = TestApp::spawn().await;
+    let client = app.sign_up("jane@example.com").await;
+    let body = |password: &str| DeleteAccountBody {
+        password: Some(password.into()),
+        transfers: Vec::new(),
+    };
+
+    let wrong = client.delete_account(&body("Wrong-Horse-42")).await;
+    assert_eq!(problem(wrong).code, ErrorCode::Forbidden);
+
+    client.delete_account(&body(PASSWORD)).await.unwrap();
+    assert_eq!(
+        problem(client.get_profile().await).code,
+        ErrorCode::Unauthorized
+    );
+
+    // Dono de organizações precisa transferi-las antes
+    let owner = app.log_in(SEEDED_EMAIL, SEEDED_PASSWORD).await;
+    let owns = problem(owner.delete_account(&body(SEEDED_PASSWORD)).await);
+    assert_eq!(owns.code, ErrorCode::Conflict);
+    assert!(!owns.details.is_empty());
+}
@@ -0,0 +1,44 @@
+mod common;
+
+use common::TestApp;
+use sea_orm::ConnectionTrait;
+use serde_json::{json, Value};
+
+async fn get(app: &TestApp, path: &str) -> (u16, Value) {
+    let response = reqwest::get(app.base_url.join(path).unwrap())
+        .await
+        .unwrap();
+    let status = response.status().as_u16();
+
+    (status, response.json().await.unwrap())
+}
+
+#[tokio::test]
+async fn test_ready_once_every_migration_is_applied() {
+    let app = TestApp::spawn().await;
+
+    assert_eq!(
+        get(&app, "health/live").await,
+        (200, json!({ "status": "ok" }))
+    );
+    assert_eq!(
+        get(&app, "health/ready").await,
+        (
+            200,
+            json!({ "status": "ok", "checks": { "database": "ok", "migrations": "ok" } })
+        )
+    );
+
+    // Como se o deploy tivesse chegado antes da última migração
+    app.db
+        .execute_unprepared(
+            "DELETE FROM seaql_migrations WHERE version = \
+             (SELECT MAX(version) FROM seaql_migrations)",
+        )
+        .await
+        .unwrap();
+
+    let (status, body) = get(&app, "health/ready").await;
+    assert_eq!(status, 503);
+    assert_eq!(body["checks"]["migrations"], "pending");
+}
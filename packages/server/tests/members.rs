@@ -0,0 +1,37 @@
+mod common;
+
+use api_types::{error::ErrorCode, members::Role};
+use common::{problem, TestApp, SEEDED_EMAIL, SEEDED_PASSWORD};
+
+#[tokio::test]
+async fn test_admin_lists_members() {
+    let app = TestApp::spawn().await;
+    // Entra na acme-admin pelo domínio do e-mail
+    let joined = app.create_user("Jane Doe", "jane@acme.com").await;
+    let admin = app.log_in(SEEDED_EMAIL, SEEDED_PASSWORD).await;
+
+    let members = admin.get_members("acme-admin").await.unwrap().members;
+
+    assert_eq!(members.len(), 4);
+    let john = members.iter().find(|m| m.email == SEEDED_EMAIL).unwrap();
+    assert_eq!(john.role, Role::Admin);
+    let jane = members.iter().find(|m| m.user_id == joined).unwrap();
+    assert_eq!(jane.role, Role::Member);
+}
+
+#[tokio::test]
+async fn test_members_and_outsiders_cannot_list_members() {
+    let app = TestApp::spawn().await;
+
+    let member = app.sign_up("jane@acme.com").await;
+    let forbidden = problem(member.get_members("acme-admin").await);
+    assert_eq!(forbidden.code, ErrorCode::Forbidden);
+
+    let outsider = app.sign_up("joe@example.com").await;
+    let not_member = problem(outsider.get_members("acme-admin").await);
+    assert_eq!(not_member.code, ErrorCode::Forbidden);
+
+    let forged = app.client().with_token("not-a-jwt");
+    let unauthorized = problem(forged.get_members("acme-admin").await);
+    assert_eq!(unauthorized.code, ErrorCode::Unauthorized);
+}
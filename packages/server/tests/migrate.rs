@@ -0,0 +1,90 @@
+mod common;
+
+use common::{TestApp, SEEDED_EMAIL};
+use entities::users;
+use migration::LEGACY_SEED_VERSION;
+use sea_orm::{
+    ColumnTrait, ConnectionTrait, DatabaseBackend, EntityTrait, PaginatorTrait, QueryFilter,
+    QuerySelect,
+};
+use server::db::{self, Migrate};
+
+/// Counts users by id only, since the rolled back migration may have dropped
+/// a column of the entity.
+async fn user_count(app: &TestApp) -> u64 {
+    users::Entity::find()
+        .select_only()
+        .column(users::Column::Id)
+        .count(&app.db)
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_concurrent_migrations_apply_once() {
+    let app = TestApp::spawn().await;
+    assert!(db::pending_migrations(&app.db).await.unwrap().is_empty());
+    let users_before = user_count(&app).await;
+
+    db::migrate(&app.db, Migrate::Down { steps: 1 })
+        .await
+        .unwrap();
+
+    let status = db::migration_status(&app.db).await.unwrap();
+    let (latest, applied) = status.last().unwrap();
+    assert!(!applied);
+    assert_ne!(latest, LEGACY_SEED_VERSION);
+    assert_eq!(
+        db::pending_migrations(&app.db).await.unwrap(),
+        [latest.as_str()]
+    );
+    // Só o esquema volta, os dados ficam
+    assert_eq!(user_count(&app).await, users_before);
+
+    if app.db.get_database_backend() == DatabaseBackend::Sqlite {
+        // Sem advisory lock, SQLite é para uma instância só
+        db::migrate(&app.db, Migrate::Up).await.unwrap();
+    } else {
+        // Duas instâncias subindo com --migrate ao mesmo tempo
+        let (first, second) = tokio::join!(
+            db::migrate(&app.db, Migrate::Up),
+            db::migrate(&app.db, Migrate::Up)
+        );
+        first.unwrap();
+        second.unwrap();
+    }
+
+    assert!(db::pending_migrations(&app.db).await.unwrap().is_empty());
+    assert_eq!(user_count(&app).await, users_before);
+}
+
+#[tokio::test]
+async fn test_seed_replaces_the_data_and_is_not_a_migration() {
+    let app = TestApp::spawn().await;
+    app.create_user("Jane Doe", "jane@example.com").await;
+
+    // Bancos migrados quando o seed ainda fazia parte do Migrator
+    app.db
+        .execute_unprepared(
+            "INSERT INTO seaql_migrations (version, applied_at) \
+             VALUES ('m20251229_052055_seed_data', 0)",
+        )
+        .await
+        .unwrap();
+    db::migrate(&app.db, Migrate::Up).await.unwrap();
+
+    db::seed(&app.db).await.unwrap();
+
+    let seeded = users::Entity::find()
+        .filter(users::Column::Email.eq(SEEDED_EMAIL))
+        .count(&app.db)
+        .await
+        .unwrap();
+    let jane = users::Entity::find()
+        .filter(users::Column::Email.eq("jane@example.com"))
+        .count(&app.db)
+        .await
+        .unwrap();
+    assert_eq!((seeded, jane), (1, 0));
+    assert!(db::pending_migrations(&app.db).await.unwrap().is_empty());
+}